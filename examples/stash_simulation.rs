@@ -0,0 +1,49 @@
+//! Measures the stash size of Path ORAM for several bucket sizes and numbers
+//! of items, to compare the empirical overflow probability with the bound
+//! `14 * 0.6002^R`.
+//!
+//! Usage: `stash_simulation [csv|json] [nb_accesses] [seed]`
+
+use cosmian_crypto_core::{reexport::rand_core::SeedableRng, CsRng};
+use path_oram::simulation::simulate;
+use std::{env, process};
+
+const BUCKET_SIZES: [usize; 4] = [3, 4, 5, 6];
+const NB_ITEMS: [usize; 3] = [1 << 10, 1 << 14, 1 << 16];
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let format = args.get(1).map(String::as_str).unwrap_or("csv");
+    let nb_accesses = args
+        .get(2)
+        .map(|arg| arg.parse().expect("Invalid number of accesses"))
+        .unwrap_or(1_000_000);
+    let seed = args
+        .get(3)
+        .map(|arg| arg.parse().expect("Invalid seed"))
+        .unwrap_or(0);
+
+    if format != "csv" && format != "json" {
+        eprintln!("Unknown format {}, expected csv or json", format);
+        process::exit(1);
+    }
+
+    let mut rng = CsRng::seed_from_u64(seed);
+    let mut results = Vec::new();
+    for bucket_size in BUCKET_SIZES {
+        for nb_items in NB_ITEMS {
+            let stats = simulate(bucket_size, nb_items, nb_accesses, &mut rng)
+                .expect("Invalid simulation parameters");
+
+            results.push(match format {
+                "csv" => stats.to_csv(results.is_empty()),
+                _ => stats.to_json(),
+            });
+        }
+    }
+
+    match format {
+        "csv" => print!("{}", results.concat()),
+        _ => println!("[{}]", results.join(",")),
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{
+    btree::DataItem,
+    cipher::OramCipher,
+    client::{path_buckets, ClientOram},
+    oram::{AccessType, Oram, BUCKET_SIZE},
+};
+use cosmian_crypto_core::Aes256Gcm;
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::{Error, ErrorKind},
+    mem,
+};
+use tokio::sync::Mutex;
+
+/// Asynchronous transport of the buckets of a server tree, such as a network
+/// connection. Buckets are indexed as a binary heap: the root is 1 and the
+/// children of bucket `i` are `2i` and `2i + 1`.
+///
+/// Requests only borrow the storage, so that a write can still be in flight
+/// when the next read is sent. They may then be served in any order.
+pub trait AsyncStorage {
+    /// Reads the buckets `indices`, in the given order.
+    fn read_buckets(
+        &self,
+        indices: Vec<usize>,
+    ) -> impl Future<Output = Result<Vec<[DataItem; BUCKET_SIZE]>, Error>> + Send;
+
+    /// Overwrites each bucket of `buckets` at its index.
+    fn write_buckets(
+        &self,
+        buckets: Vec<(usize, [DataItem; BUCKET_SIZE])>,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// Server tree shared within a process, such as by tests or by a server
+/// forwarding the requests of remote clients.
+impl AsyncStorage for Mutex<Oram> {
+    async fn read_buckets(
+        &self,
+        indices: Vec<usize>,
+    ) -> Result<Vec<[DataItem; BUCKET_SIZE]>, Error> {
+        self.lock().await.read_buckets(&indices)
+    }
+
+    async fn write_buckets(
+        &self,
+        buckets: Vec<(usize, [DataItem; BUCKET_SIZE])>,
+    ) -> Result<(), Error> {
+        self.lock().await.write_buckets(buckets)
+    }
+}
+
+/// Client performing the accesses of `ClientOram` through an `AsyncStorage`,
+/// so that waiting for the server does not block the executor. Encryption
+/// and eviction are still performed by the wrapped client.
+pub struct AsyncClientOram<S: AsyncStorage, C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    storage: S,
+}
+
+impl<S: AsyncStorage, C: OramCipher> AsyncClientOram<S, C> {
+    /// Accesses the tree of `storage` with `client`, which must have set it
+    /// up. Cached top levels of the tree are kept by the client.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the stash of `client` is
+    /// outsourced, the storage transferring buckets only.
+    pub fn new(
+        client: ClientOram<C>,
+        storage: S,
+    ) -> Result<AsyncClientOram<S, C>, Error> {
+        if client.outsourced_stash().is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid client. Got an outsourced stash, expected a local one"
+                    .to_string(),
+            ));
+        }
+
+        Ok(AsyncClientOram { client, storage })
+    }
+
+    /// Path ORAM access to the block `block_id`, as `ClientOram::access`.
+    pub async fn access(
+        &mut self,
+        op: AccessType,
+        block_id: u64,
+        data: Option<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let mut results =
+            self.access_pipelined(vec![(op, block_id, data)]).await?;
+
+        Ok(results.pop().flatten())
+    }
+
+    /// Performs `requests` one after the other, as many calls to `access`
+    /// would, and returns their results in the same order. The read of each
+    /// path is sent along with the write back of the previous one instead of
+    /// after it, saving a round trip per request.
+    ///
+    /// The server is requested the same paths in the same order as by
+    /// sequential accesses, only with overlapping writes and reads. Buckets
+    /// shared by two consecutive paths, at least the root, may thus be read
+    /// before being written back: the client uses the copy it is writing
+    /// instead. The same block may be accessed several times, as its leaf is
+    /// known once the previous access is evicted.
+    ///
+    /// Requests are validated before any access, failing with
+    /// `ErrorKind::InvalidInput`. Storage errors leave the tree out of sync
+    /// with the client, which must then be restored from a checkpoint.
+    pub async fn access_pipelined(
+        &mut self,
+        requests: Vec<(AccessType, u64, Option<Vec<u8>>)>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        let mut updates = Vec::with_capacity(requests.len());
+        for (op, block_id, data) in requests {
+            updates
+                .push((block_id, self.client.new_payload(op, block_id, data)?));
+        }
+
+        let height = self.client.params().height();
+        let nb_cached = self.client.tree_top_levels() as usize;
+
+        let mut results = Vec::with_capacity(updates.len());
+        let mut pending = Vec::new();
+        for (block_id, new_payload) in updates {
+            let path = match self.client.position_map.get(&block_id) {
+                Some(&leaf) => leaf,
+                None => self.client.random_leaf(),
+            };
+            let indices = path_buckets(height, path).split_off(nb_cached);
+
+            // Buckets of the previous path being written which are read again.
+            let mut shared: HashMap<usize, [DataItem; BUCKET_SIZE]> = pending
+                .iter()
+                .filter(|(index, _)| indices.contains(index))
+                .cloned()
+                .collect();
+
+            let read = self.storage.read_buckets(indices.clone());
+            let buckets = if pending.is_empty() {
+                read.await?
+            } else {
+                let (written, read) = tokio::join!(
+                    self.storage.write_buckets(mem::take(&mut pending)),
+                    read
+                );
+                written?;
+                read?
+            };
+
+            if buckets.len() != indices.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid number of buckets read. Got {}, expected {}",
+                        buckets.len(),
+                        indices.len()
+                    ),
+                ));
+            }
+
+            let read_data = indices
+                .iter()
+                .zip(buckets)
+                .flat_map(|(index, bucket)| {
+                    shared.remove(index).unwrap_or(bucket)
+                })
+                .collect();
+
+            let mut read_data =
+                self.client.load_path(read_data, Vec::new(), path, height)?;
+            results.push(self.client.update_block(
+                &mut read_data,
+                block_id,
+                new_payload,
+            )?);
+
+            // The stash being local, no padded stash is returned.
+            (pending, _) =
+                self.client.evict_path(&mut read_data, path, height)?;
+        }
+
+        if !pending.is_empty() {
+            self.storage.write_buckets(pending).await?;
+        }
+
+        Ok(results)
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn into_parts(self) -> (ClientOram<C>, S) {
+        (self.client, self.storage)
+    }
+}
@@ -0,0 +1,224 @@
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use std::io::{Error, ErrorKind};
+    use tokio::sync::Mutex;
+
+    use crate::{
+        async_client::{AsyncClientOram, AsyncStorage},
+        btree::DataItem,
+        client::ClientOram,
+        oram::{AccessType, Oram, BUCKET_SIZE},
+        trace::{MemoryTrace, TraceEvent, TraceOp},
+    };
+
+    /// Storage whose writes yield before being served, so that the read
+    /// sent along is served first and finds stale buckets.
+    struct LateWrites {
+        oram: Mutex<Oram>,
+    }
+
+    impl AsyncStorage for LateWrites {
+        async fn read_buckets(
+            &self,
+            indices: Vec<usize>,
+        ) -> Result<Vec<[DataItem; BUCKET_SIZE]>, Error> {
+            self.oram.lock().await.read_buckets(&indices)
+        }
+
+        async fn write_buckets(
+            &self,
+            buckets: Vec<(usize, [DataItem; BUCKET_SIZE])>,
+        ) -> Result<(), Error> {
+            tokio::task::yield_now().await;
+
+            self.oram.lock().await.write_buckets(buckets)
+        }
+    }
+
+    fn _write_requests(
+        nb_blocks: u64,
+        round: u8,
+    ) -> Vec<(AccessType, u64, Option<Vec<u8>>)> {
+        (0..nb_blocks)
+            .map(|i| (AccessType::Write, i, Some(vec![i as u8 ^ round; 16])))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn async_access() {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let mut async_client =
+            AsyncClientOram::new(client, Mutex::new(path_oram)).unwrap();
+
+        for (op, block_id, data) in _write_requests(40, 0) {
+            let res_access = async_client.access(op, block_id, data).await;
+            assert_eq!(res_access.unwrap(), None);
+        }
+
+        for block_id in 0..40u64 {
+            let value = async_client
+                .access(AccessType::Read, block_id, None)
+                .await
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8; 16]));
+        }
+
+        let value = async_client
+            .access(AccessType::Read, 1000, None)
+            .await
+            .unwrap();
+        assert_eq!(value, None);
+        assert_eq!(async_client.client().nb_accesses(), 81);
+
+        // The tree can still be accessed synchronously.
+        let (mut client, path_oram) = async_client.into_parts();
+        let mut path_oram = path_oram.into_inner();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+        let value = client
+            .access(&mut path_oram, AccessType::Read, 7, None)
+            .unwrap();
+        assert_eq!(value, Some(vec![7; 16]));
+    }
+
+    #[tokio::test]
+    async fn async_access_pipelined() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        let storage = LateWrites {
+            oram: Mutex::new(path_oram),
+        };
+        let mut async_client = AsyncClientOram::new(client, storage).unwrap();
+
+        let results = async_client
+            .access_pipelined(_write_requests(40, 0))
+            .await
+            .unwrap();
+        assert_eq!(results, vec![None; 40]);
+
+        // The same blocks several times in a row.
+        let mut requests = _write_requests(40, 1);
+        requests.extend(_write_requests(40, 2));
+        requests.push((AccessType::Read, 3, None));
+        requests.push((AccessType::Read, 3, None));
+        let results = async_client.access_pipelined(requests).await.unwrap();
+        for (i, result) in results.iter().take(80).enumerate() {
+            let round = (i / 40) as u8;
+            assert_eq!(result, &Some(vec![(i % 40) as u8 ^ round; 16]));
+        }
+        assert_eq!(results[80], Some(vec![3 ^ 2; 16]));
+        assert_eq!(results[81], Some(vec![3 ^ 2; 16]));
+
+        // Each read was served before the write back of the previous path.
+        let events = trace.take();
+        assert_eq!(events.len(), 2 * 122);
+        assert_eq!(events[0].op, TraceOp::ReadBuckets);
+        assert_eq!(events[1].op, TraceOp::ReadBuckets);
+        assert_eq!(events[2].op, TraceOp::WriteBuckets);
+
+        let (mut client, storage) = async_client.into_parts();
+        let mut path_oram = storage.oram.into_inner();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        for block_id in 0..40u64 {
+            let value = client
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8 ^ 2; 16]));
+        }
+    }
+
+    #[tokio::test]
+    async fn async_same_paths_as_sequential() {
+        let requests = || {
+            let mut requests = _write_requests(40, 0);
+            requests.extend((0..40).map(|i| (AccessType::Read, i % 7, None)));
+            requests
+        };
+
+        let mut client: ClientOram =
+            ClientOram::with_seed(64, [3; 32]).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let sequential_trace = MemoryTrace::new();
+        path_oram.set_trace_sink(sequential_trace.clone());
+        for (op, block_id, data) in requests() {
+            client.access(&mut path_oram, op, block_id, data).unwrap();
+        }
+
+        let mut client: ClientOram =
+            ClientOram::with_seed(64, [3; 32]).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let pipelined_trace = MemoryTrace::new();
+        path_oram.set_trace_sink(pipelined_trace.clone());
+        let mut async_client =
+            AsyncClientOram::new(client, Mutex::new(path_oram)).unwrap();
+        async_client.access_pipelined(requests()).await.unwrap();
+
+        // The server is requested the same buckets in the same order.
+        let buckets = |events: Vec<_>, op| {
+            events
+                .into_iter()
+                .filter(|event: &TraceEvent| event.op == op)
+                .map(|event| event.buckets)
+                .collect::<Vec<_>>()
+        };
+        let sequential_reads =
+            buckets(sequential_trace.events(), TraceOp::ReadPath);
+        let pipelined_reads =
+            buckets(pipelined_trace.events(), TraceOp::ReadBuckets);
+        assert_eq!(sequential_reads.len(), 80);
+        assert_eq!(sequential_reads, pipelined_reads);
+        assert_eq!(
+            buckets(sequential_trace.events(), TraceOp::WritePath),
+            buckets(pipelined_trace.events(), TraceOp::WriteBuckets)
+        );
+    }
+
+    #[tokio::test]
+    async fn async_invalid_requests() {
+        let mut client = ClientOram::new(64).unwrap();
+        client.outsource_stash(64);
+        let path_oram = client.setup_oram(16).unwrap();
+        let res_client = AsyncClientOram::new(client, Mutex::new(path_oram));
+        assert_eq!(res_client.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+        let mut async_client =
+            AsyncClientOram::new(client, Mutex::new(path_oram)).unwrap();
+
+        // Requests are validated before any access.
+        let requests = vec![
+            (AccessType::Write, 1, Some(vec![1; 16])),
+            (AccessType::Write, 2, None),
+        ];
+        let res_access = async_client.access_pipelined(requests).await;
+        assert_eq!(res_access.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let res_access = async_client
+            .access(AccessType::Write, 1, Some(vec![1; 17]))
+            .await;
+        assert_eq!(res_access.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(trace.is_empty());
+        assert_eq!(async_client.client().nb_accesses(), 0);
+    }
+
+    #[test]
+    fn async_client_is_send() {
+        fn _assert_send<T: Send>(_: &T) {}
+
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let mut async_client =
+            AsyncClientOram::new(client, Mutex::new(path_oram)).unwrap();
+
+        // Accesses can be spawned on a multi-threaded executor.
+        let access = async_client.access(AccessType::Read, 1, None);
+        _assert_send(&access);
+    }
+}
@@ -0,0 +1,118 @@
+/*
+ * Trusted proxy serving a key-value API to local applications over a Unix
+ * socket, on top of an ORAM whose client state and key it owns.
+ *
+ *   ORAM_PROXY_KEY=<64 hex digits> oram_proxy <socket> <state directory> \
+ *       [<nb items> <block size>]
+ *
+ * The client and the tree are loaded from the state directory if it holds
+ * them, otherwise a new ORAM is set up with the given capacity and block
+ * size. The proxy runs until its standard input is closed. Both are saved
+ * together to a single file of the state directory, atomically replaced,
+ * every `SAVE_ROUNDS` rounds and once the proxy stops: a crash loses at most
+ * the writes of the last rounds, and never leaves the client out of sync
+ * with the tree.
+ *
+ * The tree is hosted by the proxy itself, standing for the untrusted server:
+ * it only ever holds what the server would, that is encrypted buckets.
+ */
+
+#[cfg(unix)]
+fn main() -> Result<(), std::io::Error> {
+    use cosmian_crypto_core::Aes256Gcm;
+    use path_oram::{
+        checkpoint::{load_state, save_state},
+        client::ClientOram,
+        concurrent::ConcurrentOram,
+        proxy::Proxy,
+    };
+    use std::{
+        env,
+        io::{self, Error, ErrorKind, Read},
+        path::PathBuf,
+        time::Duration,
+    };
+
+    // Accesses performed every period, whatever the load.
+    const NB_ACCESSES: usize = 4;
+    const PERIOD: Duration = Duration::from_millis(10);
+    // Rounds between two saves of the state.
+    const SAVE_ROUNDS: usize = 100;
+
+    let usage = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "Usage: ORAM_PROXY_KEY=<64 hex digits> oram_proxy <socket> <state \
+             directory> [<nb items> <block size>]"
+                .to_string(),
+        )
+    };
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (socket_path, state_dir) = match args.as_slice() {
+        [socket_path, state_dir, ..] => {
+            (PathBuf::from(socket_path), PathBuf::from(state_dir))
+        }
+        _ => return Err(usage()),
+    };
+
+    let key_hex = env::var("ORAM_PROXY_KEY").map_err(|_| usage())?;
+    if key_hex.len() != 64 || !key_hex.is_ascii() {
+        return Err(usage());
+    }
+    let key = (0..key_hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&key_hex[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| usage())?;
+
+    let state_path = state_dir.join("state");
+    let (client, oram) = if state_path.exists() {
+        load_state::<Aes256Gcm>(&state_path, &key)?
+    } else {
+        let (nb_items, block_size) = match &args[2..] {
+            [nb_items, block_size] => (
+                nb_items.parse().map_err(|_| usage())?,
+                block_size.parse().map_err(|_| usage())?,
+            ),
+            _ => return Err(usage()),
+        };
+        let mut client = ClientOram::with_key(nb_items, &key)?;
+        let oram = client.setup_oram(block_size)?;
+        (client, oram)
+    };
+
+    let (front_end, worker) = {
+        let state_path = state_path.clone();
+        ConcurrentOram::spawn_with_saves(
+            client,
+            oram,
+            NB_ACCESSES,
+            PERIOD,
+            SAVE_ROUNDS,
+            move |client, oram| save_state(client, oram, &state_path),
+        )?
+    };
+    let proxy = Proxy::start(&socket_path, front_end)?;
+    eprintln!("Serving on {:?}, close stdin to stop", socket_path);
+
+    io::stdin().read_to_end(&mut Vec::new())?;
+    proxy.stop()?;
+
+    let (mut client, mut oram, res_run) = worker
+        .join()
+        .map_err(|_| Error::other("The ORAM worker panicked".to_string()))?;
+
+    // Even if the worker failed, only the blocks of the failed access may be
+    // lost, rather than the writes since the last save.
+    save_state(&mut client, &mut oram, &state_path)?;
+    eprintln!("State saved to {:?}", state_path);
+
+    res_run
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("The ORAM proxy listens on a Unix socket");
+    std::process::exit(1);
+}
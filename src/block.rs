@@ -0,0 +1,194 @@
+use crate::btree::DataItem;
+use std::io::{Error, ErrorKind};
+
+/// Version of the plaintext block layout, stored in the first byte of each
+/// block.
+pub const BLOCK_FORMAT_VERSION: u8 = 1;
+
+/// Plaintext block layout, all integers being little-endian:
+///
+/// | version | flags | block id | leaf    | payload length | payload | padding |
+/// |---------|-------|----------|---------|----------------|---------|---------|
+/// | 1 byte  | 1 byte| 8 bytes  | 8 bytes | 4 bytes        |         |         |
+///
+/// Every block of an ORAM is padded to the same size before encryption so that
+/// real blocks and dummies are indistinguishable once encrypted.
+pub const BLOCK_HEADER_LENGTH: usize = 22;
+
+/// Set for real blocks, unset for dummies.
+const FLAG_REAL: u8 = 1;
+
+const VERSION_OFFSET: usize = 0;
+const FLAGS_OFFSET: usize = 1;
+const ID_OFFSET: usize = 2;
+const LEAF_OFFSET: usize = 10;
+const LENGTH_OFFSET: usize = 18;
+
+/// Real block of data stored in the ORAM, identified by `id` and assigned to
+/// the path leading to `leaf`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    id: u64,
+    leaf: usize,
+    payload: Vec<u8>,
+}
+
+impl Block {
+    pub fn new(id: u64, leaf: usize, payload: Vec<u8>) -> Block {
+        Block { id, leaf, payload }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn leaf(&self) -> usize {
+        self.leaf
+    }
+
+    pub fn set_leaf(&mut self, leaf: usize) {
+        self.leaf = leaf;
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
+    }
+
+    /// Encodes the block in a plaintext of `BLOCK_HEADER_LENGTH + block_size`
+    /// bytes, `block_size` being the maximum payload length.
+    pub fn encode(&self, block_size: usize) -> Result<DataItem, Error> {
+        if self.payload.len() > block_size
+            || self.payload.len() > u32::MAX as usize
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Payload too large. Got {} bytes, block size is {}",
+                    self.payload.len(),
+                    block_size
+                ),
+            ));
+        }
+
+        let mut data = vec![0; BLOCK_HEADER_LENGTH + block_size];
+        data[VERSION_OFFSET] = BLOCK_FORMAT_VERSION;
+        data[FLAGS_OFFSET] = FLAG_REAL;
+        data[ID_OFFSET..LEAF_OFFSET].copy_from_slice(&self.id.to_le_bytes());
+        data[LEAF_OFFSET..LENGTH_OFFSET]
+            .copy_from_slice(&(self.leaf as u64).to_le_bytes());
+        data[LENGTH_OFFSET..BLOCK_HEADER_LENGTH]
+            .copy_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        data[BLOCK_HEADER_LENGTH..BLOCK_HEADER_LENGTH + self.payload.len()]
+            .copy_from_slice(&self.payload);
+
+        Ok(DataItem::new(data))
+    }
+
+    /// Encodes a dummy block of the same size as real blocks of `block_size`
+    /// bytes of payload.
+    pub fn encode_dummy(block_size: usize) -> DataItem {
+        let mut data = vec![0; BLOCK_HEADER_LENGTH + block_size];
+        data[VERSION_OFFSET] = BLOCK_FORMAT_VERSION;
+
+        DataItem::new(data)
+    }
+
+    /// Decodes a plaintext item. Returns `None` for dummies, including
+    /// uninitialized slots of the tree.
+    pub fn decode(item: &DataItem) -> Result<Option<Block>, Error> {
+        let header = match BlockHeader::decode(item)? {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+
+        Ok(Some(Block {
+            id: header.id,
+            leaf: header.leaf,
+            payload: item.data()
+                [BLOCK_HEADER_LENGTH..BLOCK_HEADER_LENGTH + header.length]
+                .to_vec(),
+        }))
+    }
+}
+
+/// Header of a real block, which can be read without copying the payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub id: u64,
+    pub leaf: usize,
+    pub length: usize,
+}
+
+impl BlockHeader {
+    /// Decodes the header of a plaintext item. Returns `None` for dummies,
+    /// including uninitialized slots of the tree.
+    pub fn decode(item: &DataItem) -> Result<Option<BlockHeader>, Error> {
+        let data = item.data();
+
+        // Edge-case where dummies left cells uninitialized.
+        if data.is_empty() {
+            return Ok(None);
+        }
+
+        if data.len() < BLOCK_HEADER_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid block. Got {} bytes, expected at least {}",
+                    data.len(),
+                    BLOCK_HEADER_LENGTH
+                ),
+            ));
+        }
+
+        if data[VERSION_OFFSET] != BLOCK_FORMAT_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid block format version. Got {}, expected {}",
+                    data[VERSION_OFFSET], BLOCK_FORMAT_VERSION
+                ),
+            ));
+        }
+
+        if data[FLAGS_OFFSET] & FLAG_REAL == 0 {
+            return Ok(None);
+        }
+
+        let length =
+            u32::from_le_bytes(read_array(data, LENGTH_OFFSET)) as usize;
+        if length > data.len() - BLOCK_HEADER_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid block payload length. Got {}, at most {} bytes \
+                     are available",
+                    length,
+                    data.len() - BLOCK_HEADER_LENGTH
+                ),
+            ));
+        }
+
+        Ok(Some(BlockHeader {
+            id: u64::from_le_bytes(read_array(data, ID_OFFSET)),
+            leaf: u64::from_le_bytes(read_array(data, LEAF_OFFSET)) as usize,
+            length,
+        }))
+    }
+
+    /// Overwrites the leaf of an encoded real block without decoding it.
+    pub fn set_leaf(item: &mut DataItem, leaf: usize) {
+        item.data_as_mut()[LEAF_OFFSET..LENGTH_OFFSET]
+            .copy_from_slice(&(leaf as u64).to_le_bytes());
+    }
+}
+
+fn read_array<const LENGTH: usize>(data: &[u8], offset: usize) -> [u8; LENGTH] {
+    let mut array = [0; LENGTH];
+    array.copy_from_slice(&data[offset..offset + LENGTH]);
+    array
+}
@@ -0,0 +1,824 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    map::{deserialize, serialize},
+    oram::Oram,
+};
+use cosmian_crypto_core::{
+    bytes_ser_de::{to_leb128_len, Deserializer, Serializable, Serializer},
+    Aes256Gcm, CryptoCoreError,
+};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    mem,
+    ops::{Bound, RangeBounds},
+    vec,
+};
+
+/// Number of ORAM accesses performed per level of the tree by every
+/// operation: the node on the search path and one of its siblings.
+pub const ACCESSES_PER_LEVEL: usize = 2;
+
+const LEAF_TAG: u64 = 0;
+const INTERNAL_TAG: u64 = 1;
+
+/// Serialized length of a pointer in the worst case, two LEB128 `u64`.
+const POINTER_LENGTH: usize = 20;
+
+/// Location of a node in the ORAM. The leaf of a node is stored in its parent
+/// instead of the position map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pointer {
+    id: u64,
+    leaf: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Node<K, V> {
+    Internal {
+        keys: Vec<K>,
+        children: Vec<Pointer>,
+    },
+    Leaf {
+        entries: Vec<(K, V)>,
+    },
+}
+
+/// Entries of a leaf and the separator following it, if any.
+type LeafEntries<K, V> = (Vec<(K, V)>, Option<K>);
+
+/// Node fetched from the ORAM and already remapped to a new leaf.
+struct Slot<K, V> {
+    id: u64,
+    leaf: usize,
+    node: Node<K, V>,
+}
+
+impl<K, V> Slot<K, V> {
+    fn pointer(&self) -> Pointer {
+        Pointer {
+            id: self.id,
+            leaf: self.leaf,
+        }
+    }
+}
+
+/// Index of the child to follow to find `key`. Keys greater or equal to a
+/// separator are on its right.
+fn route<K: Ord>(keys: &[K], key: Bound<&K>) -> usize {
+    match key {
+        Bound::Included(key) | Bound::Excluded(key) => {
+            keys.partition_point(|k| k <= key)
+        }
+        Bound::Unbounded => 0,
+    }
+}
+
+impl<K, V> Node<K, V>
+where
+    K: Ord + Clone + Serializable,
+    V: Clone + Serializable,
+    K::Error: Display,
+    V::Error: Display,
+{
+    fn len(&self) -> usize {
+        match self {
+            Node::Internal { keys, .. } => keys.len(),
+            Node::Leaf { entries } => entries.len(),
+        }
+    }
+
+    /// Splits a node in two halves and returns the separator and the right
+    /// half.
+    fn split(&mut self) -> (K, Node<K, V>) {
+        match self {
+            Node::Leaf { entries } => {
+                let right = entries.split_off(entries.len() / 2);
+                (right[0].0.clone(), Node::Leaf { entries: right })
+            }
+            Node::Internal { keys, children } => {
+                let middle = keys.len() / 2;
+                let right_keys = keys.split_off(middle + 1);
+                let right_children = children.split_off(middle + 1);
+                let separator = keys.remove(middle);
+
+                (
+                    separator,
+                    Node::Internal {
+                        keys: right_keys,
+                        children: right_children,
+                    },
+                )
+            }
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        let to_error = |e: CryptoCoreError| {
+            Error::new(
+                ErrorKind::Interrupted,
+                format!("Invalid B+-tree node: {}", e),
+            )
+        };
+
+        let mut ser = Serializer::new();
+        match self {
+            Node::Leaf { entries } => {
+                ser.write_leb128_u64(LEAF_TAG).map_err(to_error)?;
+                ser.write_leb128_u64(entries.len() as u64)
+                    .map_err(to_error)?;
+                for (key, value) in entries {
+                    ser.write_vec(&serialize(key)?).map_err(to_error)?;
+                    ser.write_vec(&serialize(value)?).map_err(to_error)?;
+                }
+            }
+            Node::Internal { keys, children } => {
+                ser.write_leb128_u64(INTERNAL_TAG).map_err(to_error)?;
+                ser.write_leb128_u64(keys.len() as u64).map_err(to_error)?;
+                for key in keys {
+                    ser.write_vec(&serialize(key)?).map_err(to_error)?;
+                }
+                for child in children {
+                    ser.write_leb128_u64(child.id).map_err(to_error)?;
+                    ser.write_leb128_u64(child.leaf as u64)
+                        .map_err(to_error)?;
+                }
+            }
+        }
+
+        Ok(ser.finalize().to_vec())
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Node<K, V>, Error> {
+        let to_error = |e: CryptoCoreError| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid B+-tree node: {}", e),
+            )
+        };
+
+        let mut de = Deserializer::new(bytes);
+        let tag = de.read_leb128_u64().map_err(to_error)?;
+        let len = de.read_leb128_u64().map_err(to_error)?;
+
+        match tag {
+            LEAF_TAG => {
+                let mut entries = Vec::new();
+                for _ in 0..len {
+                    let key = deserialize(&de.read_vec().map_err(to_error)?)?;
+                    let value = deserialize(&de.read_vec().map_err(to_error)?)?;
+                    entries.push((key, value));
+                }
+
+                Ok(Node::Leaf { entries })
+            }
+            INTERNAL_TAG => {
+                let mut keys = Vec::new();
+                for _ in 0..len {
+                    keys.push(deserialize(&de.read_vec().map_err(to_error)?)?);
+                }
+
+                let mut children = Vec::new();
+                for _ in 0..=len {
+                    let id = de.read_leb128_u64().map_err(to_error)?;
+                    let leaf = de.read_leb128_u64().map_err(to_error)?;
+                    children.push(Pointer {
+                        id,
+                        leaf: leaf as usize,
+                    });
+                }
+
+                Ok(Node::Internal { keys, children })
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid B+-tree node tag {}", tag),
+            )),
+        }
+    }
+}
+
+/// B+-tree whose nodes are ORAM blocks, following the oblivious data
+/// structures of `https://eprint.iacr.org/2014/185`.
+///
+/// Nodes are not in the position map: each pointer holds the id of the child
+/// and the leaf it is mapped to. Nodes are fetched with `read_and_remove` from
+/// the root down, remapped to new leaves which are written in their parent,
+/// and added back to the stash at the end of the operation. Splits and merges
+/// are done top-down in a single pass.
+///
+/// Every operation performs exactly `ACCESSES_PER_LEVEL * max_height` ORAM
+/// accesses, padding with dummy accesses, whatever the operation, the key and
+/// the current height of the tree.
+///
+/// Keys and values are at most `max_key_size` and `max_value_size` bytes once
+/// serialized, which bounds the number of keys per node so that a node always
+/// fits in a block. The client must be set up before creating the tree and is
+/// dedicated to it.
+pub struct ObliviousBPlusTree<K, V, C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    root: Option<Pointer>,
+    height: usize,
+    max_height: usize,
+    max_key_size: usize,
+    max_value_size: usize,
+    max_keys: usize,
+    next_id: u64,
+    _entries: std::marker::PhantomData<(K, V)>,
+}
+
+impl<K, V, C> ObliviousBPlusTree<K, V, C>
+where
+    K: Ord + Clone + Serializable,
+    V: Clone + Serializable,
+    K::Error: Display,
+    V::Error: Display,
+    C: OramCipher,
+{
+    pub fn new(
+        client: ClientOram<C>,
+        max_height: usize,
+        max_key_size: usize,
+        max_value_size: usize,
+    ) -> Result<ObliviousBPlusTree<K, V, C>, Error> {
+        if max_height == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Maximum height shall not be null".to_string(),
+            ));
+        }
+
+        let block_size = client.block_size();
+        let header_length = 2 * to_leb128_len(block_size);
+        let key_length = to_leb128_len(max_key_size) + max_key_size;
+        let entry_length =
+            key_length + to_leb128_len(max_value_size) + max_value_size;
+
+        let max_leaf_keys =
+            block_size.saturating_sub(header_length) / entry_length;
+        let max_internal_keys = block_size
+            .saturating_sub(header_length + POINTER_LENGTH)
+            / (key_length + POINTER_LENGTH);
+        let max_keys = max_leaf_keys.min(max_internal_keys);
+
+        // Merges and splits need at least 3 keys per node.
+        if max_keys < 3 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Block size too small. Got {} bytes, nodes hold {} keys, \
+                     expected at least 3",
+                    block_size, max_keys
+                ),
+            ));
+        }
+
+        Ok(ObliviousBPlusTree {
+            client,
+            root: None,
+            height: 0,
+            max_height,
+            max_key_size,
+            max_value_size,
+            max_keys,
+            next_id: 0,
+            _entries: std::marker::PhantomData,
+        })
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Maximum number of keys of a node.
+    pub fn max_keys(&self) -> usize {
+        self.max_keys
+    }
+
+    /// Number of ORAM accesses performed by any operation.
+    pub fn accesses_per_operation(&self) -> usize {
+        ACCESSES_PER_LEVEL * self.max_height
+    }
+
+    /// Non-root nodes hold more keys than this.
+    fn min_keys(&self) -> usize {
+        (self.max_keys - 1) / 2
+    }
+
+    fn new_slot(&mut self, node: Node<K, V>) -> Slot<K, V> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        Slot {
+            id,
+            leaf: self.client.random_leaf(),
+            node,
+        }
+    }
+
+    /// Reads and removes a node from the ORAM and remaps it to a new leaf,
+    /// which the caller writes in the parent.
+    fn fetch(
+        &mut self,
+        oram: &mut Oram,
+        pointer: Pointer,
+        nb_reads: &mut usize,
+    ) -> Result<Slot<K, V>, Error> {
+        *nb_reads += 1;
+        let payload = self
+            .client
+            .read_and_remove(oram, pointer.id, pointer.leaf)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("B+-tree node {} not found", pointer.id),
+                )
+            })?;
+
+        Ok(Slot {
+            id: pointer.id,
+            leaf: self.client.random_leaf(),
+            node: Node::decode(&payload)?,
+        })
+    }
+
+    /// Adds the nodes back to the stash and pads the operation with dummy
+    /// accesses.
+    fn finish(
+        &mut self,
+        oram: &mut Oram,
+        slots: Vec<Slot<K, V>>,
+        nb_reads: usize,
+    ) -> Result<(), Error> {
+        for slot in slots {
+            let payload = slot.node.encode()?;
+            self.client.add(slot.id, slot.leaf, payload)?;
+        }
+
+        for _ in nb_reads..self.accesses_per_operation() {
+            self.client.dummy_access(oram)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the leaf where `key` is or would be. Also returns the smallest
+    /// separator greater than `key`, if any, from which the next leaf starts.
+    fn find_leaf(
+        &mut self,
+        oram: &mut Oram,
+        key: Bound<&K>,
+    ) -> Result<LeafEntries<K, V>, Error> {
+        let mut nb_reads = 0;
+        let mut slots = Vec::with_capacity(self.max_height);
+
+        let mut result = (Vec::new(), None);
+        if let Some(root) = self.root {
+            let mut current = self.fetch(oram, root, &mut nb_reads)?;
+            self.root = Some(current.pointer());
+
+            loop {
+                match &mut current.node {
+                    Node::Leaf { entries } => {
+                        result.0 = entries.clone();
+                        slots.push(current);
+                        break;
+                    }
+                    Node::Internal { keys, children } => {
+                        let i = route(keys, key);
+                        if i < keys.len() {
+                            result.1 = Some(keys[i].clone());
+                        }
+
+                        let child =
+                            self.fetch(oram, children[i], &mut nb_reads)?;
+                        children[i] = child.pointer();
+
+                        slots.push(mem::replace(&mut current, child));
+                    }
+                }
+            }
+        }
+
+        self.finish(oram, slots, nb_reads)?;
+
+        Ok(result)
+    }
+
+    /// Returns the value of `key`, if any.
+    pub fn get(
+        &mut self,
+        oram: &mut Oram,
+        key: &K,
+    ) -> Result<Option<V>, Error> {
+        let (entries, _) = self.find_leaf(oram, Bound::Included(key))?;
+
+        Ok(entries
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value))
+    }
+
+    /// Iterates over the entries whose keys are in `range`, in increasing
+    /// order. Each leaf of the range is fetched by a full operation, which
+    /// reveals the number of leaves the range spans.
+    pub fn range<'a, R: RangeBounds<K>>(
+        &'a mut self,
+        oram: &'a mut Oram,
+        range: R,
+    ) -> Range<'a, K, V, C> {
+        Range {
+            tree: self,
+            oram,
+            start: Some(range.start_bound().cloned()),
+            end: range.end_bound().cloned(),
+            entries: Vec::new().into_iter(),
+        }
+    }
+
+    /// Inserts `value` for `key` and returns the previous value, if any.
+    ///
+    /// Once the root is full at `max_height`, the tree cannot grow: nodes are
+    /// only split on the way down where their parent has room for a
+    /// separator. Fails with `ErrorKind::OutOfMemory` if the key is new and
+    /// its leaf is still full, the entries of the tree being left unchanged.
+    /// Existing keys can always be updated.
+    pub fn insert(
+        &mut self,
+        oram: &mut Oram,
+        key: K,
+        value: V,
+    ) -> Result<Option<V>, Error> {
+        let key_length = serialize(&key)?.len();
+        let value_length = serialize(&value)?.len();
+        if key_length > self.max_key_size || value_length > self.max_value_size
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Entry too large. Got {} and {} bytes, expected at most \
+                     {} and {}",
+                    key_length,
+                    value_length,
+                    self.max_key_size,
+                    self.max_value_size
+                ),
+            ));
+        }
+
+        let mut nb_reads = 0;
+        let mut slots = Vec::with_capacity(self.accesses_per_operation());
+
+        let mut current = match self.root {
+            Some(root) => self.fetch(oram, root, &mut nb_reads)?,
+            None => {
+                let root = self.new_slot(Node::Leaf {
+                    entries: Vec::new(),
+                });
+                self.height = 1;
+                root
+            }
+        };
+        self.root = Some(current.pointer());
+
+        // A full root is split into a new root before going down, unless the
+        // tree is at its maximum height.
+        if current.node.len() == self.max_keys && self.height < self.max_height
+        {
+            let (separator, right) = current.node.split();
+            let right = self.new_slot(right);
+            let root = self.new_slot(Node::Internal {
+                keys: vec![separator.clone()],
+                children: vec![current.pointer(), right.pointer()],
+            });
+            self.root = Some(root.pointer());
+            self.height += 1;
+
+            slots.push(root);
+            if key < separator {
+                slots.push(right);
+            } else {
+                slots.push(mem::replace(&mut current, right));
+            }
+        }
+
+        let old_value = loop {
+            match &mut current.node {
+                Node::Leaf { entries } => {
+                    let old_value = match entries
+                        .binary_search_by(|(k, _)| k.cmp(&key))
+                    {
+                        Ok(i) => Some(mem::replace(&mut entries[i].1, value)),
+                        Err(_) if entries.len() == self.max_keys => {
+                            slots.push(current);
+                            self.finish(oram, slots, nb_reads)?;
+
+                            return Err(Error::new(
+                                ErrorKind::OutOfMemory,
+                                format!(
+                                    "B+-tree full. Height would exceed {}",
+                                    self.max_height
+                                ),
+                            ));
+                        }
+                        Err(i) => {
+                            entries.insert(i, (key, value));
+                            None
+                        }
+                    };
+                    slots.push(current);
+
+                    break old_value;
+                }
+                Node::Internal { keys, children } => {
+                    let i = route(keys, Bound::Included(&key));
+                    let mut child =
+                        self.fetch(oram, children[i], &mut nb_reads)?;
+                    children[i] = child.pointer();
+
+                    /*
+                     * Full nodes are split on the way down, where their parent
+                     * has room for the separator. It always has, unless the
+                     * root is full at the maximum height.
+                     */
+                    if child.node.len() == self.max_keys
+                        && keys.len() < self.max_keys
+                    {
+                        let (separator, right) = child.node.split();
+                        let right = self.new_slot(right);
+                        keys.insert(i, separator.clone());
+                        children.insert(i + 1, right.pointer());
+
+                        if key < separator {
+                            slots.push(right);
+                        } else {
+                            slots.push(mem::replace(&mut child, right));
+                        }
+                    }
+
+                    slots.push(mem::replace(&mut current, child));
+                }
+            }
+        };
+
+        self.finish(oram, slots, nb_reads)?;
+
+        Ok(old_value)
+    }
+
+    /// Removes `key` and returns its value, if any.
+    pub fn remove(
+        &mut self,
+        oram: &mut Oram,
+        key: &K,
+    ) -> Result<Option<V>, Error> {
+        let mut nb_reads = 0;
+        let mut slots = Vec::with_capacity(self.accesses_per_operation());
+
+        let root = match self.root {
+            Some(root) => root,
+            None => {
+                self.finish(oram, slots, nb_reads)?;
+                return Ok(None);
+            }
+        };
+
+        let mut current = self.fetch(oram, root, &mut nb_reads)?;
+        self.root = Some(current.pointer());
+        let mut is_root = true;
+
+        let old_value = loop {
+            match &mut current.node {
+                Node::Leaf { entries } => {
+                    let old_value =
+                        match entries.binary_search_by(|(k, _)| k.cmp(key)) {
+                            Ok(i) => Some(entries.remove(i).1),
+                            Err(_) => None,
+                        };
+
+                    // An empty root leaf means an empty tree.
+                    if is_root && entries.is_empty() {
+                        self.root = None;
+                        self.height = 0;
+                    } else {
+                        slots.push(current);
+                    }
+
+                    break old_value;
+                }
+                Node::Internal { keys, children } => {
+                    let mut i = route(keys, Bound::Included(key));
+                    let mut child =
+                        self.fetch(oram, children[i], &mut nb_reads)?;
+                    children[i] = child.pointer();
+
+                    /*
+                     * Nodes with the minimum number of keys are filled up on
+                     * the way down, borrowing from or merging with a sibling.
+                     */
+                    if child.node.len() <= self.min_keys() {
+                        let j = if i > 0 { i - 1 } else { i + 1 };
+                        let mut sibling =
+                            self.fetch(oram, children[j], &mut nb_reads)?;
+                        children[j] = sibling.pointer();
+
+                        if sibling.node.len() > self.min_keys() {
+                            borrow(
+                                keys,
+                                i,
+                                &mut child.node,
+                                j,
+                                &mut sibling.node,
+                            );
+                            slots.push(sibling);
+                        } else {
+                            // Merge the right node of the pair in the left one.
+                            let k = i.min(j);
+                            let (mut left, right) = if j < i {
+                                (sibling, child)
+                            } else {
+                                (child, sibling)
+                            };
+                            merge(&mut left.node, keys.remove(k), right.node);
+                            children.remove(k + 1);
+
+                            child = left;
+                            i = k;
+                        }
+                        children[i] = child.pointer();
+                    }
+
+                    // The root is dropped once its last two children merged.
+                    if is_root && keys.is_empty() {
+                        self.root = Some(child.pointer());
+                        self.height -= 1;
+                        current = child;
+                    } else {
+                        slots.push(mem::replace(&mut current, child));
+                    }
+                    is_root = false;
+                }
+            }
+        };
+
+        self.finish(oram, slots, nb_reads)?;
+
+        Ok(old_value)
+    }
+}
+
+/// Moves a key from `sibling` to `child`, the `i`-th and `j`-th children of
+/// a node of separators `keys`.
+fn borrow<K: Clone, V>(
+    keys: &mut [K],
+    i: usize,
+    child: &mut Node<K, V>,
+    j: usize,
+    sibling: &mut Node<K, V>,
+) {
+    match (child, sibling) {
+        (
+            Node::Leaf { entries },
+            Node::Leaf {
+                entries: sibling_entries,
+            },
+        ) => {
+            if j < i {
+                if let Some(entry) = sibling_entries.pop() {
+                    entries.insert(0, entry);
+                }
+                keys[j] = entries[0].0.clone();
+            } else {
+                entries.push(sibling_entries.remove(0));
+                keys[i] = sibling_entries[0].0.clone();
+            }
+        }
+        (
+            Node::Internal {
+                keys: child_keys,
+                children,
+            },
+            Node::Internal {
+                keys: sibling_keys,
+                children: sibling_children,
+            },
+        ) => {
+            if j < i {
+                if let (Some(key), Some(pointer)) =
+                    (sibling_keys.pop(), sibling_children.pop())
+                {
+                    child_keys.insert(0, mem::replace(&mut keys[j], key));
+                    children.insert(0, pointer);
+                }
+            } else {
+                let key = sibling_keys.remove(0);
+                child_keys.push(mem::replace(&mut keys[i], key));
+                children.push(sibling_children.remove(0));
+            }
+        }
+        // Siblings are always at the same level.
+        _ => unreachable!(),
+    }
+}
+
+/// Appends `right` and the separator between them to `left`.
+fn merge<K, V>(left: &mut Node<K, V>, separator: K, right: Node<K, V>) {
+    match (left, right) {
+        (
+            Node::Leaf { entries },
+            Node::Leaf {
+                entries: right_entries,
+            },
+        ) => entries.extend(right_entries),
+        (
+            Node::Internal { keys, children },
+            Node::Internal {
+                keys: right_keys,
+                children: right_children,
+            },
+        ) => {
+            keys.push(separator);
+            keys.extend(right_keys);
+            children.extend(right_children);
+        }
+        // Siblings are always at the same level.
+        _ => unreachable!(),
+    }
+}
+
+/// Iterator over a range of an `ObliviousBPlusTree`, returned by `range`.
+pub struct Range<'a, K, V, C: OramCipher> {
+    tree: &'a mut ObliviousBPlusTree<K, V, C>,
+    oram: &'a mut Oram,
+    /// Lower bound of the next leaf to fetch, `None` once done.
+    start: Option<Bound<K>>,
+    end: Bound<K>,
+    entries: vec::IntoIter<(K, V)>,
+}
+
+impl<K, V, C> Range<'_, K, V, C>
+where
+    K: Ord,
+    C: OramCipher,
+{
+    fn before_end(&self, key: &K) -> bool {
+        match &self.end {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<K, V, C> Iterator for Range<'_, K, V, C>
+where
+    K: Ord + Clone + Serializable,
+    V: Clone + Serializable,
+    K::Error: Display,
+    V::Error: Display,
+    C: OramCipher,
+{
+    type Item = Result<(K, V), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, value)) = self.entries.next() {
+                if !self.before_end(&key) {
+                    self.start = None;
+                    self.entries = Vec::new().into_iter();
+                    return None;
+                }
+
+                return Some(Ok((key, value)));
+            }
+
+            let start = self.start.take()?;
+
+            let (entries, next_start) =
+                match self.tree.find_leaf(self.oram, start.as_ref()) {
+                    Ok(leaf) => leaf,
+                    Err(e) => return Some(Err(e)),
+                };
+
+            // The next leaf starts at the separator following this one.
+            self.start = next_start
+                .filter(|next_start| self.before_end(next_start))
+                .map(Bound::Included);
+
+            self.entries = entries
+                .into_iter()
+                .filter(|(key, _)| match &start {
+                    Bound::Included(start) => key >= start,
+                    Bound::Excluded(start) => key > start,
+                    Bound::Unbounded => true,
+                })
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::oram::BUCKET_SIZE;
+use crate::{oram::BUCKET_SIZE, params::OramParams};
 
 #[derive(Debug, Clone, Default)]
 pub struct BTree {
@@ -7,10 +7,13 @@ pub struct BTree {
 }
 
 impl BTree {
-    pub fn init_new(data_items: &mut Vec<DataItem>, nb_items: usize) -> BTree {
+    pub fn init_new(
+        data_items: &mut Vec<DataItem>,
+        params: &OramParams,
+    ) -> BTree {
         let mut tree = BTree {
             root: Option::None,
-            height: (nb_items / BUCKET_SIZE).ilog2() as u16 + 1,
+            height: params.height(),
         };
 
         let mut root = Node::new();
@@ -54,6 +57,72 @@ impl BTree {
     pub fn height(&self) -> u16 {
         self.height
     }
+
+    /// Adds a level of leaves to the tree, their buckets being filled from
+    /// `data_items` as a stack. The previous leaves keep their buckets.
+    pub fn grow(&mut self, data_items: &mut Vec<DataItem>) {
+        if let Some(root) = self.root.as_mut() {
+            BTree::add_leaves(root, data_items);
+        }
+
+        self.height += 1;
+    }
+
+    fn add_leaves(node: &mut Node, data_items: &mut Vec<DataItem>) {
+        match (node.left.as_mut(), node.right.as_mut()) {
+            (Some(left), Some(right)) => {
+                BTree::add_leaves(left, data_items);
+                BTree::add_leaves(right, data_items);
+            }
+            _ => {
+                let mut left = Box::new(Node::new());
+                let mut right = Box::new(Node::new());
+
+                for i in 0..BUCKET_SIZE {
+                    if let Some(data_item) = data_items.pop() {
+                        left.set_bucket_element(data_item, i);
+                    }
+                }
+                for i in 0..BUCKET_SIZE {
+                    if let Some(data_item) = data_items.pop() {
+                        right.set_bucket_element(data_item, i);
+                    }
+                }
+
+                node.left = Some(left);
+                node.right = Some(right);
+            }
+        }
+    }
+
+    /// Removes the level of leaves of the tree and returns the items of their
+    /// buckets, from the leftmost leaf to the rightmost one. A tree made of its
+    /// root only is left unchanged.
+    pub fn shrink(&mut self) -> Vec<DataItem> {
+        let mut data_items = Vec::new();
+        if self.height < 2 {
+            return data_items;
+        }
+
+        if let Some(root) = self.root.as_mut() {
+            BTree::remove_leaves(root, &mut data_items);
+        }
+        self.height -= 1;
+
+        data_items
+    }
+
+    fn remove_leaves(node: &mut Node, data_items: &mut Vec<DataItem>) {
+        for child in [&mut node.left, &mut node.right] {
+            if let Some(child_node) = child.as_mut() {
+                if child_node.left.is_some() {
+                    BTree::remove_leaves(child_node, data_items);
+                } else if let Some(leaf) = child.take() {
+                    data_items.extend(leaf.bucket);
+                }
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -0,0 +1,320 @@
+use crate::{
+    btree::DataItem,
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::{Oram, BUCKET_SIZE},
+    params::OramParams,
+};
+use cosmian_crypto_core::{
+    bytes_ser_de::{to_leb128_len, Deserializer, Serializable, Serializer},
+    CryptoCoreError,
+};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Error, ErrorKind, Write},
+    path::Path,
+};
+
+/// Version of the checkpoint format. Restoring a checkpoint written with
+/// another version fails.
+pub const CHECKPOINT_VERSION: u64 = 3;
+
+/// Client state needed to resume accesses against an existing server tree.
+/// It is serialized, then encrypted and authenticated with the client key
+/// before being written to disk.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClientState {
+    pub nb_items: usize,
+    pub block_size: usize,
+    pub failure_probability: f64,
+    pub nb_accesses: u64,
+    pub nb_unmapped_blocks: usize,
+    pub outsourced_stash: Option<usize>,
+    pub position_map: HashMap<u64, usize>,
+    pub stash: Vec<DataItem>,
+}
+
+impl Serializable for ClientState {
+    type Error = CryptoCoreError;
+
+    fn length(&self) -> usize {
+        let position_map_length: usize = self
+            .position_map
+            .iter()
+            .map(|(&block_id, &leaf)| {
+                to_leb128_len(block_id as usize) + to_leb128_len(leaf)
+            })
+            .sum();
+
+        let stash_length: usize = self
+            .stash
+            .iter()
+            .map(|item| to_leb128_len(item.data().len()) + item.data().len())
+            .sum();
+
+        to_leb128_len(CHECKPOINT_VERSION as usize)
+            + to_leb128_len(self.nb_items)
+            + to_leb128_len(self.block_size)
+            + to_leb128_len(self.failure_probability.to_bits() as usize)
+            + to_leb128_len(self.nb_accesses as usize)
+            + to_leb128_len(self.nb_unmapped_blocks)
+            + to_leb128_len(
+                self.outsourced_stash.map_or(0, |capacity| capacity + 1),
+            )
+            + to_leb128_len(self.position_map.len())
+            + position_map_length
+            + to_leb128_len(self.stash.len())
+            + stash_length
+    }
+
+    fn write(&self, ser: &mut Serializer) -> Result<usize, Self::Error> {
+        let mut n = ser.write_leb128_u64(CHECKPOINT_VERSION)?;
+        n += ser.write_leb128_u64(self.nb_items as u64)?;
+        n += ser.write_leb128_u64(self.block_size as u64)?;
+        n += ser.write_leb128_u64(self.failure_probability.to_bits())?;
+        n += ser.write_leb128_u64(self.nb_accesses)?;
+        n += ser.write_leb128_u64(self.nb_unmapped_blocks as u64)?;
+        // 0 stands for a stash kept by the client.
+        n += ser.write_leb128_u64(
+            self.outsourced_stash
+                .map_or(0, |capacity| capacity as u64 + 1),
+        )?;
+
+        n += ser.write_leb128_u64(self.position_map.len() as u64)?;
+        for (&block_id, &leaf) in &self.position_map {
+            n += ser.write_leb128_u64(block_id)?;
+            n += ser.write_leb128_u64(leaf as u64)?;
+        }
+
+        n += ser.write_leb128_u64(self.stash.len() as u64)?;
+        for item in &self.stash {
+            n += ser.write_vec(item.data())?;
+        }
+
+        Ok(n)
+    }
+
+    fn read(de: &mut Deserializer) -> Result<Self, Self::Error> {
+        let version = de.read_leb128_u64()?;
+        if version != CHECKPOINT_VERSION {
+            return Err(CryptoCoreError::GenericDeserializationError(format!(
+                "unsupported checkpoint version {}, expected {}",
+                version, CHECKPOINT_VERSION
+            )));
+        }
+
+        let nb_items = read_usize(de)?;
+        let block_size = read_usize(de)?;
+        let failure_probability = f64::from_bits(de.read_leb128_u64()?);
+        let nb_accesses = de.read_leb128_u64()?;
+        let nb_unmapped_blocks = read_usize(de)?;
+        let outsourced_stash = match read_usize(de)? {
+            0 => None,
+            capacity => Some(capacity - 1),
+        };
+
+        let position_map_length = read_usize(de)?;
+        let mut position_map = HashMap::with_capacity(position_map_length);
+        for _ in 0..position_map_length {
+            let block_id = de.read_leb128_u64()?;
+            let leaf = read_usize(de)?;
+            position_map.insert(block_id, leaf);
+        }
+
+        let stash_length = read_usize(de)?;
+        let mut stash = Vec::with_capacity(stash_length);
+        for _ in 0..stash_length {
+            stash.push(DataItem::new(de.read_vec()?));
+        }
+
+        Ok(ClientState {
+            nb_items,
+            block_size,
+            failure_probability,
+            nb_accesses,
+            nb_unmapped_blocks,
+            outsourced_stash,
+            position_map,
+            stash,
+        })
+    }
+}
+
+fn read_usize(de: &mut Deserializer) -> Result<usize, CryptoCoreError> {
+    let n = de.read_leb128_u64()?;
+
+    usize::try_from(n).map_err(|_| {
+        CryptoCoreError::GenericDeserializationError(format!(
+            "value {} is too big for architecture",
+            n
+        ))
+    })
+}
+
+/// Saves the buckets, the outsourced stash and the shared client state of
+/// `oram` to `path`, as the server would persist them. Items are written as
+/// they are stored, that is encrypted by the clients. Any previous tree at
+/// `path` is atomically replaced.
+pub fn save_tree(oram: &mut Oram, path: &Path) -> Result<(), Error> {
+    write_atomically(path, &tree_bytes(oram)?)
+}
+
+/// Loads the tree saved at `path` by `save_tree`, which must be shaped by
+/// `params`.
+pub fn load_tree(path: &Path, params: &OramParams) -> Result<Oram, Error> {
+    tree_from_bytes(&fs::read(path)?, params)
+}
+
+/// Saves both `client` and the tree `oram` it accesses to the single file
+/// `path`, as `ClientOram::checkpoint` and `save_tree` would. Any previous
+/// state at `path` is atomically replaced, so that a crash never leaves a
+/// client out of sync with its tree.
+pub fn save_state<C: OramCipher>(
+    client: &mut ClientOram<C>,
+    oram: &mut Oram,
+    path: &Path,
+) -> Result<(), Error> {
+    let checkpoint = client.checkpoint_bytes()?;
+    let tree = tree_bytes(oram)?;
+
+    let mut ser = Serializer::new();
+    ser.write_vec(&checkpoint)
+        .and_then(|_| ser.write_vec(&tree))
+        .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+
+    write_atomically(path, &ser.finalize())
+}
+
+/// Resumes the client and the tree saved at `path` by `save_state` with the
+/// same key.
+pub fn load_state<C: OramCipher>(
+    path: &Path,
+    key: &[u8],
+) -> Result<(ClientOram<C>, Oram), Error> {
+    let bytes = fs::read(path)?;
+    let mut de = Deserializer::new(&bytes);
+    let mut read = || {
+        de.read_vec().map_err(|e| {
+            Error::new(ErrorKind::InvalidData, format!("Invalid state: {}", e))
+        })
+    };
+    let checkpoint = read()?;
+    let tree = read()?;
+
+    let client = ClientOram::from_checkpoint_bytes(&checkpoint, key)?;
+    let oram = tree_from_bytes(&tree, client.params())?;
+
+    Ok((client, oram))
+}
+
+fn tree_bytes(oram: &mut Oram) -> Result<Vec<u8>, Error> {
+    let height = oram.tree().height();
+    let indices: Vec<usize> = (1..1 << height).collect();
+    let buckets = oram.read_buckets(&indices)?;
+    let stash = oram.read_stash();
+    let client_state = oram.read_client_state();
+
+    let mut ser = Serializer::new();
+    let mut write = || -> Result<(), CryptoCoreError> {
+        ser.write_leb128_u64(height as u64)?;
+        for item in buckets.iter().flatten() {
+            ser.write_vec(item.data())?;
+        }
+
+        ser.write_leb128_u64(stash.len() as u64)?;
+        for item in &stash {
+            ser.write_vec(item.data())?;
+        }
+
+        // An empty state stands for a tree which is not shared.
+        ser.write_vec(client_state.as_ref().map_or(&[], |state| state.data()))?;
+
+        Ok(())
+    };
+    write().map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+
+    Ok(ser.finalize().to_vec())
+}
+
+fn tree_from_bytes(bytes: &[u8], params: &OramParams) -> Result<Oram, Error> {
+    let mut de = Deserializer::new(bytes);
+    let invalid_data = |e: CryptoCoreError| {
+        Error::new(ErrorKind::InvalidData, format!("Invalid tree: {}", e))
+    };
+
+    let height = de.read_leb128_u64().map_err(invalid_data)?;
+    if height != params.height() as u64 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Invalid tree height. Got {}, expected {}",
+                height,
+                params.height()
+            ),
+        ));
+    }
+
+    let mut buckets = Vec::with_capacity((1 << height) - 1);
+    for index in 1..1 << height {
+        let mut bucket: [DataItem; BUCKET_SIZE] = Default::default();
+        for item in &mut bucket {
+            *item = DataItem::new(de.read_vec().map_err(invalid_data)?);
+        }
+        buckets.push((index, bucket));
+    }
+
+    let stash_length = read_usize(&mut de).map_err(invalid_data)?;
+    let mut stash = Vec::with_capacity(stash_length);
+    for _ in 0..stash_length {
+        stash.push(DataItem::new(de.read_vec().map_err(invalid_data)?));
+    }
+
+    let client_state = de.read_vec().map_err(invalid_data)?;
+
+    let mut oram = Oram::with_params(&mut Vec::new(), params);
+    oram.write_buckets(buckets)?;
+    oram.write_stash(stash);
+    if !client_state.is_empty() {
+        oram.write_client_state(DataItem::new(client_state));
+    }
+
+    Ok(oram)
+}
+
+/// Replaces the content of `path` by `bytes` in a crash-safe way. Bytes are
+/// first written and synced to a temporary file in the same directory which
+/// is then renamed over `path`. Since a rename is atomic, a crash leaves
+/// either the previous or the new content at `path`, never a mix of both.
+pub fn write_atomically(path: &Path, bytes: &[u8]) -> Result<(), Error> {
+    let file_name = path.file_name().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid checkpoint path {:?}", path),
+        )
+    })?;
+
+    let mut tmp_file_name = file_name.to_os_string();
+    tmp_file_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_file_name);
+
+    let mut tmp_file = File::create(&tmp_path)?;
+    tmp_file.write_all(bytes)?;
+    tmp_file.sync_all()?;
+    drop(tmp_file);
+
+    fs::rename(&tmp_path, path)?;
+
+    // Persist the rename itself. Directories cannot be opened on Windows.
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        let parent = if parent.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            parent
+        };
+        File::open(parent)?.sync_all()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::{AccessType, Oram},
+};
+use cosmian_crypto_core::Aes256Gcm;
+use std::io::{Error, ErrorKind};
+
+/// Stores values of variable length on top of a `ClientOram` by splitting
+/// them into chunks of the ORAM block size.
+///
+/// The value `value_id` owns the block ids `value_id * chunks_per_value` up to
+/// `(value_id + 1) * chunks_per_value - 1`, its `i`-th chunk being stored in
+/// the `i`-th of them. The last chunk is padded by the block encoding.
+///
+/// Every read, write or removal accesses exactly `chunks_per_value` blocks,
+/// whatever the length of the value, so that the server cannot learn it. The
+/// client shall be instantiated with a capacity of at least
+/// `chunks_per_value` blocks per stored value.
+pub struct ChunkedOram<C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    chunks_per_value: usize,
+}
+
+impl<C: OramCipher> ChunkedOram<C> {
+    pub fn new(
+        client: ClientOram<C>,
+        chunks_per_value: usize,
+    ) -> Result<ChunkedOram<C>, Error> {
+        if chunks_per_value == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Number of chunks per value shall not be null".to_string(),
+            ));
+        }
+
+        Ok(ChunkedOram {
+            client,
+            chunks_per_value,
+        })
+    }
+
+    pub fn chunks_per_value(&self) -> usize {
+        self.chunks_per_value
+    }
+
+    /// Largest value that can be stored, given the block size the ORAM was
+    /// set up with.
+    pub fn max_value_size(&self) -> usize {
+        self.client.block_size() * self.chunks_per_value
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut ClientOram<C> {
+        &mut self.client
+    }
+
+    pub fn into_client(self) -> ClientOram<C> {
+        self.client
+    }
+
+    fn chunk_id(&self, value_id: u64, i: usize) -> Result<u64, Error> {
+        value_id
+            .checked_mul(self.chunks_per_value as u64)
+            .and_then(|first_id| first_id.checked_add(i as u64))
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid value id. Got {}, chunk ids overflow",
+                        value_id
+                    ),
+                )
+            })
+    }
+
+    /// Reads and reassembles the value `value_id`. Returns `None` if it does
+    /// not exist.
+    pub fn read(
+        &mut self,
+        oram: &mut Oram,
+        value_id: u64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        // Check every chunk id before the first access.
+        self.chunk_id(value_id, self.chunks_per_value - 1)?;
+
+        let mut value: Option<Vec<u8>> = None;
+        for i in 0..self.chunks_per_value {
+            let chunk_id = self.chunk_id(value_id, i)?;
+            let chunk =
+                self.client.access(oram, AccessType::Read, chunk_id, None)?;
+
+            /*
+             * The first chunk always exists, even for an empty value. Missing
+             * chunks are still accessed not to leak the value length.
+             */
+            match (i, chunk, value.as_mut()) {
+                (0, Some(chunk), _) => value = Some(chunk),
+                (_, Some(chunk), Some(value)) => value.extend(chunk),
+                _ => (),
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Writes `value` as the value `value_id`, replacing any previous value.
+    /// Chunks of a previous longer value are removed.
+    pub fn write(
+        &mut self,
+        oram: &mut Oram,
+        value_id: u64,
+        value: &[u8],
+    ) -> Result<(), Error> {
+        if value.len() > self.max_value_size() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Value too large. Got {} bytes, maximum is {}",
+                    value.len(),
+                    self.max_value_size()
+                ),
+            ));
+        }
+        self.chunk_id(value_id, self.chunks_per_value - 1)?;
+
+        let block_size = self.client.block_size();
+        // `chunks` panics on a null size, no value can be stored then anyway.
+        let mut chunks = value.chunks(block_size.max(1));
+        for i in 0..self.chunks_per_value {
+            let chunk_id = self.chunk_id(value_id, i)?;
+
+            match chunks.next() {
+                Some(chunk) => {
+                    self.client.access(
+                        oram,
+                        AccessType::Write,
+                        chunk_id,
+                        Some(chunk.to_vec()),
+                    )?;
+                }
+                // An empty value still needs its first chunk to exist.
+                None if i == 0 => {
+                    self.client.access(
+                        oram,
+                        AccessType::Write,
+                        chunk_id,
+                        Some(Vec::new()),
+                    )?;
+                }
+                None => {
+                    self.client.remove(oram, chunk_id)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the value `value_id` and returns it, if any.
+    pub fn remove(
+        &mut self,
+        oram: &mut Oram,
+        value_id: u64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.chunk_id(value_id, self.chunks_per_value - 1)?;
+
+        let mut value: Option<Vec<u8>> = None;
+        for i in 0..self.chunks_per_value {
+            let chunk_id = self.chunk_id(value_id, i)?;
+            let chunk = self.client.remove(oram, chunk_id)?;
+
+            match (i, chunk, value.as_mut()) {
+                (0, Some(chunk), _) => value = Some(chunk),
+                (_, Some(chunk), Some(value)) => value.extend(chunk),
+                _ => (),
+            }
+        }
+
+        Ok(value)
+    }
+}
@@ -0,0 +1,222 @@
+use aes_gcm_siv::{
+    aead::{Aead, KeyInit},
+    Aes256GcmSiv as Aes256GcmSivLib, Key as SivKey, Nonce as SivNonce,
+};
+use cosmian_crypto_core::{
+    reexport::rand_core::CryptoRngCore, Aes256Gcm, ChaCha20Poly1305,
+    CryptoCoreError, Dem, FixedSizeCBytes, Instantiable, Nonce,
+    RandomFixedSizeCBytes, SymmetricKey,
+};
+use std::fmt::Debug;
+
+/// AEAD scheme used by the client to encrypt the items stored in the ORAM.
+///
+/// Ciphertexts are laid out as `nonce || encrypted data || tag`, so that each
+/// item is self-contained and can be decrypted without any extra state. The
+/// size of an encrypted item is thus the size of the plaintext plus
+/// `OVERHEAD` bytes.
+pub trait OramCipher: Sized + Debug {
+    const KEY_LENGTH: usize;
+    const NONCE_LENGTH: usize;
+    const MAC_LENGTH: usize;
+
+    /// Number of bytes added by the encryption to each plaintext.
+    const OVERHEAD: usize = Self::NONCE_LENGTH + Self::MAC_LENGTH;
+
+    /// Instantiates the cipher from `KEY_LENGTH` bytes of key material.
+    fn from_key(key: &[u8]) -> Result<Self, CryptoCoreError>;
+
+    /// Instantiates the cipher with a freshly generated key.
+    fn new_random<R: CryptoRngCore>(rng: &mut R) -> Self;
+
+    /// Encrypts `plaintext` under a fresh nonce drawn from `rng` and returns
+    /// the nonce prepended to the ciphertext.
+    fn encrypt<R: CryptoRngCore>(
+        &self,
+        rng: &mut R,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoCoreError>;
+
+    /// Decrypts a ciphertext produced by `encrypt`.
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, CryptoCoreError>;
+}
+
+fn check_ciphertext_length(
+    ciphertext: &[u8],
+    min: usize,
+) -> Result<(), CryptoCoreError> {
+    if ciphertext.len() < min {
+        return Err(CryptoCoreError::CiphertextTooSmallError {
+            ciphertext_len: ciphertext.len(),
+            min: min as u64,
+        });
+    }
+
+    Ok(())
+}
+
+/*
+ * AES-256-GCM and ChaCha20-Poly1305 both come from `cosmian_crypto_core` and
+ * share the same DEM interface, only their constants differ.
+ */
+macro_rules! impl_oram_cipher_for_dem {
+    ($dem:ty) => {
+        impl OramCipher for $dem {
+            const KEY_LENGTH: usize = <$dem>::KEY_LENGTH;
+            const NONCE_LENGTH: usize = <$dem>::NONCE_LENGTH;
+            const MAC_LENGTH: usize = <$dem>::MAC_LENGTH;
+
+            fn from_key(key: &[u8]) -> Result<Self, CryptoCoreError> {
+                let key = SymmetricKey::try_from_slice(key)?;
+                Ok(<$dem as Instantiable<{ <$dem>::KEY_LENGTH }>>::new(&key))
+            }
+
+            fn new_random<R: CryptoRngCore>(rng: &mut R) -> Self {
+                let key = SymmetricKey::new(rng);
+                <$dem as Instantiable<{ <$dem>::KEY_LENGTH }>>::new(&key)
+            }
+
+            fn encrypt<R: CryptoRngCore>(
+                &self,
+                rng: &mut R,
+                plaintext: &[u8],
+            ) -> Result<Vec<u8>, CryptoCoreError> {
+                // Generate new nonce for encryption.
+                let nonce = Nonce::new(rng);
+
+                let ciphertext = Dem::encrypt(self, &nonce, plaintext, None)?;
+
+                Ok([nonce.as_bytes(), ciphertext.as_slice()].concat())
+            }
+
+            fn decrypt(
+                &self,
+                ciphertext: &[u8],
+            ) -> Result<Vec<u8>, CryptoCoreError> {
+                check_ciphertext_length(
+                    ciphertext,
+                    <Self as OramCipher>::OVERHEAD,
+                )?;
+
+                let nonce =
+                    Nonce::try_from_slice(&ciphertext[..<$dem>::NONCE_LENGTH])?;
+
+                Dem::decrypt(
+                    self,
+                    &nonce,
+                    &ciphertext[<$dem>::NONCE_LENGTH..],
+                    None,
+                )
+            }
+        }
+    };
+}
+
+impl_oram_cipher_for_dem!(Aes256Gcm);
+impl_oram_cipher_for_dem!(ChaCha20Poly1305);
+
+/// AES-256-GCM-SIV (RFC 8452). Unlike AES-GCM, a repeated nonce only reveals
+/// whether two plaintexts are equal, which makes it the safest choice when the
+/// randomness source of the client cannot be fully trusted.
+pub struct Aes256GcmSiv(Aes256GcmSivLib);
+
+impl Aes256GcmSiv {
+    /// Use a 256-bit key.
+    pub const KEY_LENGTH: usize = 32;
+    /// Use a 128-bit MAC tag.
+    pub const MAC_LENGTH: usize = 16;
+    /// Use a 96-bit nonce.
+    pub const NONCE_LENGTH: usize = 12;
+}
+
+impl Debug for Aes256GcmSiv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Aes256GcmSiv").finish()
+    }
+}
+
+impl OramCipher for Aes256GcmSiv {
+    const KEY_LENGTH: usize = Aes256GcmSiv::KEY_LENGTH;
+    const NONCE_LENGTH: usize = Aes256GcmSiv::NONCE_LENGTH;
+    const MAC_LENGTH: usize = Aes256GcmSiv::MAC_LENGTH;
+
+    fn from_key(key: &[u8]) -> Result<Self, CryptoCoreError> {
+        Aes256GcmSivLib::new_from_slice(key)
+            .map(Aes256GcmSiv)
+            .map_err(|_| {
+                CryptoCoreError::InvalidBytesLength(
+                    "AES-256-GCM-SIV key".to_string(),
+                    key.len(),
+                    Some(Aes256GcmSiv::KEY_LENGTH),
+                )
+            })
+    }
+
+    fn new_random<R: CryptoRngCore>(rng: &mut R) -> Self {
+        let key: SymmetricKey<{ Aes256GcmSiv::KEY_LENGTH }> =
+            SymmetricKey::new(rng);
+        Aes256GcmSiv(Aes256GcmSivLib::new(
+            SivKey::<Aes256GcmSivLib>::from_slice(key.as_bytes()),
+        ))
+    }
+
+    fn encrypt<R: CryptoRngCore>(
+        &self,
+        rng: &mut R,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoCoreError> {
+        let nonce: Nonce<{ Aes256GcmSiv::NONCE_LENGTH }> = Nonce::new(rng);
+
+        let ciphertext = self
+            .0
+            .encrypt(SivNonce::from_slice(nonce.as_bytes()), plaintext)
+            .map_err(|_| CryptoCoreError::EncryptionError)?;
+
+        Ok([nonce.as_bytes(), ciphertext.as_slice()].concat())
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, CryptoCoreError> {
+        check_ciphertext_length(ciphertext, <Self as OramCipher>::OVERHEAD)?;
+
+        self.0
+            .decrypt(
+                SivNonce::from_slice(&ciphertext[..Aes256GcmSiv::NONCE_LENGTH]),
+                &ciphertext[Aes256GcmSiv::NONCE_LENGTH..],
+            )
+            .map_err(|_| CryptoCoreError::DecryptionError)
+    }
+}
+
+/// Identity "cipher", which stores items in the clear. It only serves to
+/// speed up simulations, where encryption is irrelevant, and must never be
+/// used to protect actual data.
+#[cfg(any(test, feature = "simulation"))]
+#[derive(Debug)]
+pub struct PlaintextCipher;
+
+#[cfg(any(test, feature = "simulation"))]
+impl OramCipher for PlaintextCipher {
+    const KEY_LENGTH: usize = 0;
+    const NONCE_LENGTH: usize = 0;
+    const MAC_LENGTH: usize = 0;
+
+    fn from_key(_key: &[u8]) -> Result<Self, CryptoCoreError> {
+        Ok(PlaintextCipher)
+    }
+
+    fn new_random<R: CryptoRngCore>(_rng: &mut R) -> Self {
+        PlaintextCipher
+    }
+
+    fn encrypt<R: CryptoRngCore>(
+        &self,
+        _rng: &mut R,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, CryptoCoreError> {
+        Ok(plaintext.to_vec())
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, CryptoCoreError> {
+        Ok(ciphertext.to_vec())
+    }
+}
@@ -1,67 +1,234 @@
 use crate::{
+    block::{Block, BlockHeader, BLOCK_HEADER_LENGTH},
     btree::DataItem,
+    checkpoint::{write_atomically, ClientState},
+    cipher::OramCipher,
     oram::{AccessType, Oram, BUCKET_SIZE},
+    params::OramParams,
 };
 use cosmian_crypto_core::{
-    reexport::rand_core::SeedableRng, Aes256Gcm, CryptoCoreError, CsRng, Dem,
-    FixedSizeCBytes, Instantiable, Nonce, RandomFixedSizeCBytes, SymmetricKey,
+    bytes_ser_de::Serializable,
+    reexport::rand_core::{CryptoRngCore, SeedableRng},
+    Aes256Gcm, CryptoCoreError, CsRng,
 };
 use rand::Rng;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
+    fs,
     io::{Error, ErrorKind},
+    mem,
+    path::Path,
 };
 
-pub struct ClientOram {
+/// Source of randomness of the client. It is boxed so that any CSPRNG can be
+/// injected without changing the type of the client.
+type ClientRng = Box<dyn CryptoRngCore + Send>;
+
+/// Encrypted buckets of a path to write back along with their index, and the
+/// padded stash to upload if it is outsourced.
+pub(crate) type EvictedPath =
+    (Vec<(usize, [DataItem; BUCKET_SIZE])>, Option<Vec<DataItem>>);
+
+/// Change applied to the accessed block before its path is written back.
+enum Update {
+    Keep,
+    Replace(Vec<u8>),
+    Remove,
+}
+
+/// Client state changed by the access in progress, restored if its path
+/// cannot be written back.
+#[derive(Default)]
+struct Journal {
+    /// Stash before the access, encrypted.
+    stash: Vec<DataItem>,
+    /// Cached buckets taken by the access, along with their index.
+    tree_top: Vec<(usize, [DataItem; BUCKET_SIZE])>,
+    /// Former leaves of the blocks remapped by the access, in order.
+    leaves: Vec<(u64, Option<usize>)>,
+}
+
+pub struct ClientOram<C: OramCipher = Aes256Gcm> {
     pub stash: Vec<DataItem>,
-    pub position_map: HashMap<Vec<u8>, usize>,
-    nb_items: usize,
-    csprng: CsRng,
-    cipher: Aes256Gcm,
+    /// Leaf each block id is currently mapped to.
+    pub position_map: HashMap<u64, usize>,
+    /// Number of items, validated at construction, and maximum payload length
+    /// of a block, validated when the ORAM is set up.
+    params: OramParams,
+    nb_accesses: u64,
+    /// Number of blocks stored out of the position map, by `add`.
+    nb_unmapped_blocks: usize,
+    /// Capacity of the stash when it is kept by the server instead of the
+    /// client.
+    outsourced_stash: Option<usize>,
+    /// Plaintext buckets of the top levels of the tree, kept by the client
+    /// instead of the server. Bucket `i`, indexed as a binary heap, is at
+    /// `i - 1`. Empty if the tree top is not cached.
+    tree_top: Vec<[DataItem; BUCKET_SIZE]>,
+    journal: Journal,
+    /// Draws the leaves blocks are mapped to.
+    leaf_rng: ClientRng,
+    /// Draws the nonces of encryptions.
+    nonce_rng: ClientRng,
+    cipher: C,
 }
 
 impl ClientOram {
-    pub fn new(nb_items: usize) -> ClientOram {
+    /// Instantiates a client encrypting items with AES-256-GCM.
+    pub fn new(nb_items: usize) -> Result<ClientOram, Error> {
+        ClientOram::with_cipher(nb_items)
+    }
+}
+
+impl<C: OramCipher> ClientOram<C> {
+    /// Instantiates a client encrypting items with the AEAD `C` under a
+    /// freshly generated key.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `nb_items` is null or too
+    /// large, as `OramParams::new` does.
+    pub fn with_cipher(nb_items: usize) -> Result<ClientOram<C>, Error> {
+        let params = OramParams::for_client(nb_items)?;
         let mut csprng = CsRng::from_entropy();
-        let key = SymmetricKey::new(&mut csprng);
+        let cipher = C::new_random(&mut csprng);
 
-        let mut stash_capacity: usize = 0;
-        if nb_items != 0 {
-            stash_capacity = (nb_items.ilog2() + 1) as usize;
-        }
+        Ok(ClientOram::from_parts(
+            params,
+            Box::new(CsRng::from_entropy()),
+            Box::new(csprng),
+            cipher,
+        ))
+    }
+
+    /// Instantiates a client encrypting items with the AEAD `C` under a
+    /// freshly generated key, for an ORAM shaped by `params`. The ORAM is
+    /// then set up with `setup_oram(params.block_size())`.
+    pub fn with_params(params: OramParams) -> ClientOram<C> {
+        let mut csprng = CsRng::from_entropy();
+        let cipher = C::new_random(&mut csprng);
+
+        ClientOram::from_parts(
+            params,
+            Box::new(CsRng::from_entropy()),
+            Box::new(csprng),
+            cipher,
+        )
+    }
 
+    /// Instantiates a client encrypting items with the AEAD `C` under the
+    /// given key. The same key is needed to `restore` a checkpoint.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the key or `nb_items` is
+    /// invalid.
+    pub fn with_key(
+        nb_items: usize,
+        key: &[u8],
+    ) -> Result<ClientOram<C>, Error> {
+        let params = OramParams::for_client(nb_items)?;
+        let cipher = C::from_key(key)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+
+        Ok(ClientOram::from_parts(
+            params,
+            Box::new(CsRng::from_entropy()),
+            Box::new(CsRng::from_entropy()),
+            cipher,
+        ))
+    }
+
+    /// Instantiates a client of the ORAM shaped by `params`, already set up by
+    /// another client encrypting items with the AEAD `C` under `key`.
+    pub(crate) fn with_params_and_key(
+        params: OramParams,
+        key: &[u8],
+    ) -> Result<ClientOram<C>, Error> {
+        let cipher = C::from_key(key)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+
+        Ok(ClientOram::from_parts(
+            params,
+            Box::new(CsRng::from_entropy()),
+            Box::new(CsRng::from_entropy()),
+            cipher,
+        ))
+    }
+
+    /// Instantiates a client whose key, leaves and nonces are all derived from
+    /// `seed`. Two clients given the same seed and the same operations send
+    /// the exact same data to the server, which allows replaying failures
+    /// and running reproducible stash experiments.
+    ///
+    /// The key can be recovered by anyone knowing the seed: this is only
+    /// meant for tests and simulations.
+    #[cfg(any(test, feature = "simulation"))]
+    pub fn with_seed(
+        nb_items: usize,
+        seed: [u8; 32],
+    ) -> Result<ClientOram<C>, Error> {
+        let params = OramParams::for_client(nb_items)?;
+        let mut seed_rng = CsRng::from_seed(seed);
+        let cipher = C::new_random(&mut seed_rng);
+        let leaf_rng = CsRng::from_seed(seed_rng.gen());
+        let nonce_rng = CsRng::from_seed(seed_rng.gen());
+
+        Ok(ClientOram::from_parts(
+            params,
+            Box::new(leaf_rng),
+            Box::new(nonce_rng),
+            cipher,
+        ))
+    }
+
+    /// Draws the leaves blocks are mapped to from `rng` instead of the
+    /// system entropy.
+    pub fn set_leaf_rng<R: CryptoRngCore + Send + 'static>(&mut self, rng: R) {
+        self.leaf_rng = Box::new(rng);
+    }
+
+    /// Draws the nonces of encryptions, including those of checkpoints, from
+    /// `rng` instead of the system entropy.
+    pub fn set_nonce_rng<R: CryptoRngCore + Send + 'static>(&mut self, rng: R) {
+        self.nonce_rng = Box::new(rng);
+    }
+
+    fn from_parts(
+        params: OramParams,
+        leaf_rng: ClientRng,
+        nonce_rng: ClientRng,
+        cipher: C,
+    ) -> ClientOram<C> {
         ClientOram {
             /*
              * Empty stash at initialization as described in
              * `https://eprint.iacr.org/2013/280`.
              */
-            stash: Vec::with_capacity(stash_capacity),
-            position_map: HashMap::with_capacity(nb_items),
-            nb_items,
-            csprng,
-            cipher: Aes256Gcm::new(&key),
+            stash: Vec::with_capacity(params.stash_bound()),
+            position_map: HashMap::with_capacity(params.nb_items()),
+            params,
+            nb_accesses: 0,
+            nb_unmapped_blocks: 0,
+            outsourced_stash: None,
+            tree_top: Vec::new(),
+            journal: Journal::default(),
+            leaf_rng,
+            nonce_rng,
+            cipher,
         }
     }
 
+    /// Generates encrypted dummy blocks holding up to `block_size` bytes of
+    /// payload.
     pub fn generate_dummy_items(
         &mut self,
         nb_dummy_items: usize,
-        ct_size: usize,
+        block_size: usize,
     ) -> Result<Vec<DataItem>, CryptoCoreError> {
         let mut dummy_items = Vec::with_capacity(nb_dummy_items);
+        let dummy_block = Block::encode_dummy(block_size);
 
         for _ in 0..nb_dummy_items {
-            let dummy_data = vec![0; ct_size];
-
-            // Generate new nonce for encryption.
-            let nonce = Nonce::new(&mut self.csprng);
-
-            // Encrypt null vector as dummies.
-            let encrypted_data =
-                self.cipher.encrypt(&nonce, &dummy_data, None)?;
-
-            let encrypted_dummy =
-                [nonce.as_bytes(), encrypted_data.as_slice()].concat();
+            let encrypted_dummy = self
+                .cipher
+                .encrypt(&mut self.nonce_rng, dummy_block.data())?;
 
             dummy_items.push(DataItem::new(encrypted_dummy));
         }
@@ -73,7 +240,11 @@ impl ClientOram {
     /// stackwise position. This is later given to the server on a write op.
     /// For each level of the tree, computes a bucket of DataItem from `elts`
     /// matching the given path. If less than `BUCKET_SIZE` elements match, fill
-    /// the bucket with dummy items (null vector).
+    /// the bucket with dummy blocks.
+    ///
+    /// `elts` are plaintext blocks. Real blocks present in the position map
+    /// are assigned the leaf it holds, other real blocks keep the leaf of
+    /// their header. Dummies are dropped.
     pub fn order_elements_for_writing(
         &mut self,
         elts: &mut Vec<DataItem>,
@@ -83,7 +254,7 @@ impl ClientOram {
         let mut ordered_elements: Vec<[DataItem; BUCKET_SIZE]> =
             Vec::with_capacity(tree_height);
 
-        let elt_size = elts[0].data().len();
+        let (block_size, mut real_elts) = self.real_elements(elts);
 
         for level in 0..tree_height {
             let mut bucket = [
@@ -93,25 +264,16 @@ impl ClientOram {
                 DataItem::default(),
             ];
 
-            for i in 0..BUCKET_SIZE {
-                for (j, data_item) in elts.iter().enumerate() {
-                    if let Some(&data_item_path) =
-                        self.position_map.get(data_item.data())
-                    {
-                        if data_item_path >> level == path >> level {
-                            bucket[i] = elts.remove(j);
-                            break;
-                        }
-                    }
-                }
+            for slot in bucket.iter_mut() {
+                *slot = match real_elts
+                    .iter()
+                    .position(|(leaf, _)| leaf >> level == path >> level)
+                {
+                    Some(j) => real_elts.remove(j).1,
+                    None => Block::encode_dummy(block_size),
+                };
             }
 
-            bucket.iter_mut().for_each(|data_item| {
-                if data_item.data().is_empty() {
-                    data_item.set_data(vec![0; elt_size]);
-                }
-            });
-
             ordered_elements.push(bucket);
         }
 
@@ -120,13 +282,9 @@ impl ClientOram {
          * more elements to write than slots in the path. They consitute the new
          * stash.
          */
-
-        self.stash = elts
-            .iter()
-            .filter(|data_item| {
-                self.position_map.contains_key(data_item.data())
-            })
-            .cloned()
+        self.stash = real_elts
+            .into_iter()
+            .map(|(_, data_item)| data_item)
             .collect();
 
         /*
@@ -140,37 +298,88 @@ impl ClientOram {
         ordered_elements
     }
 
+    /// Drains `elts` and returns their real blocks along with their leaf, and
+    /// the block size of dummies, that of the blocks being written.
+    fn real_elements(
+        &self,
+        elts: &mut Vec<DataItem>,
+    ) -> (usize, Vec<(usize, DataItem)>) {
+        let block_size = elts
+            .iter()
+            .find(|data_item| !data_item.data().is_empty())
+            .map_or(self.params.block_size(), |data_item| {
+                data_item.data().len().saturating_sub(BLOCK_HEADER_LENGTH)
+            });
+
+        let mut real_elts = Vec::with_capacity(elts.len());
+        for mut data_item in elts.drain(..) {
+            if let Ok(Some(header)) = BlockHeader::decode(&data_item) {
+                let leaf = match self.position_map.get(&header.id) {
+                    Some(&leaf) => {
+                        BlockHeader::set_leaf(&mut data_item, leaf);
+                        leaf
+                    }
+                    None => header.leaf,
+                };
+
+                real_elts.push((leaf, data_item));
+            }
+        }
+
+        (block_size, real_elts)
+    }
+
+    /// Draws a uniformly random leaf of the tree.
+    pub fn random_leaf(&mut self) -> usize {
+        let nb_leaves = self.params.nb_leaves();
+
+        self.leaf_rng.gen_range(0..nb_leaves)
+    }
+
     pub fn change_element_position(
         &mut self,
-        elt: &DataItem,
+        block_id: u64,
     ) -> Result<(), Error> {
-        /*
-         * Number of leaves (max_path) is the previous power of two of the
-         * number of elements.
-         */
-        let max_path = 1 << (self.nb_items / BUCKET_SIZE).ilog2();
+        let new_leaf = self.random_leaf();
 
         let position =
-            self.position_map.get_mut(elt.data()).ok_or(Error::new(
+            self.position_map.get_mut(&block_id).ok_or(Error::new(
                 ErrorKind::Interrupted,
-                format!("Error: element {:?} is not in the position map.", elt),
+                format!(
+                    "Error: block {} is not in the position map.",
+                    block_id
+                ),
             ))?;
 
-        *position = self.csprng.gen_range(0..max_path);
+        *position = new_leaf;
 
         Ok(())
     }
 
-    /// Inserting an element provides him with a uniformly random generated path
-    pub fn insert_element_in_position_map(&mut self, elt: &DataItem) {
-        let max_path = 1 << (self.nb_items / BUCKET_SIZE).ilog2();
+    /// Inserting a block provides him with a uniformly random generated path,
+    /// which is returned.
+    pub fn insert_element_in_position_map(&mut self, block_id: u64) -> usize {
+        let leaf = self.random_leaf();
+
+        self.position_map.insert(block_id, leaf);
 
-        self.position_map
-            .insert(elt.data().clone(), self.csprng.gen_range(0..max_path));
+        leaf
     }
 
-    pub fn delete_element_from_position_map(&mut self, elt: &DataItem) {
-        self.position_map.remove(elt.data());
+    /// Only forgets the leaf of the block, use `access` to remove it from the
+    /// ORAM.
+    pub fn delete_element_from_position_map(&mut self, block_id: u64) {
+        self.position_map.remove(&block_id);
+    }
+
+    /// Maps `block_id` to `leaf`, or forgets it if `None`, as part of the
+    /// access in progress.
+    fn remap(&mut self, block_id: u64, leaf: Option<usize>) {
+        let former_leaf = match leaf {
+            Some(leaf) => self.position_map.insert(block_id, leaf),
+            None => self.position_map.remove(&block_id),
+        };
+        self.journal.leaves.push((block_id, former_leaf));
     }
 
     pub fn encrypt_items(
@@ -179,22 +388,34 @@ impl ClientOram {
     ) -> Result<(), CryptoCoreError> {
         for bucket in buckets {
             for item in bucket {
-                // Generate new nonce for encryption.
-                let nonce = Nonce::new(&mut self.csprng);
-
                 let ciphertext =
-                    self.cipher.encrypt(&nonce, item.data(), Option::None)?;
+                    self.cipher.encrypt(&mut self.nonce_rng, item.data())?;
 
                 // Change element data to ciphertext.
-                item.set_data(
-                    [nonce.as_bytes(), ciphertext.as_slice()].concat(),
-                );
+                item.set_data(ciphertext);
             }
         }
 
         Ok(())
     }
 
+    /// Encrypts `plaintext` under the client key, as a single item.
+    pub(crate) fn encrypt_bytes(
+        &mut self,
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.cipher
+            .encrypt(&mut self.nonce_rng, plaintext)
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))
+    }
+
+    pub(crate) fn decrypt_bytes(
+        &self,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, CryptoCoreError> {
+        self.cipher.decrypt(ciphertext)
+    }
+
     pub fn decrypt_items(
         &self,
         items: &mut [DataItem],
@@ -205,14 +426,7 @@ impl ClientOram {
                 continue;
             }
 
-            let nonce =
-                Nonce::try_from_slice(&item.data()[..Aes256Gcm::NONCE_LENGTH])?;
-
-            let plaintext = self.cipher.decrypt(
-                &nonce,
-                &item.data()[Aes256Gcm::NONCE_LENGTH..],
-                Option::None,
-            )?;
+            let plaintext = self.cipher.decrypt(item.data())?;
 
             item.set_data(plaintext);
         }
@@ -222,15 +436,12 @@ impl ClientOram {
 
     pub fn encrypt_stash(&mut self) -> Result<(), CryptoCoreError> {
         for stash_item in self.stash.iter_mut() {
-            let nonce = Nonce::new(&mut self.csprng);
-
-            let ciphertext =
-                self.cipher
-                    .encrypt(&nonce, stash_item.data(), Option::None)?;
+            let ciphertext = self
+                .cipher
+                .encrypt(&mut self.nonce_rng, stash_item.data())?;
 
             // Change element data to ciphertext.
-            stash_item
-                .set_data([nonce.as_bytes(), ciphertext.as_slice()].concat());
+            stash_item.set_data(ciphertext);
         }
 
         Ok(())
@@ -238,15 +449,7 @@ impl ClientOram {
 
     pub fn decrypt_stash(&mut self) -> Result<(), CryptoCoreError> {
         for stash_item in self.stash.iter_mut() {
-            let nonce = Nonce::try_from_slice(
-                &stash_item.data()[..Aes256Gcm::NONCE_LENGTH],
-            )?;
-
-            let plaintext = self.cipher.decrypt(
-                &nonce,
-                &stash_item.data()[Aes256Gcm::NONCE_LENGTH..],
-                Option::None,
-            )?;
+            let plaintext = self.cipher.decrypt(stash_item.data())?;
 
             stash_item.set_data(plaintext);
         }
@@ -254,21 +457,483 @@ impl ClientOram {
         Ok(())
     }
 
-    pub fn setup_oram(&mut self, ct_size: usize) -> Result<Oram, Error> {
-        // Computes the number of slots in the complete tree.
-        let slots_complete_tree =
-            ((1 << (self.nb_items.ilog2() + 1)) - 1) * BUCKET_SIZE;
+    /// Sets up a server ORAM filled with encrypted dummy blocks, each of them
+    /// holding up to `block_size` bytes of payload.
+    pub fn setup_oram(&mut self, block_size: usize) -> Result<Oram, Error> {
+        self.params = self.params.for_block_size(block_size)?;
 
         let mut dummy_items = self
-            .generate_dummy_items(slots_complete_tree, ct_size)
+            .generate_dummy_items(self.params.nb_slots(), block_size)
             .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
 
         // Creating a new oram with potential prepended data.
-        let oram = Oram::new(&mut dummy_items, self.nb_items)?;
+        let mut oram = Oram::with_params(&mut dummy_items, &self.params);
+        self.tree_top.clear();
+
+        // The outsourced stash has a constant size from the very beginning.
+        if let Some(capacity) = self.outsourced_stash {
+            let stash_dummies =
+                self.generate_dummy_items(capacity, block_size).map_err(
+                    |e| Error::new(ErrorKind::Interrupted, e.to_string()),
+                )?;
+            oram.write_stash(stash_dummies);
+        }
 
         Ok(oram)
     }
 
+    /// Sets up a server ORAM already holding `blocks`, given as pairs of block
+    /// id and payload, in a single pass instead of one access per block.
+    ///
+    /// Each block is mapped to a uniformly random leaf and placed in the
+    /// deepest bucket of its path having a free slot, blocks finding none
+    /// being kept in the stash. Remaining slots are filled with dummies and
+    /// every slot is encrypted, so that the server tree cannot be told apart
+    /// from the one built by the same blocks being accessed one by one.
+    pub fn setup_oram_with_blocks<I>(
+        &mut self,
+        block_size: usize,
+        blocks: I,
+    ) -> Result<Oram, Error>
+    where
+        I: IntoIterator<Item = (u64, Vec<u8>)>,
+    {
+        let params = self.params.for_block_size(block_size)?;
+
+        /*
+         * Buckets are indexed as a binary heap: the root is 1 and the children
+         * of bucket i are 2i and 2i + 1. The leaf bucket of path p is then
+         * 2^(height - 1) + p.
+         */
+        let first_leaf_bucket = params.nb_leaves();
+        let mut buckets: Vec<Vec<DataItem>> =
+            vec![Vec::new(); 2 * first_leaf_bucket];
+
+        // Leaves are only added to the position map once all blocks are valid.
+        let mut positions = HashMap::new();
+        let mut stash = Vec::new();
+        for (block_id, payload) in blocks {
+            if self.position_map.contains_key(&block_id)
+                || positions.contains_key(&block_id)
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Block {} is loaded twice", block_id),
+                ));
+            }
+
+            let leaf = self.leaf_rng.gen_range(0..params.nb_leaves());
+            positions.insert(block_id, leaf);
+            let data_item =
+                Block::new(block_id, leaf, payload).encode(block_size)?;
+
+            // Deepest bucket on the path with a free slot, if any.
+            let mut bucket = first_leaf_bucket + leaf;
+            while bucket > 0 && buckets[bucket].len() == BUCKET_SIZE {
+                bucket /= 2;
+            }
+
+            match bucket {
+                0 => stash.push(data_item),
+                _ => buckets[bucket].push(data_item),
+            }
+        }
+
+        /*
+         * The tree is built by popping the items of each bucket from the end
+         * of a vector, children first, following a post-order traversal.
+         */
+        let mut items = Vec::with_capacity(buckets.len() * BUCKET_SIZE);
+        let mut to_visit = vec![(1, false)];
+        while let Some((bucket, children_visited)) = to_visit.pop() {
+            if children_visited || bucket >= first_leaf_bucket {
+                let mut bucket_items = mem::take(&mut buckets[bucket]);
+                bucket_items.resize_with(BUCKET_SIZE, || {
+                    Block::encode_dummy(block_size)
+                });
+                items.extend(bucket_items);
+            } else {
+                to_visit.push((bucket, true));
+                to_visit.push((2 * bucket + 1, false));
+                to_visit.push((2 * bucket, false));
+            }
+        }
+        items.reverse();
+
+        for item in items.iter_mut() {
+            let ciphertext = self
+                .cipher
+                .encrypt(&mut self.nonce_rng, item.data())
+                .map_err(|e| {
+                    Error::new(ErrorKind::Interrupted, e.to_string())
+                })?;
+            item.set_data(ciphertext);
+        }
+
+        let mut oram = Oram::with_params(&mut items, &params);
+
+        self.params = params;
+        self.tree_top.clear();
+        self.position_map.extend(positions);
+        self.stash = stash;
+        match self.outsourced_stash {
+            Some(capacity) => {
+                let padded_stash = self.pad_stash(capacity)?;
+                oram.write_stash(padded_stash);
+            }
+            None => self.encrypt_stash().map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?,
+        }
+
+        Ok(oram)
+    }
+
+    fn check_no_unmapped_blocks(&self) -> Result<(), Error> {
+        if self.nb_unmapped_blocks > 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Cannot resize the ORAM. Got {} blocks out of the \
+                     position map, expected none",
+                    self.nb_unmapped_blocks
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn check_tree_height(&self, oram: &Oram) -> Result<(), Error> {
+        if oram.tree().height() != self.params.height() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid ORAM. Got a tree of height {}, expected {}",
+                    oram.tree().height(),
+                    self.params.height()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Doubles the capacity of the ORAM by adding a level of leaves filled
+    /// with encrypted dummies. Blocks already stored are left untouched: the
+    /// leaf of each block of the position map is extended with a random bit,
+    /// its former path being a prefix of both new ones.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if blocks were stored out of the
+    /// position map with `add`, as by pointer-based structures: their leaves
+    /// are kept in other blocks, which could not be remapped.
+    pub fn grow(&mut self, oram: &mut Oram) -> Result<(), Error> {
+        self.check_tree_height(oram)?;
+        self.check_no_unmapped_blocks()?;
+
+        // Below `BUCKET_SIZE` items, the tree is made of its root only.
+        let params = self.params.for_nb_items(
+            self.params.nb_items().max(BUCKET_SIZE).saturating_mul(2),
+        )?;
+
+        let mut dummy_items = self
+            .generate_dummy_items(
+                params.nb_leaves() * BUCKET_SIZE,
+                params.block_size(),
+            )
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+        oram.grow(&mut dummy_items)?;
+
+        self.params = params;
+        for leaf in self.position_map.values_mut() {
+            *leaf = 2 * *leaf + self.leaf_rng.gen_range(0..2);
+        }
+
+        Ok(())
+    }
+
+    /// Halves the capacity of the ORAM by removing its level of leaves. The
+    /// leaf of each block of the position map loses its last bit. Real blocks
+    /// of the removed buckets are moved to the stash, still encrypted, and
+    /// are evicted by the next accesses.
+    ///
+    /// Only the removed level is read and decrypted, to find its real blocks,
+    /// and no block is encrypted again.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if more blocks are stored than the
+    /// halved capacity, if the tree is made of its root only, or if blocks
+    /// were stored out of the position map with `add`: pointer-based
+    /// structures keep their leaves in other blocks, which could not be
+    /// remapped. Fails with `ErrorKind::OutOfMemory` if the stash is
+    /// outsourced and could not take the blocks of the removed buckets. The
+    /// ORAM is left unchanged in all these cases.
+    pub fn shrink(&mut self, oram: &mut Oram) -> Result<(), Error> {
+        self.check_tree_height(oram)?;
+
+        if self.params.height() < 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Tree cannot be shrunk below its root".to_string(),
+            ));
+        }
+
+        if self.tree_top_levels() >= self.params.height() - 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Too many cached levels to shrink. Got {}, height would \
+                     be {}",
+                    self.tree_top_levels(),
+                    self.params.height() - 1
+                ),
+            ));
+        }
+
+        self.check_no_unmapped_blocks()?;
+
+        let params = self.params.for_nb_items(self.params.nb_items() / 2)?;
+        if self.position_map.len() > params.nb_items() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Too many blocks to shrink. Got {}, capacity would be {}",
+                    self.position_map.len(),
+                    params.nb_items()
+                ),
+            ));
+        }
+
+        // Real blocks of the leaves, still encrypted.
+        let nb_leaves = self.params.nb_leaves();
+        let indices: Vec<usize> = (nb_leaves..2 * nb_leaves).collect();
+        let leaf_items: Vec<DataItem> =
+            oram.read_buckets(&indices)?.into_iter().flatten().collect();
+        let mut moved = Vec::new();
+        for data_item in leaf_items {
+            // Edge-case where dummies left cells uninitialized.
+            if data_item.data().is_empty() {
+                continue;
+            }
+
+            let plaintext =
+                DataItem::new(self.cipher.decrypt(data_item.data()).map_err(
+                    |e| Error::new(ErrorKind::Interrupted, e.to_string()),
+                )?);
+            if BlockHeader::decode(&plaintext)?.is_some() {
+                moved.push(data_item);
+            }
+        }
+
+        if let Some(capacity) = self.outsourced_stash {
+            let mut stash = self.stash.clone();
+            stash.append(&mut oram.read_stash());
+            self.decrypt_items(&mut stash).map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?;
+
+            let mut nb_stashed = moved.len();
+            for data_item in &stash {
+                if BlockHeader::decode(data_item)?.is_some() {
+                    nb_stashed += 1;
+                }
+            }
+
+            if nb_stashed > capacity {
+                return Err(Error::new(
+                    ErrorKind::OutOfMemory,
+                    format!(
+                        "Stash overflow. Got {} items, capacity is {}",
+                        nb_stashed, capacity
+                    ),
+                ));
+            }
+        }
+
+        oram.shrink()?;
+        self.stash.append(&mut moved);
+
+        self.params = params;
+        for leaf in self.position_map.values_mut() {
+            *leaf >>= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the stash on the server instead of the client memory. On every
+    /// write, the stash is encrypted and padded with encrypted dummies to
+    /// exactly `capacity` items before being uploaded, which hides its real
+    /// occupancy from the server. It is fetched back on every read, so that
+    /// the client only keeps its key and position map between accesses.
+    ///
+    /// This must be called before `setup_oram`. Writes fail with
+    /// `ErrorKind::OutOfMemory` if more than `capacity` items overflow from
+    /// the path, the access being then undone.
+    pub fn outsource_stash(&mut self, capacity: usize) {
+        self.outsourced_stash = Some(capacity);
+    }
+
+    /// Capacity of the stash if it is kept by the server.
+    pub fn outsourced_stash(&self) -> Option<usize> {
+        self.outsourced_stash
+    }
+
+    /// Encrypts the stash and pads it with encrypted dummies so that it always
+    /// counts `capacity` items.
+    fn pad_stash(&mut self, capacity: usize) -> Result<Vec<DataItem>, Error> {
+        if self.stash.len() > capacity {
+            return Err(Error::new(
+                ErrorKind::OutOfMemory,
+                format!(
+                    "Stash overflow. Got {} items, capacity is {}",
+                    self.stash.len(),
+                    capacity
+                ),
+            ));
+        }
+
+        self.encrypt_stash()
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+
+        let mut padded_stash = std::mem::take(&mut self.stash);
+        padded_stash.append(
+            &mut self
+                .generate_dummy_items(
+                    capacity - padded_stash.len(),
+                    self.params.block_size(),
+                )
+                .map_err(|e| {
+                    Error::new(ErrorKind::Interrupted, e.to_string())
+                })?,
+        );
+
+        Ok(padded_stash)
+    }
+
+    /// Fails with `ErrorKind::OutOfMemory` if the blocks left in the stash by
+    /// the eviction of the access in progress overflow the outsourced stash.
+    /// The client is then rolled back to its state before the access, that
+    /// of the server being left as is.
+    fn check_stash_overflow(&mut self) -> Result<(), Error> {
+        match self.outsourced_stash {
+            Some(capacity) if self.stash.len() > capacity => {
+                let nb_items = self.stash.len();
+                self.roll_back();
+
+                Err(Error::new(
+                    ErrorKind::OutOfMemory,
+                    format!(
+                        "Stash overflow. Got {} items, capacity is {}",
+                        nb_items, capacity
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Restores the stash, the cached buckets and the leaves changed by the
+    /// access in progress.
+    fn roll_back(&mut self) {
+        let journal = mem::take(&mut self.journal);
+        self.stash = journal.stash;
+        for (index, bucket) in journal.tree_top {
+            self.tree_top[index - 1] = bucket;
+        }
+        for (block_id, leaf) in journal.leaves.into_iter().rev() {
+            match leaf {
+                Some(leaf) => self.position_map.insert(block_id, leaf),
+                None => self.position_map.remove(&block_id),
+            };
+        }
+    }
+
+    /// Keeps the top `nb_levels` levels of the tree in the client memory, in
+    /// plaintext, so that each access only transfers the buckets of the lower
+    /// levels of its path. The cache holds `2^nb_levels - 1` buckets, which
+    /// eviction fills as any other bucket of the path.
+    ///
+    /// The levels previously cached, if any, are first written back to the
+    /// server, so that 0 stops caching. The buckets newly cached are replaced
+    /// on the server by encrypted dummies, the client keeping their only copy.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `nb_levels` is not less than
+    /// the height of the tree. It must be called between two accesses.
+    pub fn cache_tree_top(
+        &mut self,
+        oram: &mut Oram,
+        nb_levels: u16,
+    ) -> Result<(), Error> {
+        self.check_tree_height(oram)?;
+
+        if nb_levels >= self.params.height() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid number of cached levels. Got {}, expected less \
+                     than {}",
+                    nb_levels,
+                    self.params.height()
+                ),
+            ));
+        }
+
+        if !self.tree_top.is_empty() {
+            let mut buckets = mem::take(&mut self.tree_top);
+            self.encrypt_items(&mut buckets).map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?;
+            oram.write_buckets((1..).zip(buckets).collect())?;
+        }
+
+        if nb_levels == 0 {
+            return Ok(());
+        }
+
+        let indices: Vec<usize> = (1..1 << nb_levels).collect();
+        let mut buckets = oram.read_buckets(&indices)?;
+        for bucket in buckets.iter_mut() {
+            self.decrypt_items(bucket).map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?;
+
+            // Blocks of an unexpected format would be silently dropped.
+            for data_item in bucket.iter() {
+                BlockHeader::decode(data_item)?;
+            }
+        }
+
+        let mut dummies = self
+            .generate_dummy_items(
+                indices.len() * BUCKET_SIZE,
+                self.params.block_size(),
+            )
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?
+            .into_iter();
+        oram.write_buckets(
+            indices
+                .into_iter()
+                .map(|index| {
+                    (
+                        index,
+                        std::array::from_fn(|_| {
+                            dummies.next().unwrap_or_default()
+                        }),
+                    )
+                })
+                .collect(),
+        )?;
+
+        self.tree_top = buckets;
+
+        Ok(())
+    }
+
+    /// Number of top levels of the tree cached by the client.
+    pub fn tree_top_levels(&self) -> u16 {
+        (self.tree_top.len() + 1).ilog2() as u16
+    }
+
     pub fn read_from_path(
         &mut self,
         oram: &mut Oram,
@@ -285,26 +950,97 @@ impl ClientOram {
             ));
         }
 
-        // Read values from path located in ORAM.
-        let mut read_data = oram
-            .access(AccessType::Read, path, Option::None)?
-            .ok_or_else(|| {
-                Error::new(
-                    ErrorKind::Interrupted,
-                    format!("No value returned from read at path {}", path),
-                )
-            })?;
+        // Read values from path located in ORAM, below the cached levels.
+        let read_data = if self.tree_top.is_empty() {
+            oram.access(AccessType::Read, path, Option::None)?
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Interrupted,
+                        format!("No value returned from read at path {}", path),
+                    )
+                })?
+        } else {
+            let indices = path_buckets(oram.tree().height(), path);
+            let uncached = &indices[self.tree_top_levels() as usize..];
+            oram.read_buckets(uncached)?.into_iter().flatten().collect()
+        };
+
+        let server_stash = match self.outsourced_stash {
+            Some(_) => oram.read_stash(),
+            None => Vec::new(),
+        };
+
+        self.load_path(read_data, server_stash, path, oram.tree().height())
+    }
 
+    /// Decrypts the items read from the uncached buckets of `path` and the
+    /// stash, the server part of which is `server_stash` if outsourced.
+    /// Returns them along with the cached buckets of the path, as
+    /// `read_from_path` does. Nothing is requested from the server, so that
+    /// this can be used whatever the transport of the buckets.
+    pub(crate) fn load_path(
+        &mut self,
+        mut read_data: Vec<DataItem>,
+        server_stash: Vec<DataItem>,
+        path: usize,
+        tree_height: u16,
+    ) -> Result<Vec<DataItem>, Error> {
         // Decrypt items read and client stash.
         self.decrypt_items(&mut read_data)
             .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
-        self.decrypt_stash()
-            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+        self.load_stash(server_stash)?;
+
+        // Cached buckets of the path are already in plaintext.
+        let nb_levels = self.tree_top_levels() as usize;
+        for &index in &path_buckets(tree_height, path)[..nb_levels] {
+            let bucket = mem::take(&mut self.tree_top[index - 1]);
+            self.journal.tree_top.push((index, bucket.clone()));
+            read_data.extend(bucket);
+        }
+
+        // Blocks of an unexpected format would be silently dropped on writing.
+        for data_item in self.stash.iter().chain(read_data.iter()) {
+            BlockHeader::decode(data_item)?;
+        }
 
         // Return decrypted data from path concatenated to stash.
         Ok([self.stash.as_slice(), read_data.as_slice()].concat())
     }
 
+    /// Decrypts the stash, merging `server_stash` into it if outsourced.
+    fn load_stash(&mut self, server_stash: Vec<DataItem>) -> Result<(), Error> {
+        // An access starts, the server stash staying as it is until written.
+        self.journal = Journal {
+            stash: self.stash.clone(),
+            ..Journal::default()
+        };
+
+        if self.outsourced_stash.is_some() {
+            /*
+             * Drop the padding of the stash fetched from the server. Blocks
+             * added since the last access are still kept by the client.
+             */
+            let mut stash = std::mem::take(&mut self.stash);
+            stash.extend(server_stash);
+            self.decrypt_items(&mut stash).map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?;
+
+            self.stash = Vec::with_capacity(stash.len());
+            for data_item in stash {
+                if BlockHeader::decode(&data_item)?.is_some() {
+                    self.stash.push(data_item);
+                }
+            }
+        } else {
+            self.decrypt_stash().map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?;
+        }
+
+        Ok(())
+    }
+
     pub fn write_to_path(
         &mut self,
         oram: &mut Oram,
@@ -323,33 +1059,810 @@ impl ClientOram {
             ));
         }
 
-        // Insert new elements to position map if specified.
+        // Insert new blocks to position map if specified.
         if let Some(insert_new_elts) = insert_new_elts {
             for data_item in insert_new_elts {
-                self.position_map.insert(data_item.data().clone(), 0);
-                self.change_element_position(&data_item)?;
+                let header =
+                    BlockHeader::decode(&data_item)?.ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            "Invalid block to insert. Got a dummy, expected a \
+                         real block"
+                                .to_string(),
+                        )
+                    })?;
+
+                let leaf = self.random_leaf();
+                self.remap(header.id, Some(leaf));
+                write_elts.push(data_item);
             }
         }
 
+        let (buckets, padded_stash) =
+            self.evict_path(write_elts, path, oram.tree().height())?;
+
+        if let Some(padded_stash) = padded_stash {
+            oram.write_stash(padded_stash);
+        }
+
+        if self.tree_top.is_empty() {
+            let mut buckets =
+                buckets.into_iter().map(|(_, bucket)| bucket).collect();
+            oram.access(AccessType::Write, path, Some(&mut buckets))?;
+        } else {
+            oram.write_buckets(buckets)?;
+        }
+
+        Ok(())
+    }
+
+    /// Evicts `write_elts` along `path` and encrypts the buckets to write
+    /// back, that is those below the cached levels from the root down to the
+    /// leaf, along with their index. Blocks which do not fit remain in the
+    /// stash, which is encrypted again, or padded and returned if outsourced.
+    /// Nothing is sent to the server, as by `load_path`.
+    pub(crate) fn evict_path(
+        &mut self,
+        write_elts: &mut Vec<DataItem>,
+        path: usize,
+        tree_height: u16,
+    ) -> Result<EvictedPath, Error> {
         /* Stash and elements read from path are ordered in buckets.
          * Update stash with extra elements that could not be written.
          */
         let mut ordered_elements = self.order_elements_for_writing(
             write_elts,
             path,
-            oram.tree().height() as usize,
+            tree_height as usize,
         );
+        self.check_stash_overflow()?;
+
+        // Buckets of the cached levels, from the root, stay on the client.
+        let indices = path_buckets(tree_height, path);
+        let nb_levels = self.tree_top_levels() as usize;
+        for (index, bucket) in
+            indices.iter().zip(ordered_elements.drain(..nb_levels))
+        {
+            self.tree_top[index - 1] = bucket;
+        }
+
+        // Padding the stash has to be done before encrypting the path.
+        let padded_stash = match self.outsourced_stash {
+            Some(capacity) => Some(self.pad_stash(capacity)?),
+            None => None,
+        };
 
         // Encrypt read items to write them back to the ORAM.
         self.encrypt_items(&mut ordered_elements)
             .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
 
         // Encrypt back the stash.
-        self.encrypt_stash()
+        if padded_stash.is_none() {
+            self.encrypt_stash().map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?;
+        }
+
+        self.nb_accesses += 1;
+        self.journal = Journal::default();
+
+        Ok((
+            indices[nb_levels..]
+                .iter()
+                .copied()
+                .zip(ordered_elements)
+                .collect(),
+            padded_stash,
+        ))
+    }
+
+    /// Path ORAM access to the block `block_id` as described in
+    /// `https://eprint.iacr.org/2013/280`. The path the block is mapped to is
+    /// read, the block is remapped to a new uniformly random leaf and the path
+    /// is written back. Reading returns the payload of the block. Writing
+    /// replaces it by `data` and returns the previous payload, if any.
+    ///
+    /// Reading a block which does not exist reads and writes back a random
+    /// path, which the server cannot tell apart from any other access.
+    pub fn access(
+        &mut self,
+        oram: &mut Oram,
+        op: AccessType,
+        block_id: u64,
+        data: Option<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let update = match op {
+            AccessType::Read => Update::Keep,
+            AccessType::Write => {
+                let data = data.ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        "Invalid data to write. Got None, expected Some"
+                            .to_string(),
+                    )
+                })?;
+
+                // Fail before touching the ORAM if the block cannot be encoded.
+                Block::new(block_id, 0, data.clone())
+                    .encode(self.params.block_size())?;
+
+                Update::Replace(data)
+            }
+        };
+
+        self.access_block(oram, block_id, |_| Ok(update))
+    }
+
+    /// Removes the block `block_id` from the ORAM and forgets its leaf. Returns
+    /// its payload, if any. The server sees the same read and write of a path
+    /// as for any other access.
+    pub fn remove(
+        &mut self,
+        oram: &mut Oram,
+        block_id: u64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.access_block(oram, block_id, |_| Ok(Update::Remove))
+    }
+
+    /// Replaces the payload of the block `block_id` by the result of `f`
+    /// within a single access and returns the previous payload. `f` is given
+    /// the current payload, if any, and returning `None` removes the block.
+    ///
+    /// If `f` fails or returns a payload too large for a block, the block is
+    /// left unchanged, the path is still written back and the error is
+    /// returned.
+    pub fn update<F>(
+        &mut self,
+        oram: &mut Oram,
+        block_id: u64,
+        f: F,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        F: FnOnce(Option<&[u8]>) -> Result<Option<Vec<u8>>, Error>,
+    {
+        self.access_block(oram, block_id, |payload| {
+            Ok(match f(payload)? {
+                Some(new_payload) => Update::Replace(new_payload),
+                None => Update::Remove,
+            })
+        })
+    }
+
+    fn access_block<F>(
+        &mut self,
+        oram: &mut Oram,
+        block_id: u64,
+        update: F,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        F: FnOnce(Option<&[u8]>) -> Result<Update, Error>,
+    {
+        let path = match self.position_map.get(&block_id) {
+            Some(&leaf) => leaf,
+            None => self.random_leaf(),
+        };
+
+        let mut read_data = self.read_from_path(oram, path)?;
+
+        let old_block = take_block(&mut read_data, block_id)?;
+
+        let new_leaf = self.random_leaf();
+
+        // Old block remapped to the new leaf, written back if kept.
+        let mut kept_item = match &old_block {
+            Some(old_block) => Some(
+                Block::new(block_id, new_leaf, old_block.payload().to_vec())
+                    .encode(self.params.block_size())?,
+            ),
+            None => None,
+        };
+
+        let res_new_item = match update(old_block.as_ref().map(Block::payload))
+        {
+            Ok(Update::Keep) => Ok(kept_item.take()),
+            Ok(Update::Replace(payload)) => {
+                Block::new(block_id, new_leaf, payload)
+                    .encode(self.params.block_size())
+                    .map(Some)
+            }
+            Ok(Update::Remove) => Ok(None),
+            Err(e) => Err(e),
+        };
+
+        /*
+         * The path has been read and the stash decrypted, they must be written
+         * back even if the update failed.
+         */
+        let (new_item, error) = match res_new_item {
+            Ok(new_item) => (new_item, None),
+            Err(e) => (kept_item, Some(e)),
+        };
+
+        match new_item {
+            Some(new_item) => {
+                self.remap(block_id, Some(new_leaf));
+                read_data.push(new_item);
+            }
+            None => self.remap(block_id, None),
+        }
+
+        self.write_to_path(oram, &mut read_data, None, path)?;
+
+        match error {
+            Some(e) => Err(e),
+            None => Ok(old_block.map(Block::into_payload)),
+        }
+    }
+
+    /// Performs several accesses within a single read and a single write of
+    /// the union of their paths, each bucket shared by several paths being
+    /// transferred once. Each request is given as to `access` and the results
+    /// are returned in the same order.
+    ///
+    /// Blocks are remapped to new random leaves as by `access`, then evicted
+    /// greedily as deep as possible along all paths. Requests must access
+    /// distinct blocks, otherwise the batch fails with
+    /// `ErrorKind::InvalidInput` before any access.
+    pub fn access_batch(
+        &mut self,
+        oram: &mut Oram,
+        requests: Vec<(AccessType, u64, Option<Vec<u8>>)>,
+    ) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        if requests.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut block_ids = HashSet::with_capacity(requests.len());
+        let mut updates = Vec::with_capacity(requests.len());
+        for (op, block_id, data) in requests {
+            if !block_ids.insert(block_id) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Block {} accessed twice in a batch", block_id),
+                ));
+            }
+
+            let new_payload = self.new_payload(op, block_id, data)?;
+            updates.push((block_id, new_payload));
+        }
+
+        let paths: Vec<usize> = updates
+            .iter()
+            .map(|(block_id, _)| match self.position_map.get(block_id) {
+                Some(&leaf) => leaf,
+                None => self.random_leaf(),
+            })
+            .collect();
+
+        let (buckets, mut read_data) = self.read_from_paths(oram, &paths)?;
+
+        let mut results = Vec::with_capacity(updates.len());
+        for (block_id, new_payload) in updates {
+            results.push(self.update_block(
+                &mut read_data,
+                block_id,
+                new_payload,
+            )?);
+        }
+
+        self.write_to_buckets(oram, &mut read_data, buckets)?;
+        self.nb_accesses += paths.len() as u64;
+
+        Ok(results)
+    }
+
+    /// Payload written by the request `op` to `block_id`, checking that it
+    /// fits in a block. Fails with `ErrorKind::InvalidInput` if writing
+    /// without data.
+    pub(crate) fn new_payload(
+        &self,
+        op: AccessType,
+        block_id: u64,
+        data: Option<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        match op {
+            AccessType::Read => Ok(None),
+            AccessType::Write => {
+                let data = data.ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        "Invalid data to write. Got None, expected Some"
+                            .to_string(),
+                    )
+                })?;
+
+                Block::new(block_id, 0, data.clone())
+                    .encode(self.params.block_size())?;
+
+                Ok(Some(data))
+            }
+        }
+    }
+
+    /// Takes the block `block_id` out of the plaintext items `read_data` and
+    /// puts it back remapped to a new random leaf, holding `new_payload` if
+    /// any, its former payload otherwise. The block is removed if it has
+    /// neither. Returns the former payload.
+    pub(crate) fn update_block(
+        &mut self,
+        read_data: &mut Vec<DataItem>,
+        block_id: u64,
+        new_payload: Option<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let old_block = take_block(read_data, block_id)?;
+        let old_payload = old_block.map(Block::into_payload);
+
+        match new_payload.or_else(|| old_payload.clone()) {
+            Some(payload) => {
+                let new_leaf = self.random_leaf();
+                self.remap(block_id, Some(new_leaf));
+                read_data.push(
+                    Block::new(block_id, new_leaf, payload)
+                        .encode(self.params.block_size())?,
+                );
+            }
+            None => self.remap(block_id, None),
+        }
+
+        Ok(old_payload)
+    }
+
+    /// Reads the buckets of all `paths` and returns their indices along with
+    /// the decrypted stash and items read, as `read_from_path`.
+    fn read_from_paths(
+        &mut self,
+        oram: &mut Oram,
+        paths: &[usize],
+    ) -> Result<(Vec<usize>, Vec<DataItem>), Error> {
+        let height = oram.tree().height();
+        let indices: BTreeSet<usize> = paths
+            .iter()
+            .flat_map(|&path| path_buckets(height, path))
+            .collect();
+
+        // Only the buckets below the cached levels are read from the server.
+        let nb_cached = self.tree_top.len();
+        let uncached: Vec<usize> =
+            indices.range(nb_cached + 1..).copied().collect();
+        let mut read_data: Vec<DataItem> = oram
+            .read_buckets(&uncached)?
+            .into_iter()
+            .flatten()
+            .collect();
+        self.decrypt_items(&mut read_data)
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+        let server_stash = match self.outsourced_stash {
+            Some(_) => oram.read_stash(),
+            None => Vec::new(),
+        };
+        self.load_stash(server_stash)?;
+
+        for &index in indices.range(..=nb_cached) {
+            let bucket = mem::take(&mut self.tree_top[index - 1]);
+            self.journal.tree_top.push((index, bucket.clone()));
+            read_data.extend(bucket);
+        }
+
+        // Blocks of an unexpected format would be silently dropped on writing.
+        for data_item in self.stash.iter().chain(read_data.iter()) {
+            BlockHeader::decode(data_item)?;
+        }
+
+        Ok((
+            indices.into_iter().collect(),
+            [mem::take(&mut self.stash), read_data].concat(),
+        ))
+    }
+
+    /// Evicts `write_elts` to the buckets `indices`, deepest buckets first,
+    /// and writes them back along with the stash of the remaining blocks.
+    fn write_to_buckets(
+        &mut self,
+        oram: &mut Oram,
+        write_elts: &mut Vec<DataItem>,
+        mut indices: Vec<usize>,
+    ) -> Result<(), Error> {
+        let height = oram.tree().height() as u32;
+        let nb_leaves = 1 << (height - 1);
+        let (block_size, mut real_elts) = self.real_elements(write_elts);
+
+        indices.sort_by_key(|index| std::cmp::Reverse(index.ilog2()));
+        let mut buckets = Vec::with_capacity(indices.len());
+        for index in indices {
+            let shift = height - 1 - index.ilog2();
+            let mut bucket = [
+                DataItem::default(),
+                DataItem::default(),
+                DataItem::default(),
+                DataItem::default(),
+            ];
+
+            for slot in bucket.iter_mut() {
+                *slot = match real_elts
+                    .iter()
+                    .position(|(leaf, _)| (nb_leaves + leaf) >> shift == index)
+                {
+                    Some(j) => real_elts.remove(j).1,
+                    None => Block::encode_dummy(block_size),
+                };
+            }
+
+            buckets.push((index, bucket));
+        }
+
+        self.stash = real_elts
+            .into_iter()
+            .map(|(_, data_item)| data_item)
+            .collect();
+        self.check_stash_overflow()?;
+
+        // Buckets of the cached levels stay on the client.
+        let nb_cached = self.tree_top.len();
+        let (cached, buckets): (Vec<_>, Vec<_>) = buckets
+            .into_iter()
+            .partition(|(index, _)| *index <= nb_cached);
+        for (index, bucket) in cached {
+            self.tree_top[index - 1] = bucket;
+        }
+
+        // Padding the stash has to be done before encrypting the buckets.
+        let padded_stash = match self.outsourced_stash {
+            Some(capacity) => Some(self.pad_stash(capacity)?),
+            None => None,
+        };
+
+        let (indices, mut buckets): (Vec<_>, Vec<_>) =
+            buckets.into_iter().unzip();
+        self.encrypt_items(&mut buckets)
             .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
 
-        oram.access(AccessType::Write, path, Some(&mut ordered_elements))?;
+        match padded_stash {
+            Some(padded_stash) => oram.write_stash(padded_stash),
+            None => self.encrypt_stash().map_err(|e| {
+                Error::new(ErrorKind::Interrupted, e.to_string())
+            })?,
+        }
+        self.journal = Journal::default();
+
+        oram.write_buckets(indices.into_iter().zip(buckets).collect())
+    }
+
+    /// Reads the path to `leaf`, removes the block `block_id` from it and
+    /// writes the path back. Returns the payload of the block, if found.
+    ///
+    /// Together with `add`, this is meant for pointer-based data structures
+    /// where the leaf of a block is stored in the block pointing to it rather
+    /// than in the position map, as described in
+    /// `https://eprint.iacr.org/2014/185`. Such blocks must use ids which are
+    /// not in the position map.
+    pub fn read_and_remove(
+        &mut self,
+        oram: &mut Oram,
+        block_id: u64,
+        leaf: usize,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let mut read_data = self.read_from_path(oram, leaf)?;
+
+        // Blocks were validated on reading, this cannot fail.
+        let old_block = take_block(&mut read_data, block_id)?;
+
+        self.write_to_path(oram, &mut read_data, None, leaf)?;
+        if old_block.is_some() {
+            self.nb_unmapped_blocks = self.nb_unmapped_blocks.saturating_sub(1);
+        }
+
+        Ok(old_block.map(Block::into_payload))
+    }
+
+    /// Adds the block `block_id` mapped to `leaf` to the stash, without
+    /// accessing the server. It is evicted to the tree by the next accesses.
+    pub fn add(
+        &mut self,
+        block_id: u64,
+        leaf: usize,
+        payload: Vec<u8>,
+    ) -> Result<(), Error> {
+        let data_item = Block::new(block_id, leaf, payload)
+            .encode(self.params.block_size())?;
+
+        // The stash is kept encrypted between accesses.
+        let ciphertext = self
+            .cipher
+            .encrypt(&mut self.nonce_rng, data_item.data())
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+        self.stash.push(DataItem::new(ciphertext));
+        self.nb_unmapped_blocks += 1;
 
         Ok(())
     }
+
+    /// Reads and writes back a uniformly random path, which the server cannot
+    /// tell apart from a real access. Blocks of the stash are evicted along
+    /// the path.
+    pub fn dummy_access(&mut self, oram: &mut Oram) -> Result<(), Error> {
+        let path = self.random_leaf();
+
+        let mut read_data = self.read_from_path(oram, path)?;
+        self.write_to_path(oram, &mut read_data, None, path)
+    }
+
+    /// Decrypts the whole tree, the stash and the cached tree top, and checks
+    /// that:
+    /// - the tree is complete and shaped by the parameters of the client,
+    ///   each bucket holding `BUCKET_SIZE` items;
+    /// - every real block lies on the path of its leaf, that of the position
+    ///   map if the block is in it, that of its header otherwise;
+    /// - no block appears twice across the tree and the stash;
+    /// - every block of the position map is found.
+    ///
+    /// Fails with `ErrorKind::InvalidData` on the first violation. Meant for
+    /// debugging and for checking an ORAM after a restore, as it reads the
+    /// full tree. It must be called between two accesses.
+    pub fn verify_invariants(&self, oram: &mut Oram) -> Result<(), Error> {
+        self.check_tree_height(oram)?;
+
+        let height = self.params.height() as u32;
+        let nb_leaves = self.params.nb_leaves();
+
+        // Items along with their bucket, indexed as a binary heap.
+        let mut buckets = Vec::with_capacity(self.params.nb_slots());
+        let mut items = Vec::with_capacity(self.params.nb_slots());
+        let mut nodes = vec![(1, oram.tree().root.as_deref())];
+        while let Some((bucket, node)) = nodes.pop() {
+            match node {
+                Some(node) if bucket < 2 * nb_leaves => {
+                    // The server only holds dummies in place of cached ones.
+                    if bucket > self.tree_top.len() {
+                        for item in node.bucket() {
+                            buckets.push(Some(bucket));
+                            items.push(item.clone());
+                        }
+                    }
+                    nodes.push((2 * bucket, node.left.as_deref()));
+                    nodes.push((2 * bucket + 1, node.right.as_deref()));
+                }
+                Some(_) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Unexpected bucket {} below the leaves",
+                            bucket
+                        ),
+                    ));
+                }
+                None if bucket < 2 * nb_leaves => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Bucket {} missing", bucket),
+                    ));
+                }
+                None => {}
+            }
+        }
+
+        // Blocks added since the last access are kept by the client.
+        let mut stash = self.stash.clone();
+        if self.outsourced_stash.is_some() {
+            stash.append(&mut oram.read_stash());
+        }
+        buckets.resize(buckets.len() + stash.len(), None);
+        items.append(&mut stash);
+
+        self.decrypt_items(&mut items)
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+
+        for (bucket, cached_bucket) in (1..).zip(&self.tree_top) {
+            for item in cached_bucket {
+                buckets.push(Some(bucket));
+                items.push(item.clone());
+            }
+        }
+
+        let mut block_ids = HashSet::new();
+        for (bucket, item) in buckets.into_iter().zip(items.iter()) {
+            let header = match BlockHeader::decode(item)? {
+                Some(header) => header,
+                None => continue,
+            };
+
+            if !block_ids.insert(header.id) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Block {} stored twice", header.id),
+                ));
+            }
+
+            let leaf = self
+                .position_map
+                .get(&header.id)
+                .copied()
+                .unwrap_or(header.leaf);
+            if leaf >= nb_leaves {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid leaf of block {}. Got {}, expected less than \
+                         {}",
+                        header.id, leaf, nb_leaves
+                    ),
+                ));
+            }
+
+            if let Some(bucket) = bucket {
+                let level = bucket.ilog2();
+                if (nb_leaves + leaf) >> (height - 1 - level) != bucket {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!(
+                            "Block {} in bucket {}, out of the path of leaf {}",
+                            header.id, bucket, leaf
+                        ),
+                    ));
+                }
+            }
+        }
+
+        if let Some(block_id) = self
+            .position_map
+            .keys()
+            .find(|block_id| !block_ids.contains(*block_id))
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Block {} of the position map not found", block_id),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.params.block_size()
+    }
+
+    pub fn params(&self) -> &OramParams {
+        &self.params
+    }
+
+    /// Number of path writes performed since the ORAM was set up.
+    pub fn nb_accesses(&self) -> u64 {
+        self.nb_accesses
+    }
+
+    /// Resumes the count of path writes performed by all the clients of a
+    /// shared tree.
+    pub(crate) fn set_nb_accesses(&mut self, nb_accesses: u64) {
+        self.nb_accesses = nb_accesses;
+    }
+
+    /// Saves the position map, the stash and the counters of the client to
+    /// `path`, encrypted and authenticated under the client key. Any previous
+    /// checkpoint at `path` is atomically replaced.
+    ///
+    /// Checkpoints must be taken between two accesses, i.e. after a
+    /// `write_to_path` and before the next `read_from_path`. Blocks of the
+    /// cached tree top are saved along with the stash: the restored client
+    /// does not cache the tree top until `cache_tree_top` is called again.
+    pub fn checkpoint(&mut self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write_atomically(path.as_ref(), &self.checkpoint_bytes()?)
+    }
+
+    /// Encrypted checkpoint written by `checkpoint`.
+    pub(crate) fn checkpoint_bytes(&mut self) -> Result<Vec<u8>, Error> {
+        // The server only holds dummies in place of the cached buckets.
+        let mut stash = self.stash.clone();
+        for data_item in self.tree_top.iter().flatten() {
+            if BlockHeader::decode(data_item)?.is_some() {
+                let ciphertext = self
+                    .cipher
+                    .encrypt(&mut self.nonce_rng, data_item.data())
+                    .map_err(|e| {
+                        Error::new(ErrorKind::Interrupted, e.to_string())
+                    })?;
+                stash.push(DataItem::new(ciphertext));
+            }
+        }
+
+        let state = ClientState {
+            nb_items: self.params.nb_items(),
+            block_size: self.params.block_size(),
+            failure_probability: self.params.failure_probability(),
+            nb_accesses: self.nb_accesses,
+            nb_unmapped_blocks: self.nb_unmapped_blocks,
+            outsourced_stash: self.outsourced_stash,
+            position_map: self.position_map.clone(),
+            stash,
+        };
+
+        let serialized_state = state
+            .serialize()
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))?;
+
+        self.cipher
+            .encrypt(&mut self.nonce_rng, &serialized_state)
+            .map_err(|e| Error::new(ErrorKind::Interrupted, e.to_string()))
+    }
+
+    /// Resumes a client from a checkpoint written by `checkpoint` with the
+    /// same key. The restored client can keep on accessing the server tree
+    /// the checkpoint was taken against.
+    pub fn restore(
+        path: impl AsRef<Path>,
+        key: &[u8],
+    ) -> Result<ClientOram<C>, Error> {
+        ClientOram::from_checkpoint_bytes(&fs::read(path)?, key)
+    }
+
+    /// Resumes a client from the bytes of a checkpoint, as `restore`.
+    pub(crate) fn from_checkpoint_bytes(
+        encrypted_state: &[u8],
+        key: &[u8],
+    ) -> Result<ClientOram<C>, Error> {
+        let cipher = C::from_key(key)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+
+        let serialized_state =
+            cipher.decrypt(encrypted_state).map_err(|e| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Checkpoint authentication failed: {}", e),
+                )
+            })?;
+
+        let state = ClientState::deserialize(&serialized_state)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        let params = OramParams::with_failure_probability(
+            state.nb_items,
+            state.block_size,
+            state.failure_probability,
+        )
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        let mut client = ClientOram::from_parts(
+            params,
+            Box::new(CsRng::from_entropy()),
+            Box::new(CsRng::from_entropy()),
+            cipher,
+        );
+        client.nb_accesses = state.nb_accesses;
+        client.nb_unmapped_blocks = state.nb_unmapped_blocks;
+        client.outsourced_stash = state.outsourced_stash;
+        client.position_map = state.position_map;
+        client.stash = state.stash;
+
+        Ok(client)
+    }
+}
+
+/// Buckets of `path` in a tree of `height` levels, from the root down to the
+/// leaf, indexed as a binary heap.
+pub(crate) fn path_buckets(height: u16, path: usize) -> Vec<usize> {
+    let leaf_bucket = (1 << (height - 1)) + path;
+
+    (0..height)
+        .rev()
+        .map(|shift| leaf_bucket >> shift)
+        .collect()
+}
+
+/// Removes the block `block_id` from plaintext items and decodes it.
+fn take_block(
+    data_items: &mut Vec<DataItem>,
+    block_id: u64,
+) -> Result<Option<Block>, Error> {
+    let mut position = None;
+    for (i, data_item) in data_items.iter().enumerate() {
+        if let Some(header) = BlockHeader::decode(data_item)? {
+            if header.id == block_id {
+                position = Some(i);
+                break;
+            }
+        }
+    }
+
+    match position {
+        Some(i) => Block::decode(&data_items.remove(i)),
+        None => Ok(None),
+    }
 }
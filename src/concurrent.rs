@@ -0,0 +1,249 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::Oram,
+    scheduler::{FixedRateScheduler, Ticket},
+};
+use std::{
+    io::{Error, ErrorKind},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+pub use crate::scheduler::Request;
+
+/// Thread of the worker, returning the client and the server once stopped,
+/// along with the error which stopped it, if any.
+pub type WorkerHandle<C> = JoinHandle<(ClientOram<C>, Oram, Result<(), Error>)>;
+
+/// Saves the client and the server, see `ConcurrentOram::spawn_with_saves`.
+type Save<C> =
+    Box<dyn FnMut(&mut ClientOram<C>, &mut Oram) -> Result<(), Error> + Send>;
+
+type Reply = Sender<Result<Option<Vec<u8>>, Error>>;
+
+/// Request queued along with the channel its result is sent back to.
+struct Job {
+    request: Request,
+    reply: Reply,
+}
+
+/// Request submitted to a `ConcurrentOram`, not served yet.
+pub struct PendingRequest {
+    reply: Receiver<Result<Option<Vec<u8>>, Error>>,
+}
+
+impl PendingRequest {
+    /// Blocks until the request is served and returns the payload the block
+    /// had before it, as `ClientOram::access`.
+    pub fn wait(self) -> Result<Option<Vec<u8>>, Error> {
+        self.reply.recv().unwrap_or_else(|_| Err(worker_stopped()))
+    }
+}
+
+/// Thread-safe front-end of an ORAM, cheap to clone and share between
+/// threads. Requests are queued and served by a worker thread owning the
+/// client and the server, in the spirit of ObliviStore and TaoStore.
+///
+/// The worker runs the rounds of a `FixedRateScheduler`: exactly
+/// `nb_accesses` path accesses every `period`, whatever the number of
+/// requests queued. The server can thus not tell when, nor how much, the
+/// front-end is used.
+#[derive(Clone)]
+pub struct ConcurrentOram {
+    jobs: Sender<Job>,
+}
+
+impl ConcurrentOram {
+    /// Starts the worker serving requests to `oram` with `client`, which must
+    /// have set it up. The worker stops once every handle is dropped and the
+    /// requests already queued are served, or once a round fails, and returns
+    /// the client and the server in both cases.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `nb_accesses` or `period` is
+    /// zero.
+    pub fn spawn<C: OramCipher + Send + 'static>(
+        client: ClientOram<C>,
+        oram: Oram,
+        nb_accesses: usize,
+        period: Duration,
+    ) -> Result<(ConcurrentOram, WorkerHandle<C>), Error> {
+        Self::spawn_worker(client, oram, nb_accesses, period, None)
+    }
+
+    /// Starts the worker as `spawn`, which also calls `save` with the client
+    /// and the server every `nb_rounds` rounds, once the requests served are
+    /// answered. A crash then loses at most the writes of the last
+    /// `nb_rounds` rounds. Rounds are delayed while saving, and the worker
+    /// stops if `save` fails.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `nb_accesses`, `period` or
+    /// `nb_rounds` is zero.
+    pub fn spawn_with_saves<C, F>(
+        client: ClientOram<C>,
+        oram: Oram,
+        nb_accesses: usize,
+        period: Duration,
+        nb_rounds: usize,
+        save: F,
+    ) -> Result<(ConcurrentOram, WorkerHandle<C>), Error>
+    where
+        C: OramCipher + Send + 'static,
+        F: FnMut(&mut ClientOram<C>, &mut Oram) -> Result<(), Error>
+            + Send
+            + 'static,
+    {
+        if nb_rounds == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Number of rounds between saves shall not be null".to_string(),
+            ));
+        }
+
+        let save: Save<C> = Box::new(save);
+        Self::spawn_worker(
+            client,
+            oram,
+            nb_accesses,
+            period,
+            Some((nb_rounds, save)),
+        )
+    }
+
+    fn spawn_worker<C: OramCipher + Send + 'static>(
+        client: ClientOram<C>,
+        oram: Oram,
+        nb_accesses: usize,
+        period: Duration,
+        save: Option<(usize, Save<C>)>,
+    ) -> Result<(ConcurrentOram, WorkerHandle<C>), Error> {
+        let scheduler =
+            FixedRateScheduler::new(client, oram, nb_accesses, period)?;
+        let (jobs, queue) = mpsc::channel();
+        let mut worker = Worker {
+            scheduler,
+            queue,
+            replies: Vec::new(),
+            save,
+        };
+
+        Ok((
+            ConcurrentOram { jobs },
+            thread::spawn(move || {
+                let res_run = worker.run();
+                let (client, oram) = worker.scheduler.into_parts();
+
+                (client, oram, res_run)
+            }),
+        ))
+    }
+
+    /// Queues `request` without waiting for it to be served.
+    pub fn submit(&self, request: Request) -> Result<PendingRequest, Error> {
+        let (reply, receiver) = mpsc::channel();
+        self.jobs
+            .send(Job { request, reply })
+            .map_err(|_| worker_stopped())?;
+
+        Ok(PendingRequest { reply: receiver })
+    }
+
+    pub fn read(&self, block_id: u64) -> Result<Option<Vec<u8>>, Error> {
+        self.submit(Request::Read(block_id))?.wait()
+    }
+
+    pub fn write(
+        &self,
+        block_id: u64,
+        data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.submit(Request::Write(block_id, data))?.wait()
+    }
+
+    pub fn remove(&self, block_id: u64) -> Result<Option<Vec<u8>>, Error> {
+        self.submit(Request::Remove(block_id))?.wait()
+    }
+}
+
+/// Owner of the client and the server, serving the queued requests.
+struct Worker<C: OramCipher> {
+    scheduler: FixedRateScheduler<C>,
+    queue: Receiver<Job>,
+    /// Requests submitted to the scheduler and not answered yet.
+    replies: Vec<(Ticket, Reply)>,
+    /// Number of rounds between two saves, and how to save.
+    save: Option<(usize, Save<C>)>,
+}
+
+impl<C: OramCipher> Worker<C> {
+    /// Serves requests until every handle is dropped or a round fails.
+    fn run(&mut self) -> Result<(), Error> {
+        let mut nb_rounds: usize = 0;
+        let mut connected = true;
+        while connected || self.scheduler.nb_pending() > 0 {
+            // Requests are received until the round starts.
+            loop {
+                let timeout = self
+                    .scheduler
+                    .next_round()
+                    .saturating_duration_since(Instant::now());
+                match self.queue.recv_timeout(timeout) {
+                    Ok(job) => {
+                        let ticket = self.scheduler.submit(job.request);
+                        // Invalid requests are answered right away.
+                        match self.scheduler.take_result(ticket) {
+                            Some(result) => {
+                                let _ = job.reply.send(result);
+                            }
+                            None => self.replies.push((ticket, job.reply)),
+                        }
+                    }
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        connected = false;
+                        break;
+                    }
+                }
+            }
+
+            // The tree may be out of sync with the client, which stops once
+            // the requests served are answered.
+            let res_round = self.scheduler.run_round();
+            self.answer();
+            res_round?;
+
+            nb_rounds += 1;
+            if let Some((save_rounds, save)) = &mut self.save {
+                if nb_rounds.is_multiple_of(*save_rounds) {
+                    let (client, oram) = self.scheduler.parts_mut();
+                    save(client, oram)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends the results of the requests served back to their callers.
+    fn answer(&mut self) {
+        let scheduler = &mut self.scheduler;
+        self.replies.retain(|(ticket, reply)| {
+            match scheduler.take_result(*ticket) {
+                Some(result) => {
+                    // The caller may have given up waiting.
+                    let _ = reply.send(result);
+                    false
+                }
+                None => true,
+            }
+        });
+    }
+}
+
+fn worker_stopped() -> Error {
+    Error::new(
+        ErrorKind::BrokenPipe,
+        "The ORAM worker has stopped".to_string(),
+    )
+}
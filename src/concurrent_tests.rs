@@ -0,0 +1,284 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Error, ErrorKind},
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        client::ClientOram,
+        concurrent::{ConcurrentOram, Request},
+        oram::AccessType,
+        trace::{MemoryTrace, TraceOp},
+    };
+
+    #[test]
+    fn concurrent_access() {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let (front_end, worker) = ConcurrentOram::spawn(
+            client,
+            path_oram,
+            4,
+            Duration::from_millis(1),
+        )
+        .unwrap();
+
+        let threads: Vec<_> = (0..8u64)
+            .map(|i| {
+                let front_end = front_end.clone();
+                thread::spawn(move || {
+                    for block_id in 5 * i..5 * i + 5 {
+                        let data = vec![block_id as u8; 16];
+                        assert_eq!(
+                            front_end.write(block_id, data).unwrap(),
+                            None
+                        );
+                    }
+                    for block_id in 5 * i..5 * i + 5 {
+                        assert_eq!(
+                            front_end.read(block_id).unwrap(),
+                            Some(vec![block_id as u8; 16])
+                        );
+                    }
+                    assert_eq!(
+                        front_end.remove(5 * i).unwrap(),
+                        Some(vec![5 * i as u8; 16])
+                    );
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // The worker stops once every handle is dropped.
+        drop(front_end);
+        let (mut client, mut path_oram, res_run) = worker.join().unwrap();
+        assert!(res_run.is_ok());
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        assert_eq!(client.position_map.len(), 32);
+
+        for block_id in 0..40u64 {
+            let value = client
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            match block_id % 5 {
+                0 => assert_eq!(value, None),
+                _ => assert_eq!(value, Some(vec![block_id as u8; 16])),
+            }
+        }
+    }
+
+    #[test]
+    fn concurrent_deduplication() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        // Requests are all queued long before the first round.
+        let (front_end, worker) = ConcurrentOram::spawn(
+            client,
+            path_oram,
+            1,
+            Duration::from_millis(200),
+        )
+        .unwrap();
+
+        let requests = vec![
+            Request::Write(5, vec![1; 16]),
+            Request::Read(5),
+            Request::Write(5, vec![2; 16]),
+            Request::Remove(5),
+            Request::Read(5),
+            Request::Write(5, vec![3; 16]),
+        ];
+        let pending: Vec<_> = requests
+            .into_iter()
+            .map(|request| front_end.submit(request).unwrap())
+            .collect();
+        let results: Vec<_> =
+            pending.into_iter().map(|p| p.wait().unwrap()).collect();
+        assert_eq!(
+            results,
+            vec![
+                None,
+                Some(vec![1; 16]),
+                Some(vec![1; 16]),
+                Some(vec![2; 16]),
+                None,
+                None
+            ]
+        );
+
+        // All of them were served by a single access.
+        let ops: Vec<_> = trace.events().iter().map(|event| event.op).collect();
+        assert_eq!(ops, vec![TraceOp::ReadPath, TraceOp::WritePath]);
+
+        assert_eq!(front_end.read(5).unwrap(), Some(vec![3; 16]));
+        drop(front_end);
+        assert!(worker.join().unwrap().2.is_ok());
+    }
+
+    #[test]
+    fn concurrent_fixed_rate() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        let (front_end, worker) = ConcurrentOram::spawn(
+            client,
+            path_oram,
+            3,
+            Duration::from_millis(5),
+        )
+        .unwrap();
+
+        // Dummy accesses are performed while no request is queued.
+        thread::sleep(Duration::from_millis(50));
+        assert!(trace.len() >= 2 * 3);
+
+        for block_id in 0..10u64 {
+            front_end.submit(Request::Read(block_id)).unwrap();
+        }
+        drop(front_end);
+        let (client, _, res_run) = worker.join().unwrap();
+        assert!(res_run.is_ok());
+
+        // Every round performs the same number of accesses.
+        assert_eq!(client.nb_accesses() % 3, 0);
+        assert_eq!(trace.len() as u64, 2 * client.nb_accesses());
+
+        // No round starts early, whatever the requests queued.
+        let events = trace.events();
+        for (round, event) in events.iter().step_by(2 * 3).enumerate() {
+            assert!(
+                event.timestamp
+                    >= Duration::from_millis(5) * (round as u32 + 1)
+            );
+        }
+    }
+
+    #[test]
+    fn concurrent_invalid_requests() {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let res_spawn = ConcurrentOram::spawn(
+            client,
+            path_oram,
+            0,
+            Duration::from_millis(1),
+        );
+        assert_eq!(res_spawn.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let res_spawn =
+            ConcurrentOram::spawn(client, path_oram, 1, Duration::ZERO);
+        assert_eq!(res_spawn.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let (front_end, worker) = ConcurrentOram::spawn(
+            client,
+            path_oram,
+            1,
+            Duration::from_millis(1),
+        )
+        .unwrap();
+
+        let res_write = front_end.write(1, vec![1; 17]);
+        assert_eq!(res_write.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(front_end.read(1).unwrap(), None);
+
+        drop(front_end);
+        let (client, _, res_run) = worker.join().unwrap();
+        assert!(res_run.is_ok());
+        assert!(client.position_map.is_empty());
+    }
+
+    #[test]
+    fn concurrent_saves() {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let res_spawn = ConcurrentOram::spawn_with_saves(
+            client,
+            path_oram,
+            1,
+            Duration::from_millis(1),
+            0,
+            |_, _| Ok(()),
+        );
+        assert_eq!(res_spawn.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        // Number of accesses and of blocks at each save.
+        let saves = Arc::new(Mutex::new(Vec::new()));
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let (front_end, worker) = {
+            let saves = Arc::clone(&saves);
+            ConcurrentOram::spawn_with_saves(
+                client,
+                path_oram,
+                2,
+                Duration::from_millis(1),
+                3,
+                move |client, _| {
+                    saves.lock().unwrap().push((
+                        client.nb_accesses(),
+                        client.position_map.len(),
+                    ));
+                    Ok(())
+                },
+            )
+            .unwrap()
+        };
+
+        for block_id in 0..10u64 {
+            front_end.write(block_id, vec![block_id as u8; 16]).unwrap();
+        }
+        drop(front_end);
+        let (client, _, res_run) = worker.join().unwrap();
+        assert!(res_run.is_ok());
+
+        // Saves happen every 3 rounds, that is every 6 accesses.
+        let saves = saves.lock().unwrap();
+        assert!(!saves.is_empty());
+        for (i, &(nb_accesses, _)) in saves.iter().enumerate() {
+            assert_eq!(nb_accesses, 6 * (i as u64 + 1));
+        }
+        assert!(client.nb_accesses() < 6 * (saves.len() as u64 + 1));
+        assert!(saves.windows(2).all(|saves| saves[0].1 <= saves[1].1));
+    }
+
+    #[test]
+    fn concurrent_failed_save() {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let (front_end, worker) = ConcurrentOram::spawn_with_saves(
+            client,
+            path_oram,
+            1,
+            Duration::from_millis(1),
+            1,
+            |_, _| Err(Error::other("Disk full".to_string())),
+        )
+        .unwrap();
+
+        // The worker stops, the requests of the round being answered.
+        assert_eq!(front_end.write(3, vec![3; 16]).unwrap(), None);
+        let (mut client, mut path_oram, res_run) = worker.join().unwrap();
+        assert_eq!(res_run.unwrap_err().kind(), ErrorKind::Other);
+        assert!(front_end.read(3).is_err());
+
+        // The client and the server are still given back.
+        let value = client
+            .access(&mut path_oram, AccessType::Read, 3, None)
+            .unwrap();
+        assert_eq!(value, Some(vec![3; 16]));
+    }
+}
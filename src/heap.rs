@@ -0,0 +1,428 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    map::{deserialize, serialize},
+    oram::{AccessType, Oram},
+};
+use cosmian_crypto_core::{bytes_ser_de::Serializable, Aes256Gcm};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    marker::PhantomData,
+};
+
+/// Length of the header of an entry: its priority and its handle, both
+/// little-endian `u64`.
+const ENTRY_HEADER_LENGTH: usize = 16;
+
+/// Entry of the heap. Entries are ordered by priority, then by handle so that
+/// entries of equal priority are extracted in insertion order.
+struct Entry {
+    priority: u64,
+    handle: u64,
+    value: Vec<u8>,
+}
+
+impl Entry {
+    fn key(&self) -> (u64, u64) {
+        (self.priority, self.handle)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        [
+            &self.priority.to_le_bytes()[..],
+            &self.handle.to_le_bytes(),
+            &self.value,
+        ]
+        .concat()
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Entry, Error> {
+        if bytes.len() < ENTRY_HEADER_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Invalid heap entry. Got {} bytes, expected at least {}",
+                    bytes.len(),
+                    ENTRY_HEADER_LENGTH
+                ),
+            ));
+        }
+
+        Ok(Entry {
+            priority: read_u64(&bytes[..8])?,
+            handle: read_u64(&bytes[8..ENTRY_HEADER_LENGTH])?,
+            value: bytes[ENTRY_HEADER_LENGTH..].to_vec(),
+        })
+    }
+}
+
+/// Min-heap stored in the ORAM, as a binary heap laid out in an array of
+/// blocks.
+///
+/// The `i`-th slot of the heap is stored in the block `i - 1`. Each entry is
+/// given a handle when inserted, the block `capacity + handle` holding the
+/// slot of the entry so that its priority can later be decreased. Sifting an
+/// entry up or down accesses a bounded number of blocks per level of the
+/// heap.
+///
+/// Every operation, whatever its kind and the number of levels the entry
+/// moves, is padded with dummy accesses to `accesses_per_operation` ORAM
+/// accesses. The server thus cannot tell an insertion from an extraction or
+/// learn the order of priorities.
+///
+/// The client shall be instantiated with a capacity of at least
+/// `2 * capacity` blocks, the ORAM being set up with blocks of at least 16
+/// bytes plus the length of the serialized values.
+pub struct ObliviousHeap<V, C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    capacity: usize,
+    len: usize,
+    next_handle: u64,
+    _values: PhantomData<V>,
+}
+
+impl<V, C> ObliviousHeap<V, C>
+where
+    V: Serializable,
+    V::Error: Display,
+    C: OramCipher,
+{
+    pub fn new(
+        client: ClientOram<C>,
+        capacity: usize,
+    ) -> Result<ObliviousHeap<V, C>, Error> {
+        if capacity == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Heap capacity shall not be null".to_string(),
+            ));
+        }
+
+        if client.block_size() < ENTRY_HEADER_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Block size too small. Got {} bytes, expected at least {}",
+                    client.block_size(),
+                    ENTRY_HEADER_LENGTH
+                ),
+            ));
+        }
+
+        Ok(ObliviousHeap {
+            client,
+            capacity,
+            len: 0,
+            next_handle: 0,
+            _values: PhantomData,
+        })
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of ORAM accesses performed by every operation, that of an
+    /// extraction sifting an entry down the whole heap: the root, its handle
+    /// and the last entry, then the two children, the moved entry and its
+    /// handle on each level, then the final entry and its handle.
+    pub fn accesses_per_operation(&self) -> usize {
+        4 * self.capacity.ilog2() as usize + 5
+    }
+
+    fn slot_id(slot: usize) -> u64 {
+        slot as u64 - 1
+    }
+
+    fn handle_id(&self, handle: u64) -> Result<u64, Error> {
+        (self.capacity as u64).checked_add(handle).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid handle. Got {}, block id overflows", handle),
+            )
+        })
+    }
+
+    /// Pads the operation started after `start` accesses with dummy accesses.
+    fn pad(&mut self, oram: &mut Oram, start: u64) -> Result<(), Error> {
+        let end = start + self.accesses_per_operation() as u64;
+        while self.client.nb_accesses() < end {
+            self.client.dummy_access(oram)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_slot(
+        &mut self,
+        oram: &mut Oram,
+        slot: usize,
+    ) -> Result<Entry, Error> {
+        let bytes = self
+            .client
+            .access(oram, AccessType::Read, Self::slot_id(slot), None)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Heap slot {} is missing", slot),
+                )
+            })?;
+
+        Entry::decode(&bytes)
+    }
+
+    /// Writes `entry` to `slot` and points its handle to it.
+    fn write_slot(
+        &mut self,
+        oram: &mut Oram,
+        slot: usize,
+        entry: &Entry,
+    ) -> Result<(), Error> {
+        self.client.access(
+            oram,
+            AccessType::Write,
+            Self::slot_id(slot),
+            Some(entry.encode()),
+        )?;
+
+        let handle_id = self.handle_id(entry.handle)?;
+        self.client.access(
+            oram,
+            AccessType::Write,
+            handle_id,
+            Some((slot as u64).to_le_bytes().to_vec()),
+        )?;
+
+        Ok(())
+    }
+
+    /// Moves `entry` up from the empty slot `hole`, parents greater than it
+    /// being moved down.
+    fn sift_up(
+        &mut self,
+        oram: &mut Oram,
+        entry: Entry,
+        mut hole: usize,
+    ) -> Result<(), Error> {
+        while hole > 1 {
+            let parent = self.read_slot(oram, hole / 2)?;
+            if entry.key() >= parent.key() {
+                break;
+            }
+
+            self.write_slot(oram, hole, &parent)?;
+            hole /= 2;
+        }
+
+        self.write_slot(oram, hole, &entry)
+    }
+
+    /// Moves `entry` down from the empty slot `hole`, the smallest child
+    /// being moved up while it is smaller than `entry`.
+    fn sift_down(
+        &mut self,
+        oram: &mut Oram,
+        entry: Entry,
+        mut hole: usize,
+    ) -> Result<(), Error> {
+        while 2 * hole <= self.len {
+            let mut child = 2 * hole;
+            let mut child_entry = self.read_slot(oram, child)?;
+            if child < self.len {
+                let right_entry = self.read_slot(oram, child + 1)?;
+                if right_entry.key() < child_entry.key() {
+                    child += 1;
+                    child_entry = right_entry;
+                }
+            }
+
+            if entry.key() <= child_entry.key() {
+                break;
+            }
+
+            self.write_slot(oram, hole, &child_entry)?;
+            hole = child;
+        }
+
+        self.write_slot(oram, hole, &entry)
+    }
+
+    /// Inserts `value` with `priority` and returns the handle of the entry.
+    ///
+    /// Fails with `ErrorKind::OutOfMemory` if the heap is full, the ORAM
+    /// being left untouched.
+    pub fn insert(
+        &mut self,
+        oram: &mut Oram,
+        priority: u64,
+        value: &V,
+    ) -> Result<u64, Error> {
+        let value = serialize(value)?;
+
+        let block_size = self.client.block_size();
+        if ENTRY_HEADER_LENGTH + value.len() > block_size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Entry too large. Got {} bytes, block size is {}",
+                    ENTRY_HEADER_LENGTH + value.len(),
+                    block_size
+                ),
+            ));
+        }
+
+        if self.len == self.capacity {
+            return Err(Error::new(
+                ErrorKind::OutOfMemory,
+                format!("Heap full. Capacity is {} entries", self.capacity),
+            ));
+        }
+
+        let handle = self.next_handle;
+        self.handle_id(handle)?;
+        self.next_handle += 1;
+
+        let start = self.client.nb_accesses();
+
+        let res_sift = self.sift_up(
+            oram,
+            Entry {
+                priority,
+                handle,
+                value,
+            },
+            self.len + 1,
+        );
+        // The entry only counts once written.
+        if res_sift.is_ok() {
+            self.len += 1;
+        }
+
+        self.pad(oram, start)?;
+        res_sift.map(|_| handle)
+    }
+
+    /// Removes the entry of lowest priority and returns its priority and
+    /// value, if any.
+    pub fn extract_min(
+        &mut self,
+        oram: &mut Oram,
+    ) -> Result<Option<(u64, V)>, Error> {
+        let start = self.client.nb_accesses();
+        let res_extract = self.extract_min_unpadded(oram);
+        self.pad(oram, start)?;
+
+        res_extract?
+            .map(|root| Ok((root.priority, deserialize::<V>(&root.value)?)))
+            .transpose()
+    }
+
+    fn extract_min_unpadded(
+        &mut self,
+        oram: &mut Oram,
+    ) -> Result<Option<Entry>, Error> {
+        if self.len == 0 {
+            return Ok(None);
+        }
+
+        let root = self.read_slot(oram, 1)?;
+        let handle_id = self.handle_id(root.handle)?;
+        self.client.remove(oram, handle_id)?;
+
+        // The last entry fills the root slot, which is left empty otherwise.
+        let last = self
+            .client
+            .remove(oram, Self::slot_id(self.len))?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Heap slot {} is missing", self.len),
+                )
+            })?;
+        self.len -= 1;
+
+        if self.len > 0 {
+            self.sift_down(oram, Entry::decode(&last)?, 1)?;
+        }
+
+        Ok(Some(root))
+    }
+
+    /// Lowers the priority of the entry `handle` to `priority`.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the entry has been extracted and
+    /// with `ErrorKind::InvalidInput` if `priority` is greater than its
+    /// current priority, the heap being left unchanged.
+    pub fn decrease_key(
+        &mut self,
+        oram: &mut Oram,
+        handle: u64,
+        priority: u64,
+    ) -> Result<(), Error> {
+        let handle_id = self.handle_id(handle)?;
+
+        let start = self.client.nb_accesses();
+        let res_decrease =
+            self.decrease_key_unpadded(oram, handle, handle_id, priority);
+        self.pad(oram, start)?;
+
+        res_decrease
+    }
+
+    fn decrease_key_unpadded(
+        &mut self,
+        oram: &mut Oram,
+        handle: u64,
+        handle_id: u64,
+        priority: u64,
+    ) -> Result<(), Error> {
+        let slot = self
+            .client
+            .access(oram, AccessType::Read, handle_id, None)?
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!("Heap entry {} not found", handle),
+                )
+            })?;
+        let slot = read_u64(&slot)? as usize;
+
+        let mut entry = self.read_slot(oram, slot)?;
+        if priority > entry.priority {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Priority can only be decreased. Got {}, current is {}",
+                    priority, entry.priority
+                ),
+            ));
+        }
+
+        entry.priority = priority;
+        self.sift_up(oram, entry, slot)
+    }
+}
+
+fn read_u64(bytes: &[u8]) -> Result<u64, Error> {
+    let array = bytes.try_into().map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid integer. Got {} bytes, expected 8", bytes.len()),
+        )
+    })?;
+
+    Ok(u64::from_le_bytes(array))
+}
@@ -0,0 +1,30 @@
+#[cfg(feature = "async")]
+pub mod async_client;
+mod async_tests;
+pub mod block;
+pub mod bplustree;
+pub mod btree;
+pub mod checkpoint;
+pub mod chunked;
+pub mod cipher;
+pub mod client;
+pub mod concurrent;
+mod concurrent_tests;
+pub mod heap;
+pub mod linked;
+pub mod map;
+mod model_tests;
+mod obliviousness_tests;
+pub mod oram;
+mod oram_tests;
+pub mod params;
+#[cfg(unix)]
+pub mod proxy;
+mod proxy_tests;
+pub mod scheduler;
+mod scheduler_tests;
+pub mod shared;
+mod shared_tests;
+#[cfg(any(test, feature = "simulation"))]
+pub mod simulation;
+pub mod trace;
@@ -0,0 +1,318 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    map::{deserialize, serialize},
+    oram::Oram,
+};
+use cosmian_crypto_core::{bytes_ser_de::Serializable, Aes256Gcm};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    marker::PhantomData,
+};
+
+/// Length of the header of an element: a flag telling whether a pointer
+/// follows, then the id and the leaf of the pointed block, both little-endian
+/// `u64`.
+const ELEMENT_HEADER_LENGTH: usize = 17;
+
+/// Location of an element in the ORAM. The leaf of an element is stored in
+/// the element pointing to it instead of the position map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pointer {
+    id: u64,
+    leaf: usize,
+}
+
+fn encode_element(next: Option<Pointer>, value: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0; ELEMENT_HEADER_LENGTH];
+    if let Some(next) = next {
+        bytes[0] = 1;
+        bytes[1..9].copy_from_slice(&next.id.to_le_bytes());
+        bytes[9..17].copy_from_slice(&(next.leaf as u64).to_le_bytes());
+    }
+    bytes.extend_from_slice(value);
+
+    bytes
+}
+
+fn decode_element(bytes: &[u8]) -> Result<(Option<Pointer>, &[u8]), Error> {
+    if bytes.len() < ELEMENT_HEADER_LENGTH {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Invalid element. Got {} bytes, expected at least {}",
+                bytes.len(),
+                ELEMENT_HEADER_LENGTH
+            ),
+        ));
+    }
+
+    let mut id = [0; 8];
+    id.copy_from_slice(&bytes[1..9]);
+    let mut leaf = [0; 8];
+    leaf.copy_from_slice(&bytes[9..17]);
+
+    let next = match bytes[0] {
+        0 => None,
+        _ => Some(Pointer {
+            id: u64::from_le_bytes(id),
+            leaf: u64::from_le_bytes(leaf) as usize,
+        }),
+    };
+
+    Ok((next, &bytes[ELEMENT_HEADER_LENGTH..]))
+}
+
+/// Checks that `len` elements can be held and that `value` fits in a block,
+/// before any access.
+fn check_push<C: OramCipher>(
+    client: &ClientOram<C>,
+    len: usize,
+    capacity: usize,
+    value: &[u8],
+) -> Result<(), Error> {
+    let block_size = client.block_size();
+    if ELEMENT_HEADER_LENGTH + value.len() > block_size {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Element too large. Got {} bytes, block size is {}",
+                ELEMENT_HEADER_LENGTH + value.len(),
+                block_size
+            ),
+        ));
+    }
+
+    if len == capacity {
+        return Err(Error::new(
+            ErrorKind::OutOfMemory,
+            format!("Capacity of {} elements reached", capacity),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reads and removes the element at `pointer`, which must exist.
+fn pop_element<C: OramCipher>(
+    client: &mut ClientOram<C>,
+    oram: &mut Oram,
+    pointer: Pointer,
+) -> Result<(Option<Pointer>, Vec<u8>), Error> {
+    let bytes = client
+        .read_and_remove(oram, pointer.id, pointer.leaf)?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Element {} not found", pointer.id),
+            )
+        })?;
+
+    let (next, value) = decode_element(&bytes)?;
+    Ok((next, value.to_vec()))
+}
+
+/// LIFO stack stored in the ORAM, following the oblivious data structures of
+/// `https://eprint.iacr.org/2014/185`.
+///
+/// Each element holds the id and leaf of the element below it, the client
+/// only keeping those of the top. Elements are thus not in the position map.
+/// A push adds the new element to the stash, a pop reads and removes the top
+/// element from its path.
+///
+/// Every push or pop performs exactly one ORAM access, a push being padded
+/// with a dummy access which also evicts the stash. The client must be set up
+/// before creating the stack and is dedicated to it.
+pub struct ObliviousStack<V, C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    top: Option<Pointer>,
+    len: usize,
+    capacity: usize,
+    next_id: u64,
+    _values: PhantomData<V>,
+}
+
+impl<V, C> ObliviousStack<V, C>
+where
+    V: Serializable,
+    V::Error: Display,
+    C: OramCipher,
+{
+    pub fn new(client: ClientOram<C>, capacity: usize) -> ObliviousStack<V, C> {
+        ObliviousStack {
+            client,
+            top: None,
+            len: 0,
+            capacity,
+            next_id: 0,
+            _values: PhantomData,
+        }
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Pushes `value` on top of the stack.
+    ///
+    /// Fails with `ErrorKind::OutOfMemory` if the stack is full, the ORAM
+    /// being left untouched.
+    pub fn push(&mut self, oram: &mut Oram, value: &V) -> Result<(), Error> {
+        let value = serialize(value)?;
+        check_push(&self.client, self.len, self.capacity, &value)?;
+
+        let pointer = Pointer {
+            id: self.next_id,
+            leaf: self.client.random_leaf(),
+        };
+        self.client.add(
+            pointer.id,
+            pointer.leaf,
+            encode_element(self.top, &value),
+        )?;
+        self.next_id += 1;
+        self.top = Some(pointer);
+        self.len += 1;
+
+        self.client.dummy_access(oram)
+    }
+
+    /// Removes the top of the stack and returns it, if any.
+    pub fn pop(&mut self, oram: &mut Oram) -> Result<Option<V>, Error> {
+        let top = match self.top {
+            Some(top) => top,
+            None => {
+                self.client.dummy_access(oram)?;
+                return Ok(None);
+            }
+        };
+
+        let (next, value) = pop_element(&mut self.client, oram, top)?;
+        self.top = next;
+        self.len -= 1;
+
+        deserialize::<V>(&value).map(Some)
+    }
+}
+
+/// FIFO queue stored in the ORAM, following the oblivious data structures of
+/// `https://eprint.iacr.org/2014/185`.
+///
+/// Each element holds the id and leaf of the element enqueued after it. Those
+/// are drawn when the element is enqueued, so that no element has to be
+/// updated afterwards. The client only keeps the location of the head and
+/// the one reserved for the next element, elements not being in the position
+/// map.
+///
+/// Every enqueue or dequeue performs exactly one ORAM access, an enqueue
+/// being padded with a dummy access which also evicts the stash. The client
+/// must be set up before creating the queue and is dedicated to it.
+pub struct ObliviousQueue<V, C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    head: Pointer,
+    /// Location reserved for the next enqueued element.
+    tail: Pointer,
+    len: usize,
+    capacity: usize,
+    next_id: u64,
+    _values: PhantomData<V>,
+}
+
+impl<V, C> ObliviousQueue<V, C>
+where
+    V: Serializable,
+    V::Error: Display,
+    C: OramCipher,
+{
+    pub fn new(
+        mut client: ClientOram<C>,
+        capacity: usize,
+    ) -> ObliviousQueue<V, C> {
+        let tail = Pointer {
+            id: 0,
+            leaf: client.random_leaf(),
+        };
+
+        ObliviousQueue {
+            client,
+            head: tail,
+            tail,
+            len: 0,
+            capacity,
+            next_id: 1,
+            _values: PhantomData,
+        }
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds `value` at the back of the queue.
+    ///
+    /// Fails with `ErrorKind::OutOfMemory` if the queue is full, the ORAM
+    /// being left untouched.
+    pub fn enqueue(&mut self, oram: &mut Oram, value: &V) -> Result<(), Error> {
+        let value = serialize(value)?;
+        check_push(&self.client, self.len, self.capacity, &value)?;
+
+        let next = Pointer {
+            id: self.next_id,
+            leaf: self.client.random_leaf(),
+        };
+        self.client.add(
+            self.tail.id,
+            self.tail.leaf,
+            encode_element(Some(next), &value),
+        )?;
+        self.next_id += 1;
+        self.tail = next;
+        self.len += 1;
+
+        self.client.dummy_access(oram)
+    }
+
+    /// Removes the front of the queue and returns it, if any.
+    pub fn dequeue(&mut self, oram: &mut Oram) -> Result<Option<V>, Error> {
+        if self.len == 0 {
+            self.client.dummy_access(oram)?;
+            return Ok(None);
+        }
+
+        let (next, value) = pop_element(&mut self.client, oram, self.head)?;
+        self.head = next.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Queue element {} has no successor", self.head.id),
+            )
+        })?;
+        self.len -= 1;
+
+        deserialize::<V>(&value).map(Some)
+    }
+}
@@ -1,12 +1,7 @@
-mod btree;
-mod client;
-mod oram;
-mod oram_tests;
-
-use crate::{btree::DataItem, client::ClientOram};
 use cosmian_crypto_core::{reexport::rand_core::SeedableRng, CsRng};
+use path_oram::{client::ClientOram, oram::AccessType};
 use rand::RngCore;
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 
 fn main() -> Result<(), Error> {
     println!("Hello, Path-Oram!");
@@ -14,67 +9,66 @@ fn main() -> Result<(), Error> {
     /*
      * Implementation from https://eprint.iacr.org/2013/280.
      *
-     * Example of use for 183 items stored and a ciphertext size of 16 bytes.
+     * Example of use for 183 items stored and a block size of 16 bytes.
      * This means that there will be ceil(183/4) = 46 nodes to hold those
      * items which completes to 63 nodes for the tree. There will then be 32
      * leaves.
      */
     let nb_items: usize = 183;
-    let ct_size: usize = 16;
+    let block_size: usize = 16;
 
     /*
      * Client.
      */
-    let mut client = ClientOram::new(nb_items);
+    let mut client = ClientOram::new(nb_items)?;
+
+    let mut oram = client.setup_oram(block_size)?;
 
-    // Let's insert elements to position map.
+    // Let's insert blocks in the ORAM.
     let mut csprng = CsRng::from_entropy();
     // Arbitrary 26 insertions for the example.
     let nb_insertions = 26;
     let mut new_values = Vec::with_capacity(nb_insertions);
 
-    for _ in 0..nb_insertions {
-        let mut rand_value = vec![0; ct_size];
+    for block_id in 0..nb_insertions as u64 {
+        let mut rand_value = vec![0; block_size];
         csprng.fill_bytes(&mut rand_value);
-        let data_item = DataItem::new(rand_value.clone());
 
-        client.insert_element_in_position_map(&data_item);
+        // Each access reads a whole path and writes it back.
+        client.access(
+            &mut oram,
+            AccessType::Write,
+            block_id,
+            Some(rand_value.clone()),
+        )?;
 
-        new_values.push(data_item);
+        new_values.push(rand_value);
     }
 
-    let mut oram = client.setup_oram(ct_size)?;
-
-    let path = 22;
-    let mut read_data = client.read_from_path(&mut oram, path)?;
-
-    // Let's insert the new values upon writing.
-    client.write_to_path(&mut oram, &mut read_data, Some(new_values), path)?;
-
-    /*
-     * Let's make another read. Same path or different one, doesn't matter for
-     * the example.
-     */
-    read_data = client.read_from_path(&mut oram, path)?;
-
-    /* Changing an element in the values obtained */
+    /* Changing an element in the ORAM */
     /* -------------------------------------------*/
-    let idx_data_item_to_change = 6;
-
-    // First remove old element from position map.
-    client
-        .delete_element_from_position_map(&read_data[idx_data_item_to_change]);
+    let block_id_to_change = 6;
+
+    let mut data = client
+        .access(&mut oram, AccessType::Read, block_id_to_change, None)?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("Block {} not found", block_id_to_change),
+            )
+        })?;
+    assert_eq!(data, new_values[block_id_to_change as usize]);
 
     // Let's pretend the user changes the element.
-    let data_changed = read_data[idx_data_item_to_change].data_as_mut();
-    data_changed[0] = 255;
-
-    // Insert changed element into position map.
-    client.insert_element_in_position_map(&read_data[idx_data_item_to_change]);
+    data[0] = 255;
+
+    client.access(
+        &mut oram,
+        AccessType::Write,
+        block_id_to_change,
+        Some(data),
+    )?;
     /* -------------------------------------------*/
 
-    // Not inserting anything new this time.
-    client.write_to_path(&mut oram, &mut read_data, Option::None, path)?;
-
     Ok(())
 }
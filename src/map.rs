@@ -0,0 +1,354 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::{AccessType, Oram},
+};
+use cosmian_crypto_core::{
+    bytes_ser_de::{to_leb128_len, Deserializer, Serializable, Serializer},
+    Aes256Gcm, CryptoCoreError,
+};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+    marker::PhantomData,
+};
+
+/// Serialized key and value of an entry of the map.
+type Entry = (Vec<u8>, Vec<u8>);
+
+/// Key-value map stored in the ORAM.
+///
+/// Keys are hashed into `nb_probes` distinct candidate block ids among
+/// `nb_buckets`.
+/// Each block holds as many entries as its size allows, an entry being stored
+/// in any of its candidate blocks. This handles collisions and balances the
+/// load between blocks. A key may have outdated entries in candidate blocks
+/// probed before its current one, which supersedes them.
+///
+/// Every operation, whatever its kind and whether the key exists or not,
+/// accesses the `nb_probes` candidate blocks of the key exactly once. The
+/// server thus cannot tell a lookup from an insertion or a removal.
+///
+/// Keys are compared by their serialization, which must be deterministic. The
+/// client shall be instantiated with a capacity of at least `nb_buckets`
+/// blocks.
+pub struct ObliviousMap<K, V, C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    nb_buckets: u64,
+    nb_probes: usize,
+    _entries: PhantomData<(K, V)>,
+}
+
+impl<K, V, C> ObliviousMap<K, V, C>
+where
+    K: Serializable,
+    V: Serializable,
+    K::Error: Display,
+    V::Error: Display,
+    C: OramCipher,
+{
+    pub fn new(
+        client: ClientOram<C>,
+        nb_buckets: u64,
+        nb_probes: usize,
+    ) -> Result<ObliviousMap<K, V, C>, Error> {
+        if nb_buckets == 0 || nb_probes == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Number of buckets and probes shall not be null".to_string(),
+            ));
+        }
+
+        // Candidate buckets of a key are distinct.
+        if nb_probes as u64 > nb_buckets {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Too many probes. Got {}, expected at most {} buckets",
+                    nb_probes, nb_buckets
+                ),
+            ));
+        }
+
+        Ok(ObliviousMap {
+            client,
+            nb_buckets,
+            nb_probes,
+            _entries: PhantomData,
+        })
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut ClientOram<C> {
+        &mut self.client
+    }
+
+    pub fn into_client(self) -> ClientOram<C> {
+        self.client
+    }
+
+    /// Block ids of the candidate buckets of a serialized key, all distinct.
+    fn bucket_ids(&self, key: &[u8]) -> Vec<u64> {
+        /*
+         * Double hashing: the step is coprime with the number of buckets, so
+         * that the first `nb_buckets` probes all land in distinct buckets.
+         */
+        let start = hash(key, 0) % self.nb_buckets;
+        let mut step = match self.nb_buckets {
+            1 => 1,
+            nb_buckets => 1 + hash(key, 1) % (nb_buckets - 1),
+        };
+        while gcd(step, self.nb_buckets) != 1 {
+            step += 1;
+        }
+
+        (0..self.nb_probes as u64)
+            .map(|probe| {
+                ((start as u128 + probe as u128 * step as u128)
+                    % self.nb_buckets as u128) as u64
+            })
+            .collect()
+    }
+
+    /// Returns the value of `key`, if any.
+    pub fn get(
+        &mut self,
+        oram: &mut Oram,
+        key: &K,
+    ) -> Result<Option<V>, Error> {
+        let key = serialize(key)?;
+
+        let mut value = None;
+        for bucket_id in self.bucket_ids(&key) {
+            let bucket =
+                self.client
+                    .access(oram, AccessType::Read, bucket_id, None)?;
+
+            // Keep on probing not to reveal where the entry was found.
+            if let Some(bucket) = bucket {
+                if let Some((_, v)) = decode_entries(&bucket)?
+                    .into_iter()
+                    .find(|(k, _)| *k == key)
+                {
+                    value = Some(v);
+                }
+            }
+        }
+
+        value.map(|value| deserialize::<V>(&value)).transpose()
+    }
+
+    pub fn contains(
+        &mut self,
+        oram: &mut Oram,
+        key: &K,
+    ) -> Result<bool, Error> {
+        Ok(self.get(oram, key)?.is_some())
+    }
+
+    /// Inserts `value` for `key` and returns the previous value, if any.
+    ///
+    /// Fails with `ErrorKind::OutOfMemory` if no candidate block has room for
+    /// the entry, the map being left unchanged.
+    pub fn insert(
+        &mut self,
+        oram: &mut Oram,
+        key: &K,
+        value: &V,
+    ) -> Result<Option<V>, Error> {
+        let key = serialize(key)?;
+        let value = serialize(value)?;
+
+        let block_size = self.client.block_size();
+        let entry_length = entry_length(&key, &value);
+        if to_leb128_len(1) + entry_length > block_size {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Entry too large. Got {} bytes, block size is {}",
+                    entry_length, block_size
+                ),
+            ));
+        }
+
+        let mut old_entry = None;
+        let mut new_entry = Some((key.clone(), value));
+        for bucket_id in self.bucket_ids(&key) {
+            /*
+             * The new entry is written in the first bucket having room for it
+             * and previous entries are removed from the buckets probed next.
+             * A previous entry is kept where the new one does not fit, until
+             * it is placed: if no bucket has room, the map is left unchanged
+             * without any additional access, and the entry in the last probed
+             * bucket is otherwise the current one.
+             */
+            self.client.update(oram, bucket_id, |bucket| {
+                let mut entries = match bucket {
+                    Some(bucket) => decode_entries(bucket)?,
+                    None => Vec::new(),
+                };
+
+                let found = entries
+                    .iter()
+                    .position(|(k, _)| *k == key)
+                    .map(|i| (i, entries.remove(i)));
+
+                if let Some(entry) = new_entry.take() {
+                    entries.push(entry);
+                    if encoded_length(&entries) > block_size {
+                        new_entry = entries.pop();
+                        if let Some((i, entry)) = &found {
+                            entries.insert(*i, entry.clone());
+                        }
+                    }
+                }
+
+                if let Some((_, entry)) = found {
+                    old_entry = Some(entry);
+                }
+
+                encode_entries(&entries)
+            })?;
+        }
+
+        if new_entry.is_some() {
+            return Err(Error::new(
+                ErrorKind::OutOfMemory,
+                format!(
+                    "Map buckets full. All {} candidate buckets of the key \
+                     are full",
+                    self.nb_probes
+                ),
+            ));
+        }
+
+        old_entry
+            .map(|(_, value)| deserialize::<V>(&value))
+            .transpose()
+    }
+
+    /// Removes `key` and returns its value, if any.
+    pub fn remove(
+        &mut self,
+        oram: &mut Oram,
+        key: &K,
+    ) -> Result<Option<V>, Error> {
+        let key = serialize(key)?;
+
+        let mut old_value = None;
+        for bucket_id in self.bucket_ids(&key) {
+            self.client.update(oram, bucket_id, |bucket| {
+                let mut entries = match bucket {
+                    Some(bucket) => decode_entries(bucket)?,
+                    None => Vec::new(),
+                };
+
+                if let Some(i) = entries.iter().position(|(k, _)| *k == key) {
+                    old_value = Some(entries.remove(i).1);
+                }
+
+                encode_entries(&entries)
+            })?;
+        }
+
+        old_value.map(|value| deserialize::<V>(&value)).transpose()
+    }
+}
+
+/// FNV-1a of a serialized key, salted with `seed`. Block ids are never
+/// revealed to the server, the hash only has to spread keys evenly and to be
+/// stable across restores.
+fn hash(key: &[u8], seed: u64) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in seed.to_le_bytes().iter().chain(key) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a
+}
+
+pub(crate) fn serialize<T>(object: &T) -> Result<Vec<u8>, Error>
+where
+    T: Serializable,
+    T::Error: Display,
+{
+    object
+        .serialize()
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))
+}
+
+pub(crate) fn deserialize<T>(bytes: &[u8]) -> Result<T, Error>
+where
+    T: Serializable,
+    T::Error: Display,
+{
+    T::deserialize(bytes)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+fn entry_length(key: &[u8], value: &[u8]) -> usize {
+    to_leb128_len(key.len())
+        + key.len()
+        + to_leb128_len(value.len())
+        + value.len()
+}
+
+fn encoded_length(entries: &[Entry]) -> usize {
+    to_leb128_len(entries.len())
+        + entries
+            .iter()
+            .map(|(key, value)| entry_length(key, value))
+            .sum::<usize>()
+}
+
+/// Encodes the entries of a bucket. Empty buckets are removed from the ORAM.
+fn encode_entries(entries: &[Entry]) -> Result<Option<Vec<u8>>, Error> {
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    let to_error = |e: CryptoCoreError| {
+        Error::new(ErrorKind::Interrupted, format!("Invalid map bucket: {}", e))
+    };
+
+    let mut ser = Serializer::with_capacity(encoded_length(entries));
+    ser.write_leb128_u64(entries.len() as u64)
+        .map_err(to_error)?;
+    for (key, value) in entries {
+        ser.write_vec(key).map_err(to_error)?;
+        ser.write_vec(value).map_err(to_error)?;
+    }
+
+    Ok(Some(ser.finalize().to_vec()))
+}
+
+fn decode_entries(bucket: &[u8]) -> Result<Vec<Entry>, Error> {
+    let to_error = |e: CryptoCoreError| {
+        Error::new(ErrorKind::InvalidData, format!("Invalid map bucket: {}", e))
+    };
+
+    let mut de = Deserializer::new(bucket);
+    let nb_entries = de.read_leb128_u64().map_err(to_error)?;
+
+    let mut entries = Vec::new();
+    for _ in 0..nb_entries {
+        let key = de.read_vec().map_err(to_error)?;
+        let value = de.read_vec().map_err(to_error)?;
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
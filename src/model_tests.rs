@@ -0,0 +1,229 @@
+#[cfg(test)]
+mod tests {
+    use proptest::{
+        collection::{btree_map, vec},
+        option,
+        prelude::*,
+        test_runner::TestCaseError,
+    };
+    use std::collections::{BTreeMap, HashMap, HashSet};
+
+    use crate::{
+        block::Block,
+        btree::{DataItem, Node},
+        client::ClientOram,
+        oram::{AccessType, Oram, BUCKET_SIZE},
+    };
+
+    const NB_ITEMS: usize = 32;
+    /// Block ids are drawn among fewer ids than items, so that blocks are
+    /// often accessed again, written over and removed.
+    const NB_IDS: u64 = 24;
+    const BLOCK_SIZE: usize = 16;
+
+    /// Operation of the client, applied to both the ORAM and the model.
+    #[derive(Debug, Clone)]
+    enum Op {
+        Read(u64),
+        Write(u64, Vec<u8>),
+        Remove(u64),
+        Dummy,
+        /// Reads, or writes if given a payload, of distinct blocks.
+        Batch(BTreeMap<u64, Option<Vec<u8>>>),
+    }
+
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            3 => (0..NB_IDS).prop_map(Op::Read),
+            3 => (0..NB_IDS, vec(any::<u8>(), 0..=BLOCK_SIZE))
+                .prop_map(|(id, data)| Op::Write(id, data)),
+            1 => (0..NB_IDS).prop_map(Op::Remove),
+            1 => Just(Op::Dummy),
+            1 => btree_map(
+                0..NB_IDS,
+                option::of(vec(any::<u8>(), 0..=BLOCK_SIZE)),
+                1..8
+            )
+            .prop_map(Op::Batch),
+        ]
+    }
+
+    /// Collects the items of the subtree of `node`, which has index `index`
+    /// when buckets are indexed as a binary heap, the root being 1.
+    fn _tree_items(
+        node: Option<&Node>,
+        index: usize,
+        items: &mut Vec<(usize, DataItem)>,
+    ) {
+        if let Some(node) = node {
+            for item in node.bucket() {
+                items.push((index, item.clone()));
+            }
+            _tree_items(node.left.as_deref(), 2 * index, items);
+            _tree_items(node.right.as_deref(), 2 * index + 1, items);
+        }
+    }
+
+    /// Checks that the real blocks of the tree and the stash are exactly the
+    /// blocks of `model`, each of them once, on the path of its leaf in the
+    /// position map.
+    fn _check_invariants(
+        client: &ClientOram,
+        path_oram: &mut Oram,
+        model: &HashMap<u64, Vec<u8>>,
+    ) -> Result<(), TestCaseError> {
+        let height = path_oram.tree().height() as u32;
+        let nb_leaves = 1 << (height - 1);
+
+        let mut tree_items = Vec::new();
+        _tree_items(path_oram.tree().root.as_deref(), 1, &mut tree_items);
+        prop_assert_eq!(tree_items.len(), ((1 << height) - 1) * BUCKET_SIZE);
+
+        let (buckets, mut items): (Vec<usize>, Vec<DataItem>) =
+            tree_items.into_iter().unzip();
+        client.decrypt_items(&mut items).unwrap();
+
+        // Blocks of the stash are in no bucket.
+        let mut stash = client.stash.clone();
+        stash.append(&mut path_oram.read_stash());
+        client.decrypt_items(&mut stash).unwrap();
+
+        let mut seen = HashSet::new();
+        let located = buckets
+            .into_iter()
+            .map(Some)
+            .zip(items)
+            .chain(std::iter::repeat(None).zip(stash));
+        for (bucket, item) in located {
+            let block = match Block::decode(&item).unwrap() {
+                Some(block) => block,
+                None => continue,
+            };
+
+            prop_assert!(seen.insert(block.id()), "{} twice", block.id());
+            prop_assert_eq!(
+                Some(block.payload()),
+                model.get(&block.id()).map(Vec::as_slice)
+            );
+
+            let leaf = client.position_map.get(&block.id()).copied();
+            prop_assert_eq!(leaf, Some(block.leaf()));
+
+            if let Some(bucket) = bucket {
+                let level = bucket.ilog2();
+                prop_assert_eq!(
+                    (nb_leaves + block.leaf()) >> (height - 1 - level),
+                    bucket,
+                    "Block {} out of the path of its leaf",
+                    block.id()
+                );
+            }
+        }
+
+        prop_assert_eq!(seen.len(), model.len());
+        prop_assert_eq!(client.position_map.len(), model.len());
+        prop_assert!(client.stash.len() <= client.params().stash_bound());
+
+        Ok(())
+    }
+
+    fn _run_model(
+        ops: Vec<Op>,
+        outsourced_stash: Option<usize>,
+        tree_top_levels: u16,
+    ) -> Result<(), TestCaseError> {
+        let mut client = ClientOram::new(NB_ITEMS).unwrap();
+        if let Some(capacity) = outsourced_stash {
+            client.outsource_stash(capacity);
+        }
+        let mut path_oram = client.setup_oram(BLOCK_SIZE).unwrap();
+        client
+            .cache_tree_top(&mut path_oram, tree_top_levels)
+            .unwrap();
+        let mut model: HashMap<u64, Vec<u8>> = HashMap::new();
+
+        for op in ops {
+            match op {
+                Op::Read(id) => {
+                    let value = client
+                        .access(&mut path_oram, AccessType::Read, id, None)
+                        .unwrap();
+                    prop_assert_eq!(value.as_ref(), model.get(&id));
+                }
+                Op::Write(id, data) => {
+                    let previous = client
+                        .access(
+                            &mut path_oram,
+                            AccessType::Write,
+                            id,
+                            Some(data.clone()),
+                        )
+                        .unwrap();
+                    prop_assert_eq!(previous, model.insert(id, data));
+                }
+                Op::Remove(id) => {
+                    let removed = client.remove(&mut path_oram, id).unwrap();
+                    prop_assert_eq!(removed, model.remove(&id));
+                }
+                Op::Dummy => client.dummy_access(&mut path_oram).unwrap(),
+                Op::Batch(requests) => {
+                    let mut expected = Vec::with_capacity(requests.len());
+                    let requests = requests
+                        .into_iter()
+                        .map(|(id, data)| {
+                            expected.push(match &data {
+                                Some(data) => model.insert(id, data.clone()),
+                                None => model.get(&id).cloned(),
+                            });
+
+                            match data {
+                                Some(data) => {
+                                    (AccessType::Write, id, Some(data))
+                                }
+                                None => (AccessType::Read, id, None),
+                            }
+                        })
+                        .collect();
+
+                    let values =
+                        client.access_batch(&mut path_oram, requests).unwrap();
+                    prop_assert_eq!(values, expected);
+                }
+            }
+
+            prop_assert!(client.verify_invariants(&mut path_oram).is_ok());
+            // Cached buckets are out of reach of the independent checks.
+            if tree_top_levels == 0 {
+                _check_invariants(&client, &mut path_oram, &model)?;
+            }
+        }
+
+        client.cache_tree_top(&mut path_oram, 0).unwrap();
+        _check_invariants(&client, &mut path_oram, &model)?;
+
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn model_local_stash(ops in vec(op_strategy(), 1..150)) {
+            _run_model(ops, None, 0)?;
+        }
+
+        /*
+         * The stash cannot hold more blocks than there are ids, so that the
+         * outsourced stash never overflows.
+         */
+        #[test]
+        fn model_outsourced_stash(ops in vec(op_strategy(), 1..150)) {
+            _run_model(ops, Some(NB_IDS as usize), 0)?;
+        }
+
+        #[test]
+        fn model_tree_top_cache(ops in vec(op_strategy(), 1..150)) {
+            _run_model(ops, None, 2)?;
+        }
+    }
+}
@@ -0,0 +1,285 @@
+#[cfg(test)]
+mod tests {
+    use cosmian_crypto_core::{reexport::rand_core::SeedableRng, CsRng};
+    use rand::Rng;
+
+    use crate::{
+        client::ClientOram,
+        oram::AccessType,
+        trace::{MemoryTrace, TraceEvent, TraceOp},
+    };
+
+    /*
+     * The client being seeded, every test is deterministic. Statistics are
+     * compared to critical values at a significance of about 10^-6, so that
+     * changing a seed does not make a test fail spuriously while a biased
+     * distribution of leaves is still rejected.
+     */
+    const NB_ITEMS: usize = 64;
+    const NB_LEAVES: usize = 16;
+    const NB_ACCESSES: u64 = 4000;
+    /// Quantile of the standard normal distribution for 10^-6.
+    const Z_CRITICAL: f64 = 4.753;
+
+    /// Logical sequence of block ids accessed by the client.
+    enum Sequence {
+        Sequential,
+        SameBlock,
+        Random,
+    }
+
+    impl Sequence {
+        fn block_ids(&self, nb_accesses: u64, rng: &mut CsRng) -> Vec<u64> {
+            (0..nb_accesses)
+                .map(|i| match self {
+                    Sequence::Sequential => i % NB_ITEMS as u64,
+                    Sequence::SameBlock => 7,
+                    Sequence::Random => rng.gen_range(0..NB_ITEMS as u64),
+                })
+                .collect()
+        }
+    }
+
+    /// Leaves read by the server while the client accesses `sequence`.
+    fn _leaf_trace(sequence: Sequence, seed: u8) -> Vec<TraceEvent> {
+        let mut client: ClientOram =
+            ClientOram::with_seed(NB_ITEMS, [seed; 32]).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+
+        // Every block is written once before the trace is recorded.
+        for block_id in 0..NB_ITEMS as u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![block_id as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        let mut rng = CsRng::seed_from_u64(seed as u64);
+        for (i, block_id) in sequence
+            .block_ids(NB_ACCESSES, &mut rng)
+            .into_iter()
+            .enumerate()
+        {
+            let op = match i % 2 {
+                0 => AccessType::Read,
+                _ => AccessType::Write,
+            };
+            let data = match op {
+                AccessType::Read => None,
+                AccessType::Write => Some(vec![i as u8; 16]),
+            };
+
+            client.access(&mut path_oram, op, block_id, data).unwrap();
+        }
+
+        trace.take()
+    }
+
+    /// Leaves of the path reads of `events`, checking that each path written
+    /// back is the path just read.
+    fn _read_leaves(events: &[TraceEvent]) -> Vec<usize> {
+        assert_eq!(events.len() % 2, 0);
+
+        events
+            .chunks(2)
+            .map(|access| {
+                assert_eq!(access[0].op, TraceOp::ReadPath);
+                assert_eq!(access[1].op, TraceOp::WritePath);
+                assert_eq!(access[0].path, access[1].path);
+                assert_eq!(access[0].nb_items, access[1].nb_items);
+                assert_eq!(access[0].nb_bytes, access[1].nb_bytes);
+
+                access[0].path.unwrap()
+            })
+            .collect()
+    }
+
+    fn _histogram(leaves: &[usize], nb_leaves: usize) -> Vec<u64> {
+        let mut histogram = vec![0; nb_leaves];
+        for leaf in leaves {
+            histogram[*leaf] += 1;
+        }
+
+        histogram
+    }
+
+    /// Critical value of the chi-squared distribution with `df` degrees of
+    /// freedom, from the Wilson-Hilferty approximation.
+    fn _chi_squared_critical(df: usize) -> f64 {
+        let df = df as f64;
+        let variance = 2.0 / (9.0 * df);
+
+        df * (1.0 - variance + Z_CRITICAL * variance.sqrt()).powi(3)
+    }
+
+    /// Pearson statistic of `histogram` against the uniform distribution.
+    fn _chi_squared_uniform(histogram: &[u64]) -> f64 {
+        let total: u64 = histogram.iter().sum();
+        let expected = total as f64 / histogram.len() as f64;
+
+        histogram
+            .iter()
+            .map(|count| (*count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    /// Pearson statistic of the homogeneity of two histograms, that is of
+    /// the hypothesis that both samples come from the same distribution.
+    fn _chi_squared_homogeneity(first: &[u64], second: &[u64]) -> f64 {
+        let first_total: u64 = first.iter().sum();
+        let second_total: u64 = second.iter().sum();
+        let total = (first_total + second_total) as f64;
+
+        let mut statistic = 0.0;
+        for (first_count, second_count) in first.iter().zip(second) {
+            let column = (first_count + second_count) as f64;
+            if column == 0.0 {
+                continue;
+            }
+
+            for (count, row) in
+                [(first_count, first_total), (second_count, second_total)]
+            {
+                let expected = column * row as f64 / total;
+                statistic += (*count as f64 - expected).powi(2) / expected;
+            }
+        }
+
+        statistic
+    }
+
+    /// Kolmogorov-Smirnov statistic of two samples of leaves, the largest
+    /// distance between their empirical distribution functions.
+    fn _ks_two_samples(first: &[u64], second: &[u64]) -> f64 {
+        let first_total: u64 = first.iter().sum();
+        let second_total: u64 = second.iter().sum();
+
+        let mut first_cdf = 0.0;
+        let mut second_cdf = 0.0;
+        let mut distance: f64 = 0.0;
+        for (first_count, second_count) in first.iter().zip(second) {
+            first_cdf += *first_count as f64 / first_total as f64;
+            second_cdf += *second_count as f64 / second_total as f64;
+            distance = distance.max((first_cdf - second_cdf).abs());
+        }
+
+        distance
+    }
+
+    /// Critical value of the Kolmogorov-Smirnov statistic for samples of
+    /// `n` and `m` values, at a significance of 10^-6.
+    fn _ks_critical(n: usize, m: usize) -> f64 {
+        let c = (-(1e-6_f64 / 2.0).ln() / 2.0).sqrt();
+
+        c * ((n + m) as f64 / (n * m) as f64).sqrt()
+    }
+
+    fn _assert_uniform(leaves: &[usize], nb_leaves: usize) {
+        let histogram = _histogram(leaves, nb_leaves);
+        let statistic = _chi_squared_uniform(&histogram);
+        let critical = _chi_squared_critical(nb_leaves - 1);
+
+        assert!(
+            statistic < critical,
+            "Leaves not uniform: chi-squared {} >= {}, histogram {:?}",
+            statistic,
+            critical,
+            histogram
+        );
+    }
+
+    #[test]
+    fn statistics_reject_biased_leaves() {
+        let mut rng = CsRng::seed_from_u64(0);
+        let uniform: Vec<usize> = (0..NB_ACCESSES)
+            .map(|_| rng.gen_range(0..NB_LEAVES))
+            .collect();
+        _assert_uniform(&uniform, NB_LEAVES);
+
+        // Even leaves are read more often than odd ones.
+        let biased: Vec<usize> = (0..NB_ACCESSES)
+            .map(|i| match i % 4 {
+                0 => 2 * rng.gen_range(0..NB_LEAVES / 2),
+                _ => rng.gen_range(0..NB_LEAVES),
+            })
+            .collect();
+        let biased_histogram = _histogram(&biased, NB_LEAVES);
+        assert!(
+            _chi_squared_uniform(&biased_histogram)
+                > _chi_squared_critical(NB_LEAVES - 1)
+        );
+
+        // Leaves only read in the lower half of the tree.
+        let uniform_histogram = _histogram(&uniform, NB_LEAVES);
+        let low: Vec<usize> = (0..NB_ACCESSES)
+            .map(|_| rng.gen_range(0..NB_LEAVES / 2))
+            .collect();
+        let low_histogram = _histogram(&low, NB_LEAVES);
+        let n = NB_ACCESSES as usize;
+        assert!(
+            _chi_squared_homogeneity(&uniform_histogram, &low_histogram)
+                > _chi_squared_critical(NB_LEAVES - 1)
+        );
+        assert!(
+            _ks_two_samples(&uniform_histogram, &low_histogram)
+                > _ks_critical(n, n)
+        );
+    }
+
+    #[test]
+    fn leaves_uniform_for_any_sequence() {
+        for (sequence, seed) in [
+            (Sequence::Sequential, 1),
+            (Sequence::SameBlock, 2),
+            (Sequence::Random, 3),
+        ] {
+            let leaves = _read_leaves(&_leaf_trace(sequence, seed));
+            assert_eq!(leaves.len(), NB_ACCESSES as usize);
+            _assert_uniform(&leaves, NB_LEAVES);
+        }
+    }
+
+    #[test]
+    fn leaves_independent_of_sequence() {
+        let sequential = _read_leaves(&_leaf_trace(Sequence::Sequential, 4));
+        let same_block = _read_leaves(&_leaf_trace(Sequence::SameBlock, 5));
+        let random = _read_leaves(&_leaf_trace(Sequence::Random, 6));
+
+        let n = NB_ACCESSES as usize;
+        let critical = _chi_squared_critical(NB_LEAVES - 1);
+        for (first, second) in [
+            (&sequential, &same_block),
+            (&sequential, &random),
+            (&same_block, &random),
+        ] {
+            let first = _histogram(first, NB_LEAVES);
+            let second = _histogram(second, NB_LEAVES);
+
+            assert!(_chi_squared_homogeneity(&first, &second) < critical);
+            assert!(_ks_two_samples(&first, &second) < _ks_critical(n, n));
+        }
+    }
+
+    #[test]
+    fn consecutive_leaves_independent() {
+        /*
+         * Accessing the same block again reads the leaf it was remapped to
+         * during the previous access, which must not be related to the leaf
+         * it was read from. Pairs of consecutive leaves are thus uniform
+         * over all pairs.
+         */
+        let leaves = _read_leaves(&_leaf_trace(Sequence::SameBlock, 7));
+        let pairs: Vec<usize> = leaves
+            .windows(2)
+            .map(|pair| pair[0] * NB_LEAVES + pair[1])
+            .collect();
+        _assert_uniform(&pairs, NB_LEAVES * NB_LEAVES);
+    }
+}
@@ -1,5 +1,12 @@
-use crate::btree::{BTree, DataItem, Node};
-use std::io::{Error, ErrorKind};
+use crate::{
+    btree::{BTree, DataItem, Node},
+    params::OramParams,
+    trace::{TraceOp, TraceSink, Tracer},
+};
+use std::{
+    collections::BTreeSet,
+    io::{Error, ErrorKind},
+};
 
 pub const BUCKET_SIZE: usize = 4;
 
@@ -10,6 +17,12 @@ pub enum AccessType {
 
 pub struct Oram {
     tree: BTree,
+    /// Encrypted stash uploaded by clients which do not keep it locally.
+    stash: Vec<DataItem>,
+    /// Encrypted state of the clients sharing the tree, if any.
+    client_state: Option<DataItem>,
+    /// Recorder of the requests served, for obliviousness audits.
+    tracer: Option<Tracer>,
 }
 
 impl Oram {
@@ -17,16 +30,25 @@ impl Oram {
         data_items: &mut Vec<DataItem>,
         nb_items: usize,
     ) -> Result<Oram, Error> {
-        if nb_items == 0 {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Number of items shall not be null".to_string(),
-            ));
-        }
+        // The server does not need the block size.
+        Ok(Oram::with_params(
+            data_items,
+            &OramParams::new(nb_items, 0)?,
+        ))
+    }
 
-        Ok(Oram {
-            tree: BTree::init_new(data_items, nb_items),
-        })
+    /// Builds the tree shaped by `params`, its buckets being filled from
+    /// `data_items` as a stack.
+    pub fn with_params(
+        data_items: &mut Vec<DataItem>,
+        params: &OramParams,
+    ) -> Oram {
+        Oram {
+            tree: BTree::init_new(data_items, params),
+            stash: Vec::new(),
+            client_state: None,
+            tracer: None,
+        }
     }
 
     pub fn access(
@@ -51,19 +73,35 @@ impl Oram {
                 let mut path_items = Vec::new();
 
                 Oram::read_path(
-                    self.tree.root.as_ref(),
+                    self.tree.root.as_deref(),
                     &mut path_items,
                     path,
                     self.tree.height(),
                     0,
                 );
 
+                if let Some(tracer) = self.tracer.as_mut() {
+                    tracer.record(
+                        TraceOp::ReadPath,
+                        Some((path, self.tree.height())),
+                        &path_items,
+                    );
+                }
+
                 // Returning values from tree visit.
                 Ok(Some(path_items))
             }
             AccessType::Write => {
                 if let Some(data) = data {
                     let tree_height = self.tree.height();
+                    if let Some(tracer) = self.tracer.as_mut() {
+                        tracer.record(
+                            TraceOp::WritePath,
+                            Some((path, tree_height)),
+                            data.iter().flatten(),
+                        );
+                    }
+
                     Oram::write_path(
                         self.tree.root.as_mut(),
                         data,
@@ -85,7 +123,7 @@ impl Oram {
     }
 
     fn read_path(
-        node: Option<&Box<Node>>,
+        node: Option<&Node>,
         path_data: &mut Vec<DataItem>,
         path: usize,
         height: u16,
@@ -109,9 +147,9 @@ impl Oram {
                 bit_shift = 0;
             }
 
-            if (path >> bit_shift) % 2 == 0 {
+            if (path >> bit_shift) & 1 == 0 {
                 Oram::read_path(
-                    node.left.as_ref(),
+                    node.left.as_deref(),
                     path_data,
                     path,
                     height,
@@ -119,7 +157,7 @@ impl Oram {
                 );
             } else {
                 Oram::read_path(
-                    node.right.as_ref(),
+                    node.right.as_deref(),
                     path_data,
                     path,
                     height,
@@ -151,7 +189,7 @@ impl Oram {
                 bit_shift = 0;
             }
 
-            if (path >> bit_shift) % 2 == 0 {
+            if (path >> bit_shift) & 1 == 0 {
                 Oram::write_path(
                     node.left.as_mut(),
                     path_data,
@@ -180,7 +218,236 @@ impl Oram {
         }
     }
 
+    /// Reads the buckets of all `paths` at once, a bucket shared by several
+    /// paths being read once. Returns the buckets along with their index,
+    /// the root being 1 and the children of bucket `i` being `2i` and
+    /// `2i + 1`, in increasing order of index.
+    pub fn read_paths(
+        &mut self,
+        paths: &[usize],
+    ) -> Result<Vec<(usize, [DataItem; BUCKET_SIZE])>, Error> {
+        let height = self.tree.height() as u32;
+        let nb_leaves = 1 << (height - 1);
+
+        let mut indices = BTreeSet::new();
+        for &path in paths {
+            if path >= nb_leaves {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid path access. Got {}, expected in range 0..{}",
+                        path,
+                        nb_leaves - 1
+                    ),
+                ));
+            }
+
+            indices
+                .extend((0..height).map(|level| (nb_leaves + path) >> level));
+        }
+
+        let indices: Vec<usize> = indices.into_iter().collect();
+        let buckets = self.read_buckets(&indices)?;
+
+        Ok(indices.into_iter().zip(buckets).collect())
+    }
+
+    /// Reads the buckets `indices`, indexed as by `read_paths`, in the given
+    /// order.
+    pub fn read_buckets(
+        &mut self,
+        indices: &[usize],
+    ) -> Result<Vec<[DataItem; BUCKET_SIZE]>, Error> {
+        let mut buckets = Vec::with_capacity(indices.len());
+        for &index in indices {
+            let node = self.node(index).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Invalid bucket. Got {}, expected in range 1..{}",
+                        index,
+                        (1 << self.tree.height()) - 1
+                    ),
+                )
+            })?;
+            buckets.push(node.bucket().clone());
+        }
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record_buckets(
+                TraceOp::ReadBuckets,
+                indices.to_vec(),
+                buckets.iter().flatten(),
+            );
+        }
+
+        Ok(buckets)
+    }
+
+    /// Overwrites each bucket of `buckets` at its index, as returned by
+    /// `read_paths`. No bucket is written if any index is invalid.
+    pub fn write_buckets(
+        &mut self,
+        buckets: Vec<(usize, [DataItem; BUCKET_SIZE])>,
+    ) -> Result<(), Error> {
+        let nb_buckets = (1 << self.tree.height()) - 1;
+        if let Some((index, _)) = buckets
+            .iter()
+            .find(|(index, _)| *index == 0 || *index > nb_buckets)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid bucket. Got {}, expected in range 1..{}",
+                    index, nb_buckets
+                ),
+            ));
+        }
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record_buckets(
+                TraceOp::WriteBuckets,
+                buckets.iter().map(|(index, _)| *index).collect(),
+                buckets.iter().flat_map(|(_, bucket)| bucket),
+            );
+        }
+
+        for (index, bucket) in buckets {
+            // The tree is complete, all valid buckets exist.
+            if let Some(node) = self.node_mut(index) {
+                node.set_bucket(bucket);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Node of the bucket `index`, following the bits of `index` below its
+    /// most significant one from the root, 0 going left.
+    fn node(&self, index: usize) -> Option<&Node> {
+        if index == 0 {
+            return None;
+        }
+
+        let mut node = self.tree.root.as_deref();
+        for shift in (0..index.ilog2()).rev() {
+            node = match (index >> shift) & 1 {
+                0 => node?.left.as_deref(),
+                _ => node?.right.as_deref(),
+            };
+        }
+
+        node
+    }
+
+    fn node_mut(&mut self, index: usize) -> Option<&mut Node> {
+        if index == 0 {
+            return None;
+        }
+
+        let mut node = self.tree.root.as_deref_mut();
+        for shift in (0..index.ilog2()).rev() {
+            node = match (index >> shift) & 1 {
+                0 => node?.left.as_deref_mut(),
+                _ => node?.right.as_deref_mut(),
+            };
+        }
+
+        node
+    }
+
+    /// Adds a level of leaves to the tree, filled with `data_items`. Paths
+    /// are one bit longer afterwards, path `p` becoming paths `2p` and
+    /// `2p + 1`.
+    pub fn grow(
+        &mut self,
+        data_items: &mut Vec<DataItem>,
+    ) -> Result<(), Error> {
+        let nb_slots = (1 << self.tree.height()) * BUCKET_SIZE;
+        if data_items.len() != nb_slots {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid number of items to grow the tree. Got {}, \
+                     expected {}",
+                    data_items.len(),
+                    nb_slots
+                ),
+            ));
+        }
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceOp::Grow, None, data_items.iter());
+        }
+
+        self.tree.grow(data_items);
+
+        Ok(())
+    }
+
+    /// Removes the level of leaves of the tree and returns their items. Paths
+    /// are one bit shorter afterwards, paths `2p` and `2p + 1` becoming path
+    /// `p`.
+    pub fn shrink(&mut self) -> Result<Vec<DataItem>, Error> {
+        if self.tree.height() < 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Tree cannot be shrunk below its root".to_string(),
+            ));
+        }
+
+        let leaf_items = self.tree.shrink();
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceOp::Shrink, None, &leaf_items);
+        }
+
+        Ok(leaf_items)
+    }
+
     pub fn tree(&self) -> &BTree {
         &self.tree
     }
+
+    pub fn read_stash(&mut self) -> Vec<DataItem> {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceOp::ReadStash, None, &self.stash);
+        }
+
+        self.stash.clone()
+    }
+
+    pub fn write_stash(&mut self, stash: Vec<DataItem>) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceOp::WriteStash, None, &stash);
+        }
+
+        self.stash = stash;
+    }
+
+    pub fn read_client_state(&mut self) -> Option<DataItem> {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceOp::ReadState, None, &self.client_state);
+        }
+
+        self.client_state.clone()
+    }
+
+    pub fn write_client_state(&mut self, state: DataItem) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(TraceOp::WriteState, None, [&state]);
+        }
+
+        self.client_state = Some(state);
+    }
+
+    /// Records every request served from now on into `sink`, timestamps
+    /// being relative to this call. Replaces the previous sink, if any.
+    pub fn set_trace_sink<S: TraceSink + 'static>(&mut self, sink: S) {
+        self.tracer = Some(Tracer::new(Box::new(sink)));
+    }
+
+    /// Stops recording requests and returns the sink, if any.
+    pub fn take_trace_sink(&mut self) -> Option<Box<dyn TraceSink>> {
+        self.tracer.take().map(Tracer::into_sink)
+    }
 }
@@ -1,19 +1,39 @@
 #[cfg(test)]
 mod tests {
-    use cosmian_crypto_core::{reexport::rand_core::SeedableRng, CsRng};
-    use rand::RngCore;
+    use cosmian_crypto_core::{
+        bytes_ser_de::{to_leb128_len, Deserializer, Serializable, Serializer},
+        reexport::rand_core::SeedableRng,
+        Aes256Gcm, ChaCha20Poly1305, CryptoCoreError, CsRng,
+    };
+    use rand::{seq::SliceRandom, RngCore};
+    use std::{
+        collections::BTreeMap, fs, io::ErrorKind, ops::Bound, path::PathBuf,
+    };
 
     use crate::{
+        block::{
+            Block, BlockHeader, BLOCK_FORMAT_VERSION, BLOCK_HEADER_LENGTH,
+        },
+        bplustree::ObliviousBPlusTree,
         btree::{DataItem, Node},
+        checkpoint::{load_state, load_tree, save_state, save_tree},
+        chunked::ChunkedOram,
+        cipher::{Aes256GcmSiv, OramCipher, PlaintextCipher},
         client::ClientOram,
+        heap::ObliviousHeap,
+        linked::{ObliviousQueue, ObliviousStack},
+        map::ObliviousMap,
         oram::{AccessType, Oram, BUCKET_SIZE},
+        params::{OramParams, DEFAULT_FAILURE_PROBABILITY},
+        simulation::{simulate, simulate_client, StashStats},
+        trace::{CsvTrace, MemoryTrace, TraceEvent, TraceOp, TraceSink},
     };
 
-    fn _complete_tree_size(node: Option<&Box<Node>>) -> usize {
+    fn _complete_tree_size(node: Option<&Node>) -> usize {
         if let Some(node) = node {
             return 1
-                + _complete_tree_size(node.left.as_ref())
-                + _complete_tree_size(node.right.as_ref());
+                + _complete_tree_size(node.left.as_deref())
+                + _complete_tree_size(node.right.as_deref());
         }
         0
     }
@@ -33,7 +53,8 @@ mod tests {
         let path_oram = Oram::new(&mut Vec::new(), nb_items);
 
         if let Ok(path_oram) = path_oram {
-            let tree_size = _complete_tree_size(path_oram.tree().root.as_ref());
+            let tree_size =
+                _complete_tree_size(path_oram.tree().root.as_deref());
             assert_eq!(tree_size, 1);
         }
     }
@@ -45,7 +66,8 @@ mod tests {
         let path_oram = Oram::new(&mut Vec::new(), nb_items);
 
         if let Ok(path_oram) = path_oram {
-            let tree_size = _complete_tree_size(path_oram.tree().root.as_ref());
+            let tree_size =
+                _complete_tree_size(path_oram.tree().root.as_deref());
             assert_eq!(tree_size, 63);
         }
     }
@@ -57,7 +79,8 @@ mod tests {
         let path_oram = Oram::new(&mut Vec::new(), nb_items);
 
         if let Ok(path_oram) = path_oram {
-            let tree_size = _complete_tree_size(path_oram.tree().root.as_ref());
+            let tree_size =
+                _complete_tree_size(path_oram.tree().root.as_deref());
             assert_eq!(tree_size, 15);
         }
     }
@@ -69,7 +92,8 @@ mod tests {
         let path_oram = Oram::new(&mut Vec::new(), nb_items);
 
         if let Ok(path_oram) = path_oram {
-            let tree_size = _complete_tree_size(path_oram.tree().root.as_ref());
+            let tree_size =
+                _complete_tree_size(path_oram.tree().root.as_deref());
             assert_eq!(tree_size, 31);
         }
     }
@@ -170,7 +194,7 @@ mod tests {
         let nb_items: usize = 15 * BUCKET_SIZE;
         let ct_size: usize = 16;
 
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_result = client.generate_dummy_items(nb_items, ct_size);
 
@@ -206,16 +230,18 @@ mod tests {
         let mut new_value = vec![0; 3];
         csprng.fill_bytes(&mut new_value);
 
-        client.position_map.insert(new_value.clone(), 0);
-        let new_item = DataItem::new(new_value);
+        let block_id = csprng.next_u64();
+        client.position_map.insert(block_id, 0);
+        let new_item =
+            Block::new(block_id, 0, new_value).encode(ct_size).unwrap();
 
-        let chg_res = client.change_element_position(&new_item);
+        let chg_res = client.change_element_position(block_id);
         assert!(chg_res.is_ok());
 
         /* Manually insert an element in the stash to check if it empties after
          * ordering elements.
          */
-        client.stash.push(DataItem::new(vec![0; 10]));
+        client.stash.push(Block::encode_dummy(10));
 
         let mut ordered_elements = client.order_elements_for_writing(
             &mut [path_values.as_slice(), &[new_item]].concat(),
@@ -250,7 +276,7 @@ mod tests {
         let nb_items: usize = 15 * BUCKET_SIZE;
         let ct_size: usize = 16;
 
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_result = client.generate_dummy_items(nb_items, ct_size);
 
@@ -288,7 +314,7 @@ mod tests {
         /* Manually insert an element in the stash to check if it empties after
          * ordering elements.
          */
-        client.stash.push(DataItem::new(vec![0; 10]));
+        client.stash.push(Block::encode_dummy(10));
 
         let mut ordered_elements = client.order_elements_for_writing(
             &mut path_values,
@@ -322,7 +348,7 @@ mod tests {
         let nb_items: usize = 15 * BUCKET_SIZE;
         let ct_size: usize = 16;
 
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_result = client.generate_dummy_items(nb_items, ct_size);
 
@@ -360,14 +386,15 @@ mod tests {
         let mut csprng = CsRng::from_entropy();
         let mut new_values = Vec::with_capacity(4);
 
-        for _ in 0..4 {
+        for block_id in 0..4 {
             let mut rand_value = vec![0; 3];
             csprng.fill_bytes(&mut rand_value);
 
-            client.position_map.insert(rand_value.clone(), 0);
+            client.position_map.insert(block_id, 0);
 
-            let data_item = DataItem::new(rand_value);
-            let res_chg = client.change_element_position(&data_item);
+            let data_item =
+                Block::new(block_id, 0, rand_value).encode(ct_size).unwrap();
+            let res_chg = client.change_element_position(block_id);
             assert!(res_chg.is_ok());
 
             new_values.push(data_item);
@@ -376,7 +403,7 @@ mod tests {
         /* Manually insert an element in the stash to check if it empties after
          * ordering elements.
          */
-        client.stash.push(DataItem::new(vec![0; 10]));
+        client.stash.push(Block::encode_dummy(10));
 
         let mut ordered_elements = client.order_elements_for_writing(
             &mut [path_values.as_slice(), new_values.as_slice()].concat(),
@@ -409,7 +436,7 @@ mod tests {
         let nb_items: usize = 15 * BUCKET_SIZE;
         let ct_size: usize = 16;
 
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_result = client.generate_dummy_items(nb_items, ct_size);
 
@@ -445,14 +472,15 @@ mod tests {
         let mut csprng = CsRng::from_entropy();
         let mut new_values = Vec::with_capacity(path_values.len());
 
-        for _ in 0..path_values.len() + 2 {
+        for block_id in 0..path_values.len() as u64 + 2 {
             let mut rand_value = vec![0; 3];
             csprng.fill_bytes(&mut rand_value);
 
-            client.position_map.insert(rand_value.clone(), 0);
+            client.position_map.insert(block_id, 0);
 
-            let data_item = DataItem::new(rand_value);
-            let res_chg = client.change_element_position(&data_item);
+            let data_item =
+                Block::new(block_id, 0, rand_value).encode(ct_size).unwrap();
+            let res_chg = client.change_element_position(block_id);
             assert!(res_chg.is_ok());
             new_values.push(data_item);
         }
@@ -488,7 +516,7 @@ mod tests {
     fn generate_zero_dummies() {
         let nb_items: usize = 0;
         let ct_size = 16;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(16).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
@@ -501,7 +529,7 @@ mod tests {
     fn generate_dummies_random_number() {
         let nb_items: usize = 173;
         let ct_size = 16;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
@@ -512,7 +540,7 @@ mod tests {
     fn generate_dummies_small() {
         let nb_items: usize = 15;
         let ct_size = 16;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
@@ -525,7 +553,7 @@ mod tests {
     fn generate_dummies_big() {
         let nb_items: usize = (1 << 11) - 1;
         let ct_size = 16;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
@@ -538,56 +566,45 @@ mod tests {
     fn generate_dummies_null_ct_size() {
         let nb_items: usize = 15;
         let ct_size = 0;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
         assert!(dummies_res.is_ok());
         let dummies = dummies_res.unwrap();
         assert_eq!(dummies.len(), nb_items);
-        // Nonce + tag length.
-        assert_eq!(dummies[0].data().len(), 12 + 16);
+        // Header + nonce + tag length.
+        assert_eq!(dummies[0].data().len(), BLOCK_HEADER_LENGTH + 12 + 16);
     }
 
     #[test]
     fn generate_dummies_tremendous_ct_size() {
         let nb_items: usize = 15;
         let ct_size = 1000;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
         assert!(dummies_res.is_ok());
         let dummies = dummies_res.unwrap();
         assert_eq!(dummies.len(), nb_items);
-        // Nonce + tag length.
-        assert_eq!(dummies[0].data().len(), ct_size + 12 + 16);
+        // Header + nonce + tag length.
+        assert_eq!(
+            dummies[0].data().len(),
+            BLOCK_HEADER_LENGTH + ct_size + 12 + 16
+        );
     }
 
     #[test]
     fn change_position_bad1() {
         let nb_items: usize = 15;
-        let mut client = ClientOram::new(nb_items);
-
-        let secret_message: Vec<u8> = [
-            66, 114, 117, 99, 101, 32, 83, 99, 104, 110, 101, 105, 101, 114,
-            32, 107, 101, 101, 112, 115, 32, 99, 111, 110, 115, 116, 97, 110,
-            116, 32, 116, 105, 109, 101,
-        ]
-        .to_vec();
-
-        let secret_message2: Vec<u8> = [
-            66, 114, 117, 99, 101, 32, 83, 99, 104, 110, 101, 105, 101, 114,
-            32, 107, 101, 101, 112, 115, 32, 99, 111, 110, 115, 116, 97, 110,
-            116, 32, 115, 105, 122, 101,
-        ]
-        .to_vec();
+        let mut client = ClientOram::new(nb_items).unwrap();
 
-        let data_item_secret2 = DataItem::new(secret_message2);
+        let secret_block_id: u64 = 0x4272_7563_6520_5363;
 
-        client.position_map.insert(secret_message, 2);
+        client.position_map.insert(secret_block_id, 2);
 
-        let res_chg = client.change_element_position(&data_item_secret2);
+        let res_chg = client.change_element_position(secret_block_id + 1);
         assert!(res_chg.is_err());
         println!("{:?}", res_chg.unwrap_err().to_string());
     }
@@ -595,43 +612,31 @@ mod tests {
     #[test]
     fn change_position_bad2() {
         let nb_items: usize = 15;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
-        let secret_message: Vec<u8> = [
-            66, 114, 117, 99, 101, 32, 83, 99, 104, 110, 101, 105, 101, 114,
-            32, 107, 101, 101, 112, 115, 32, 99, 111, 110, 115, 116, 97, 110,
-            116, 32, 116, 105, 109, 101,
-        ]
-        .to_vec();
+        let secret_block_id: u64 = 0x4272_7563_6520_5363;
 
-        client.position_map.insert(secret_message, 2);
+        client.position_map.insert(secret_block_id, 2);
+        client.delete_element_from_position_map(secret_block_id);
 
-        let data_item_empty = DataItem::new(Vec::new());
-
-        let res_chg = client.change_element_position(&data_item_empty);
+        let res_chg = client.change_element_position(secret_block_id);
         assert!(res_chg.is_err());
     }
 
     #[test]
     fn change_position() {
         let nb_items: usize = 60;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
-        let secret_message: Vec<u8> = [
-            66, 114, 117, 99, 101, 32, 83, 99, 104, 110, 101, 105, 101, 114,
-            32, 107, 101, 101, 112, 115, 32, 99, 111, 110, 115, 116, 97, 110,
-            116, 32, 116, 105, 109, 101,
-        ]
-        .to_vec();
+        let secret_block_id: u64 = 0x4272_7563_6520_5363;
 
-        client.position_map.insert(secret_message.clone(), 100);
-        let data_item_secret1 = DataItem::new(secret_message.clone());
+        client.position_map.insert(secret_block_id, 100);
 
-        let res_chg = client.change_element_position(&data_item_secret1);
+        let res_chg = client.change_element_position(secret_block_id);
 
         assert!(res_chg.is_ok());
 
-        let new_value = client.position_map.get(&secret_message).unwrap();
+        let new_value = client.position_map.get(&secret_block_id).unwrap();
 
         assert_ne!(*new_value, 100);
         assert!(*new_value < 8);
@@ -640,24 +645,19 @@ mod tests {
     #[test]
     fn change_position_repeatedely() {
         let nb_items: usize = 100;
-        let mut client = ClientOram::new(nb_items);
+        let mut client =
+            ClientOram::<Aes256Gcm>::with_seed(nb_items, [42; 32]).unwrap();
 
-        let secret_message: Vec<u8> = [
-            66, 114, 117, 99, 101, 32, 83, 99, 104, 110, 101, 105, 101, 114,
-            32, 107, 101, 101, 112, 115, 32, 99, 111, 110, 115, 116, 97, 110,
-            116, 32, 116, 105, 109, 101,
-        ]
-        .to_vec();
+        let secret_block_id: u64 = 0x4272_7563_6520_5363;
 
-        client.position_map.insert(secret_message.clone(), 1337);
-        let data_item_secret1 = DataItem::new(secret_message.clone());
+        client.position_map.insert(secret_block_id, 1337);
 
         for _ in 0..1000 {
-            let res_chg = client.change_element_position(&data_item_secret1);
+            let res_chg = client.change_element_position(secret_block_id);
 
             assert!(res_chg.is_ok());
 
-            let new_value = client.position_map.get(&secret_message).unwrap();
+            let new_value = client.position_map.get(&secret_block_id).unwrap();
 
             assert_ne!(*new_value, 1337);
             assert!(*new_value < 16);
@@ -667,7 +667,7 @@ mod tests {
     #[test]
     fn order_elements() {
         let nb_items: usize = 48;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let elt1: Vec<u8> = [66, 114, 117].to_vec();
         let elt2: Vec<u8> = [99, 101, 32].to_vec();
@@ -681,31 +681,35 @@ mod tests {
         let elt10: Vec<u8> = [97, 110, 116].to_vec();
         let elt11: Vec<u8> = [32, 116, 105].to_vec();
 
-        client.position_map.insert(elt1.clone(), 0);
-        client.position_map.insert(elt2.clone(), 1);
-        client.position_map.insert(elt3.clone(), 4);
-        client.position_map.insert(elt4.clone(), 4);
-        client.position_map.insert(elt5.clone(), 2);
-        client.position_map.insert(elt6.clone(), 3);
-        client.position_map.insert(elt7.clone(), 6);
-        client.position_map.insert(elt8.clone(), 1);
-        client.position_map.insert(elt9.clone(), 3);
-        client.position_map.insert(elt10.clone(), 0);
-        client.position_map.insert(elt11.clone(), 5);
-
-        client.stash =
-            [DataItem::new(elt1.clone()), DataItem::new(elt2.clone())].to_vec();
+        client.position_map.insert(1, 0);
+        client.position_map.insert(2, 1);
+        client.position_map.insert(3, 4);
+        client.position_map.insert(4, 4);
+        client.position_map.insert(5, 2);
+        client.position_map.insert(6, 3);
+        client.position_map.insert(7, 6);
+        client.position_map.insert(8, 1);
+        client.position_map.insert(9, 3);
+        client.position_map.insert(10, 0);
+        client.position_map.insert(11, 5);
+
+        // Blocks are encoded with a stale leaf, the position map prevails.
+        let block = |id: u64, elt: &Vec<u8>| {
+            Block::new(id, 7, elt.clone()).encode(3).unwrap()
+        };
+
+        client.stash = [block(1, &elt1), block(2, &elt2)].to_vec();
 
         let path_values_decrypted = vec![
-            DataItem::new(elt3.clone()),
-            DataItem::new(elt4.clone()),
-            DataItem::new(elt5.clone()),
-            DataItem::new(elt6.clone()),
-            DataItem::new(elt7.clone()),
-            DataItem::new(elt8.clone()),
-            DataItem::new(elt9.clone()),
-            DataItem::new(elt10.clone()),
-            DataItem::new(elt11.clone()),
+            block(3, &elt3),
+            block(4, &elt4),
+            block(5, &elt5),
+            block(6, &elt6),
+            block(7, &elt7),
+            block(8, &elt8),
+            block(9, &elt9),
+            block(10, &elt10),
+            block(11, &elt11),
         ];
 
         let path = 3;
@@ -723,35 +727,29 @@ mod tests {
         assert_eq!(ordered_buckets[2].len(), BUCKET_SIZE);
         assert_eq!(ordered_buckets[3].len(), BUCKET_SIZE);
 
-        let empty = DataItem::new(vec![0; 3]);
+        let empty = Block::encode_dummy(3);
+        let block = |id: u64, elt: Vec<u8>| {
+            let leaf = *client.position_map.get(&id).unwrap();
+            Block::new(id, leaf, elt).encode(3).unwrap()
+        };
 
         assert_eq!(
             ordered_buckets,
             vec![
                 [
-                    DataItem::new(elt3),
-                    DataItem::new(elt4),
-                    DataItem::new(elt7),
-                    DataItem::new(elt11)
-                ],
-                [
-                    DataItem::new(elt1),
-                    DataItem::new(elt2),
-                    DataItem::new(elt8),
-                    DataItem::new(elt10)
-                ],
-                [
-                    DataItem::new(elt5),
-                    empty.clone(),
-                    empty.clone(),
-                    empty.clone()
+                    block(3, elt3),
+                    block(4, elt4),
+                    block(7, elt7),
+                    block(11, elt11)
                 ],
                 [
-                    DataItem::new(elt6),
-                    DataItem::new(elt9),
-                    empty.clone(),
-                    empty
+                    block(1, elt1),
+                    block(2, elt2),
+                    block(8, elt8),
+                    block(10, elt10)
                 ],
+                [block(5, elt5), empty.clone(), empty.clone(), empty.clone()],
+                [block(6, elt6), block(9, elt9), empty.clone(), empty],
             ]
         )
     }
@@ -760,7 +758,7 @@ mod tests {
     fn decrypt_dummies() {
         let nb_items: usize = 15;
         let ct_size = 16;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let dummies_res = client.generate_dummy_items(nb_items, ct_size);
 
@@ -768,24 +766,26 @@ mod tests {
         let mut dummies = dummies_res.unwrap();
         assert_eq!(dummies.len(), nb_items);
 
-        // Nonce + tag length.
-        assert_eq!(dummies[0].data().len(), ct_size + 12 + 16);
+        // Header + nonce + tag length.
+        assert_eq!(
+            dummies[0].data().len(),
+            BLOCK_HEADER_LENGTH + ct_size + 12 + 16
+        );
 
         let decrypt_res = client.decrypt_items(&mut dummies);
         assert!(decrypt_res.is_ok());
 
-        let null_vector: Vec<u8> = vec![0; ct_size];
-
-        dummies
-            .iter()
-            .for_each(|dummy| assert_eq!(dummy.data(), &null_vector));
+        dummies.iter().for_each(|dummy| {
+            assert_eq!(dummy.data().len(), BLOCK_HEADER_LENGTH + ct_size);
+            assert!(Block::decode(dummy).unwrap().is_none());
+        });
     }
 
     #[test]
     fn client_encrypt_decrypt_stash() {
         let nb_items: usize = 15;
         let ct_size = 16;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         client.stash = vec![DataItem::new(vec![0; ct_size]); 4];
 
@@ -805,7 +805,7 @@ mod tests {
     #[test]
     fn client_encrypt_decrypt_empty_stash() {
         let nb_items: usize = 15;
-        let mut client = ClientOram::new(nb_items);
+        let mut client = ClientOram::new(nb_items).unwrap();
 
         let stash_encrypt_res = client.encrypt_stash();
         assert!(stash_encrypt_res.is_ok());
@@ -814,144 +814,3297 @@ mod tests {
         assert!(stash_decrypt_res.is_ok());
     }
 
+    fn _decrypt_dummies_with<C: OramCipher>() {
+        let nb_items: usize = 15;
+        let ct_size = 16;
+        let mut client = ClientOram::<C>::with_cipher(nb_items).unwrap();
+
+        let dummies_res = client.generate_dummy_items(nb_items, ct_size);
+
+        assert!(dummies_res.is_ok());
+        let mut dummies = dummies_res.unwrap();
+        assert_eq!(
+            dummies[0].data().len(),
+            BLOCK_HEADER_LENGTH + ct_size + C::OVERHEAD
+        );
+
+        let decrypt_res = client.decrypt_items(&mut dummies);
+        assert!(decrypt_res.is_ok());
+
+        dummies.iter().for_each(|dummy| {
+            assert_eq!(dummy.data().len(), BLOCK_HEADER_LENGTH + ct_size);
+            assert!(Block::decode(dummy).unwrap().is_none());
+        });
+    }
+
     #[test]
-    fn general_behavior() {
-        /*
-         * Example of use for 183 items stored and a ciphertext size of 16 bytes.
-         * This means that there will be ceil(183/4) = 46 nodes to hold those
-         * items which completes to 63 nodes for the tree. There will then be 32
-         * leaves.
-         */
-        let nb_items: usize = 183;
-        let ct_size: usize = 16;
+    fn decrypt_dummies_chacha20_poly1305() {
+        _decrypt_dummies_with::<ChaCha20Poly1305>();
+    }
 
-        /*
-         * Client.
-         */
-        let mut client = ClientOram::new(nb_items);
+    #[test]
+    fn decrypt_dummies_aes_gcm_siv() {
+        _decrypt_dummies_with::<Aes256GcmSiv>();
+    }
 
-        let res_dummies = client.generate_dummy_items(nb_items, ct_size);
-        assert!(res_dummies.is_ok());
-        let mut dummies = res_dummies.unwrap();
+    #[test]
+    fn cipher_overheads() {
+        // Nonce + tag length.
+        assert_eq!(<Aes256Gcm as OramCipher>::OVERHEAD, 12 + 16);
+        assert_eq!(<ChaCha20Poly1305 as OramCipher>::OVERHEAD, 12 + 16);
+        assert_eq!(<Aes256GcmSiv as OramCipher>::OVERHEAD, 12 + 16);
+    }
 
-        /*
-         * Server.
-         */
-        let res_oram = Oram::new(&mut dummies, nb_items);
+    #[test]
+    fn cipher_bad_key_length() {
+        let key = vec![0; 16];
+
+        assert!(<Aes256Gcm as OramCipher>::from_key(&key).is_err());
+        assert!(<ChaCha20Poly1305 as OramCipher>::from_key(&key).is_err());
+        assert!(<Aes256GcmSiv as OramCipher>::from_key(&key).is_err());
+    }
+
+    #[test]
+    fn cipher_from_same_key() {
+        let mut csprng = CsRng::from_entropy();
+        let mut key = vec![0; Aes256GcmSiv::KEY_LENGTH];
+        csprng.fill_bytes(&mut key);
+
+        let cipher1 = Aes256GcmSiv::from_key(&key).unwrap();
+        let cipher2 = Aes256GcmSiv::from_key(&key).unwrap();
+
+        let message = b"Bruce Schneier keeps constant time".to_vec();
+        let ciphertext = cipher1.encrypt(&mut csprng, &message).unwrap();
+
+        assert_eq!(cipher2.decrypt(&ciphertext).unwrap(), message);
+    }
+
+    #[test]
+    fn cipher_decrypt_truncated() {
+        let mut csprng = CsRng::from_entropy();
+        let cipher = ChaCha20Poly1305::new_random(&mut csprng);
+
+        let ciphertext = cipher.encrypt(&mut csprng, &[1, 2, 3]).unwrap();
+
+        assert!(cipher.decrypt(&ciphertext[..ciphertext.len() - 1]).is_err());
+        assert!(cipher.decrypt(&ciphertext[..5]).is_err());
+        assert!(cipher.decrypt(&[]).is_err());
+    }
+
+    #[test]
+    fn client_encrypt_decrypt_stash_chacha20_poly1305() {
+        let nb_items: usize = 15;
+        let ct_size = 16;
+        let mut client =
+            ClientOram::<ChaCha20Poly1305>::with_cipher(nb_items).unwrap();
+
+        client.stash = vec![DataItem::new(vec![0; ct_size]); 4];
+
+        let stash_encrypt_res = client.encrypt_stash();
+        assert!(stash_encrypt_res.is_ok());
+
+        assert_eq!(client.stash[0].data().len(), ct_size + 12 + 16);
+
+        let stash_decrypt_res = client.decrypt_stash();
+        assert!(stash_decrypt_res.is_ok());
+
+        assert_eq!(client.stash[0].data(), &vec![0; ct_size]);
+    }
+
+    #[test]
+    fn read_write_access_aes_gcm_siv() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let ct_size: usize = 16;
+
+        let mut client =
+            ClientOram::<Aes256GcmSiv>::with_cipher(nb_items).unwrap();
+
+        let res_oram = client.setup_oram(ct_size);
         assert!(res_oram.is_ok());
         let mut path_oram = res_oram.unwrap();
 
-        // Let's read path number 22.
-        let path = 22;
-
-        let res_access = path_oram.access(AccessType::Read, path, Option::None);
-        assert!(res_access.is_ok());
-        let opt_access = res_access.unwrap();
-        assert!(opt_access.is_some());
+        let path = 5;
+        let res_read = client.read_from_path(&mut path_oram, path);
+        assert!(res_read.is_ok());
+        let mut read_data = res_read.unwrap();
 
-        // This is the data we read from the ORAM, only dummies at this point.
-        let mut read_data = opt_access.unwrap();
+        read_data.iter().for_each(|data_item| {
+            assert!(BlockHeader::decode(data_item).unwrap().is_none())
+        });
 
-        /*
-         * We received server response. Moving client side...
-         */
+        let res_write =
+            client.write_to_path(&mut path_oram, &mut read_data, None, path);
+        assert!(res_write.is_ok());
 
-        // Decrypt them nonetheless.
-        let decrypt_res = client.decrypt_items(&mut read_data);
-        assert!(decrypt_res.is_ok());
+        let res_read = client.read_from_path(&mut path_oram, path);
+        assert!(res_read.is_ok());
+        assert_eq!(
+            res_read.unwrap().len(),
+            path_oram.tree().height() as usize * BUCKET_SIZE
+        );
+    }
 
-        // Decrypt client stash.
-        let stsh_dec_res = client.decrypt_stash();
-        assert!(stsh_dec_res.is_ok());
+    fn _checkpoint_path(name: &str) -> PathBuf {
+        let mut csprng = CsRng::from_entropy();
+        std::env::temp_dir().join(format!(
+            "path_oram_{}_{}.ckp",
+            name,
+            csprng.next_u64()
+        ))
+    }
 
-        // Now read_data contains plaintext values. Decrypted dummy is null.
-        assert!(!read_data[9].data().is_empty());
-        let null_vector: Vec<u8> = vec![0; ct_size];
-        assert_eq!(read_data[9].data(), &null_vector);
+    #[test]
+    fn checkpoint_restore() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let ct_size: usize = 16;
 
-        // Let's add some real data to our position map now.
         let mut csprng = CsRng::from_entropy();
-        let mut new_values = Vec::with_capacity(
-            path_oram.tree().height() as usize * BUCKET_SIZE + 2,
+        let mut key = vec![0; 32];
+        csprng.fill_bytes(&mut key);
+
+        let res_client = ClientOram::<Aes256Gcm>::with_key(nb_items, &key);
+        assert!(res_client.is_ok());
+        let mut client = res_client.unwrap();
+
+        let res_oram = client.setup_oram(ct_size);
+        assert!(res_oram.is_ok());
+        let mut path_oram = res_oram.unwrap();
+
+        let witness = vec![10; ct_size];
+        let res_write = client.access(
+            &mut path_oram,
+            AccessType::Write,
+            42,
+            Some(witness.clone()),
         );
+        assert!(res_write.is_ok());
 
-        for _ in 0..(path_oram.tree().height() as usize * BUCKET_SIZE) + 2 {
-            let mut rand_value = vec![0; ct_size];
-            csprng.fill_bytes(&mut rand_value);
-            let data_item = DataItem::new(rand_value.clone());
+        let checkpoint_path = _checkpoint_path("restore");
+        assert!(client.checkpoint(&checkpoint_path).is_ok());
 
-            client.position_map.insert(rand_value.clone(), 0);
+        let position_map = client.position_map.clone();
+        drop(client);
 
-            let res_chg = client.change_element_position(&data_item);
-            assert!(res_chg.is_ok());
+        let res_restore =
+            ClientOram::<Aes256Gcm>::restore(&checkpoint_path, &key);
+        fs::remove_file(&checkpoint_path).unwrap();
+        assert!(res_restore.is_ok());
+        let mut client = res_restore.unwrap();
 
-            new_values.push(data_item);
-        }
-        // Push a witness value for later.
-        let witness: Vec<u8> = [
-            10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10,
-        ]
-        .to_vec();
-        client.position_map.insert(witness.clone(), path);
+        assert_eq!(client.position_map, position_map);
+        assert_eq!(client.nb_accesses(), 1);
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
 
-        new_values.push(DataItem::new(witness.clone()));
+        // The restored client can read back what was written before.
+        let res_read =
+            client.access(&mut path_oram, AccessType::Read, 42, None);
+        assert!(res_read.is_ok());
+        assert_eq!(res_read.unwrap(), Some(witness));
+    }
 
-        /* We ordered elements and put the ones that could not be written in the
-         * stash. Since we want to write 26 elements and a path can only contain
-         * tree.height * BUCKET_SIZE = 6 * 4 (here) = 24 elements, the stash has
-         * to be not empty. However, its size can vary since we assigned paths
-         * at random.
-         */
-        let mut ordered_elements = client.order_elements_for_writing(
-            &mut [new_values.as_slice(), read_data.as_slice()].concat(),
-            path,
-            path_oram.tree().height() as usize,
+    #[test]
+    fn checkpoint_replace() {
+        let nb_items: usize = 15;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let checkpoint_path = _checkpoint_path("replace");
+
+        assert!(client.checkpoint(&checkpoint_path).is_ok());
+
+        client.insert_element_in_position_map(123);
+        assert!(client.checkpoint(&checkpoint_path).is_ok());
+
+        // No temporary file is left behind.
+        let mut tmp_path = checkpoint_path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        assert!(!PathBuf::from(tmp_path).exists());
+
+        fs::remove_file(&checkpoint_path).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_restore_wrong_key() {
+        let nb_items: usize = 15;
+
+        let mut client = ClientOram::<ChaCha20Poly1305>::with_key(
+            nb_items,
+            &[1; ChaCha20Poly1305::KEY_LENGTH],
+        )
+        .unwrap();
+
+        let checkpoint_path = _checkpoint_path("wrong_key");
+        assert!(client.checkpoint(&checkpoint_path).is_ok());
+
+        let res_restore = ClientOram::<ChaCha20Poly1305>::restore(
+            &checkpoint_path,
+            &[2; ChaCha20Poly1305::KEY_LENGTH],
         );
+        fs::remove_file(&checkpoint_path).unwrap();
 
-        assert!(!client.stash.is_empty());
+        assert!(res_restore.is_err());
+    }
 
-        assert_eq!(ordered_elements.len(), path_oram.tree().height() as usize);
-        assert_eq!(ordered_elements[0].len(), BUCKET_SIZE);
+    #[test]
+    fn checkpoint_restore_tampered() {
+        let nb_items: usize = 15;
+        let key = [3; 32];
 
-        // Encrypt read items to write them back to the ORAM.
-        let enc_res = client.encrypt_items(&mut ordered_elements);
-        assert!(enc_res.is_ok());
+        let mut client =
+            ClientOram::<Aes256GcmSiv>::with_key(nb_items, &key).unwrap();
+        client.insert_element_in_position_map(123);
 
-        // Encrypt back the stash.
-        let stsh_enc_res = client.encrypt_stash();
-        assert!(stsh_enc_res.is_ok());
+        let checkpoint_path = _checkpoint_path("tampered");
+        assert!(client.checkpoint(&checkpoint_path).is_ok());
 
-        /*
-         * Sending data to the server for writing...
-         */
+        let mut bytes = fs::read(&checkpoint_path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&checkpoint_path, bytes).unwrap();
 
-        // Write ordered elements to the same path.
-        let res_write = path_oram.access(
-            AccessType::Write,
-            path,
-            Some(&mut ordered_elements),
+        let res_restore =
+            ClientOram::<Aes256GcmSiv>::restore(&checkpoint_path, &key);
+        fs::remove_file(&checkpoint_path).unwrap();
+
+        assert!(res_restore.is_err());
+    }
+
+    #[test]
+    fn checkpoint_restore_missing_file() {
+        let res_restore = ClientOram::<Aes256Gcm>::restore(
+            _checkpoint_path("missing"),
+            &[0; 32],
         );
 
-        assert!(res_write.is_ok());
-        let opt_write = res_write.unwrap();
-        assert!(opt_write.is_none());
+        assert!(res_restore.is_err());
+    }
+
+    #[test]
+    fn checkpoint_tree() {
+        let mut csprng = CsRng::from_entropy();
+        let mut key = vec![0; 32];
+        csprng.fill_bytes(&mut key);
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(64, &key).unwrap();
+        client.outsource_stash(64);
+        let mut path_oram = client.setup_oram(16).unwrap();
+        for block_id in 0..40u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![block_id as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        let checkpoint_path = _checkpoint_path("tree_client");
+        let tree_path = _checkpoint_path("tree");
+        client.checkpoint(&checkpoint_path).unwrap();
+        save_tree(&mut path_oram, &tree_path).unwrap();
+        drop(client);
+        drop(path_oram);
+
+        // Both the client and the server resume from their saved state.
+        let mut client =
+            ClientOram::<Aes256Gcm>::restore(&checkpoint_path, &key).unwrap();
+        let mut path_oram = load_tree(&tree_path, client.params()).unwrap();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        for block_id in 0..40u64 {
+            let value = client
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8; 16]));
+        }
+
+        let params = client.params().for_nb_items(256).unwrap();
+        let res_load = load_tree(&tree_path, &params);
+        assert_eq!(res_load.err().unwrap().kind(), ErrorKind::InvalidData);
+
+        fs::remove_file(&checkpoint_path).unwrap();
+        fs::remove_file(&tree_path).unwrap();
+    }
+
+    #[test]
+    fn checkpoint_state() {
+        let mut csprng = CsRng::from_entropy();
+        let mut key = vec![0; 32];
+        csprng.fill_bytes(&mut key);
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(64, &key).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        for block_id in 0..40u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![block_id as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        // The client and the tree are saved to a single file.
+        let state_path = _checkpoint_path("state");
+        save_state(&mut client, &mut path_oram, &state_path).unwrap();
+        drop(client);
+        drop(path_oram);
+
+        let (mut client, mut path_oram) =
+            load_state::<Aes256Gcm>(&state_path, &key).unwrap();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        for block_id in 0..40u64 {
+            let value = client
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8; 16]));
+        }
+
+        let mut wrong_key = key.clone();
+        wrong_key[0] ^= 1;
+        let res_load = load_state::<Aes256Gcm>(&state_path, &wrong_key);
+        assert_eq!(res_load.err().unwrap().kind(), ErrorKind::InvalidData);
+
+        // Truncated states are rejected.
+        let bytes = fs::read(&state_path).unwrap();
+        fs::write(&state_path, &bytes[..bytes.len() / 2]).unwrap();
+        let res_load = load_state::<Aes256Gcm>(&state_path, &key);
+        assert_eq!(res_load.err().unwrap().kind(), ErrorKind::InvalidData);
+
+        fs::remove_file(&state_path).unwrap();
+    }
+
+    #[test]
+    fn outsourced_stash_constant_size() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let ct_size: usize = 16;
+        let stash_capacity = 32;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        client.outsource_stash(stash_capacity);
+
+        let mut path_oram = client.setup_oram(ct_size).unwrap();
+        assert_eq!(path_oram.read_stash().len(), stash_capacity);
+
+        let path = 3;
+        let mut read_data =
+            client.read_from_path(&mut path_oram, path).unwrap();
 
         /*
-         * Let's read the same path again to check if values read are the same.
+         * Write more elements than the path can hold so that some of them
+         * overflow to the stash.
          */
-        let res_read = path_oram.access(AccessType::Read, path, Option::None);
-        assert!(res_read.is_ok());
-        let opt_read = res_read.unwrap();
-        assert!(opt_read.is_some());
+        let mut csprng = CsRng::from_entropy();
+        let mut new_values = Vec::new();
+        for block_id in 0..read_data.len() as u64 + 2 {
+            let mut rand_value = vec![0; ct_size];
+            csprng.fill_bytes(&mut rand_value);
+            let leaf = client.insert_element_in_position_map(block_id);
 
-        let mut read_values = opt_read.unwrap();
+            new_values.push(Block::new(block_id, leaf, rand_value));
+        }
+        read_data.extend(
+            new_values
+                .iter()
+                .map(|block| block.encode(ct_size).unwrap()),
+        );
 
-        let dec_res = client.decrypt_items(&mut read_values);
-        assert!(dec_res.is_ok());
+        let res_write =
+            client.write_to_path(&mut path_oram, &mut read_data, None, path);
+        assert!(res_write.is_ok());
 
-        // Check that the path we read from contains the witness we inserted.
-        read_values
+        // Nothing is kept client side, server stash size does not change.
+        assert!(client.stash.is_empty());
+        assert_eq!(path_oram.read_stash().len(), stash_capacity);
+        assert!(path_oram
+            .read_stash()
             .iter()
-            .any(|data_item| data_item.data() == &witness);
+            .all(|data_item| data_item.data().len()
+                == BLOCK_HEADER_LENGTH + ct_size + 12 + 16));
+
+        // The stash fetched back contains the overflowing elements.
+        let read_data = client.read_from_path(&mut path_oram, path).unwrap();
+        assert!(!client.stash.is_empty());
+        let read_blocks: Vec<Block> = read_data
+            .iter()
+            .filter_map(|data_item| Block::decode(data_item).unwrap())
+            .collect();
+        for new_value in &new_values {
+            if new_value.leaf() == path {
+                assert!(read_blocks.contains(new_value));
+            }
+        }
+        assert!(client.stash.iter().all(|data_item| {
+            new_values.contains(&Block::decode(data_item).unwrap().unwrap())
+        }));
+    }
+
+    #[test]
+    fn outsourced_stash_overflow() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let ct_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        client.outsource_stash(0);
+
+        let mut path_oram = client.setup_oram(ct_size).unwrap();
+        assert!(path_oram.read_stash().is_empty());
+
+        let path = 3;
+        let mut read_data =
+            client.read_from_path(&mut path_oram, path).unwrap();
+
+        /*
+         * New elements are mapped to a path only intersecting the read path at
+         * the root, one of them cannot be written.
+         */
+        let other_path = path ^ (1 << (path_oram.tree().height() - 2));
+        for block_id in 0..=BUCKET_SIZE as u64 {
+            client.position_map.insert(block_id, other_path);
+            read_data.push(
+                Block::new(block_id, other_path, vec![block_id as u8; ct_size])
+                    .encode(ct_size)
+                    .unwrap(),
+            );
+        }
+
+        let res_write =
+            client.write_to_path(&mut path_oram, &mut read_data, None, path);
+        assert_eq!(res_write.unwrap_err().kind(), ErrorKind::OutOfMemory);
+
+        // Accesses failing on an overflow leave the client as it was.
+        let mut client = ClientOram::new(nb_items).unwrap();
+        client.outsource_stash(0);
+        let mut path_oram = client.setup_oram(ct_size).unwrap();
+
+        let mut values = BTreeMap::new();
+        let mut nb_overflows = 0;
+        for i in 0..4 * nb_items as u64 {
+            let (block_id, value) = (i % nb_items as u64, vec![i as u8; 16]);
+            let res_write = client.access(
+                &mut path_oram,
+                AccessType::Write,
+                block_id,
+                Some(value.clone()),
+            );
+            match res_write {
+                Ok(old_value) => {
+                    assert_eq!(old_value, values.insert(block_id, value));
+                }
+                Err(e) => {
+                    assert_eq!(e.kind(), ErrorKind::OutOfMemory);
+                    nb_overflows += 1;
+                }
+            }
+        }
+
+        // Batches as well.
+        for i in 0..nb_items as u64 {
+            let block_ids = [i, (i + 1) % nb_items as u64];
+            let requests = block_ids
+                .iter()
+                .map(|&id| (AccessType::Write, id, Some(vec![id as u8; 8])))
+                .collect();
+            match client.access_batch(&mut path_oram, requests) {
+                Ok(old_values) => {
+                    for (block_id, old_value) in
+                        block_ids.into_iter().zip(old_values)
+                    {
+                        let value = vec![block_id as u8; 8];
+                        assert_eq!(old_value, values.insert(block_id, value));
+                    }
+                }
+                Err(e) => {
+                    assert_eq!(e.kind(), ErrorKind::OutOfMemory);
+                    nb_overflows += 1;
+                }
+            }
+        }
+        assert!(nb_overflows > 0);
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+        for (block_id, value) in values {
+            // Reads remap blocks too, and may overflow as well.
+            let res_read = loop {
+                match client.access(
+                    &mut path_oram,
+                    AccessType::Read,
+                    block_id,
+                    None,
+                ) {
+                    Err(e) if e.kind() == ErrorKind::OutOfMemory => continue,
+                    res_read => break res_read,
+                }
+            };
+            assert_eq!(res_read.unwrap(), Some(value));
+        }
+    }
+
+    #[test]
+    fn block_encode_decode() {
+        let block = Block::new(1337, 5, vec![1, 2, 3]);
+
+        let encoded = block.encode(16).unwrap();
+        assert_eq!(encoded.data().len(), BLOCK_HEADER_LENGTH + 16);
+        assert_eq!(encoded.data()[0], BLOCK_FORMAT_VERSION);
+
+        assert_eq!(Block::decode(&encoded).unwrap(), Some(block));
+
+        let header = BlockHeader::decode(&encoded).unwrap().unwrap();
+        assert_eq!(header.id, 1337);
+        assert_eq!(header.leaf, 5);
+        assert_eq!(header.length, 3);
+    }
+
+    #[test]
+    fn block_zero_payload_is_not_dummy() {
+        let block = Block::new(0, 0, vec![0; 16]);
+
+        let encoded = block.encode(16).unwrap();
+        assert_eq!(Block::decode(&encoded).unwrap(), Some(block));
+
+        let empty_block = Block::new(1, 0, Vec::new());
+
+        let encoded = empty_block.encode(16).unwrap();
+        assert_eq!(Block::decode(&encoded).unwrap(), Some(empty_block));
+    }
+
+    #[test]
+    fn block_dummy() {
+        let dummy = Block::encode_dummy(16);
+        assert_eq!(dummy.data().len(), BLOCK_HEADER_LENGTH + 16);
+        assert!(Block::decode(&dummy).unwrap().is_none());
+
+        // Uninitialized slots are dummies as well.
+        assert!(Block::decode(&DataItem::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn block_payload_too_large() {
+        let block = Block::new(1, 0, vec![1; 17]);
+        assert!(block.encode(16).is_err());
+        assert!(block.encode(17).is_ok());
+    }
+
+    #[test]
+    fn block_invalid() {
+        // Too short.
+        assert!(
+            Block::decode(&DataItem::new(vec![BLOCK_FORMAT_VERSION])).is_err()
+        );
+
+        // Unknown version.
+        let mut encoded = Block::new(1, 0, vec![1]).encode(4).unwrap();
+        encoded.data_as_mut()[0] = BLOCK_FORMAT_VERSION + 1;
+        assert!(Block::decode(&encoded).is_err());
+
+        // Length larger than the block.
+        let mut encoded = Block::new(1, 0, vec![1]).encode(4).unwrap();
+        encoded.data_as_mut()[BLOCK_HEADER_LENGTH - 4] = 5;
+        assert!(Block::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn block_set_leaf() {
+        let mut encoded = Block::new(1, 0, vec![1, 2]).encode(4).unwrap();
+        BlockHeader::set_leaf(&mut encoded, 12);
+
+        assert_eq!(
+            Block::decode(&encoded).unwrap(),
+            Some(Block::new(1, 12, vec![1, 2]))
+        );
+    }
+
+    #[test]
+    fn client_access_read_write() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        // Reading an unknown block returns nothing.
+        let res_read = client.access(&mut path_oram, AccessType::Read, 7, None);
+        assert!(res_read.is_ok());
+        assert!(res_read.unwrap().is_none());
+        assert!(client.position_map.is_empty());
+
+        for block_id in 0..20 {
+            let res_write = client.access(
+                &mut path_oram,
+                AccessType::Write,
+                block_id,
+                Some(vec![block_id as u8; block_id as usize % block_size]),
+            );
+            assert!(res_write.is_ok());
+            assert!(res_write.unwrap().is_none());
+        }
+
+        for block_id in 0..20 {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, block_id, None);
+            assert_eq!(
+                res_read.unwrap(),
+                Some(vec![block_id as u8; block_id as usize % block_size])
+            );
+        }
+
+        // Overwriting returns the previous payload.
+        let res_write = client.access(
+            &mut path_oram,
+            AccessType::Write,
+            3,
+            Some(vec![0; block_size]),
+        );
+        assert_eq!(res_write.unwrap(), Some(vec![3; 3]));
+
+        let res_read = client.access(&mut path_oram, AccessType::Read, 3, None);
+        assert_eq!(res_read.unwrap(), Some(vec![0; block_size]));
+
+        assert_eq!(client.nb_accesses(), 43);
+    }
+
+    #[test]
+    fn client_access_bad_write() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        let res_write =
+            client.access(&mut path_oram, AccessType::Write, 1, None);
+        assert!(res_write.is_err());
+
+        let res_write = client.access(
+            &mut path_oram,
+            AccessType::Write,
+            1,
+            Some(vec![1; block_size + 1]),
+        );
+        assert!(res_write.is_err());
+
+        // Nothing was sent to the server.
+        assert_eq!(client.nb_accesses(), 0);
+    }
+
+    #[test]
+    fn write_to_path_insert_new_elements() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        let path = 2;
+        let mut read_data =
+            client.read_from_path(&mut path_oram, path).unwrap();
+
+        let new_block = Block::new(99, 0, vec![9; 4]);
+        let res_write = client.write_to_path(
+            &mut path_oram,
+            &mut read_data,
+            Some(vec![new_block.encode(block_size).unwrap()]),
+            path,
+        );
+        assert!(res_write.is_ok());
+
+        // The new block was actually written and can be accessed.
+        assert!(client.position_map.contains_key(&99));
+        let res_read =
+            client.access(&mut path_oram, AccessType::Read, 99, None);
+        assert_eq!(res_read.unwrap(), Some(vec![9; 4]));
+
+        // Dummies cannot be inserted.
+        let mut read_data =
+            client.read_from_path(&mut path_oram, path).unwrap();
+        let res_write = client.write_to_path(
+            &mut path_oram,
+            &mut read_data,
+            Some(vec![Block::encode_dummy(block_size)]),
+            path,
+        );
+        assert!(res_write.is_err());
+    }
+
+    #[test]
+    fn client_remove() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        client
+            .access(&mut path_oram, AccessType::Write, 5, Some(vec![5; 5]))
+            .unwrap();
+
+        let res_remove = client.remove(&mut path_oram, 5);
+        assert_eq!(res_remove.unwrap(), Some(vec![5; 5]));
+        assert!(!client.position_map.contains_key(&5));
+
+        let res_read = client.access(&mut path_oram, AccessType::Read, 5, None);
+        assert!(res_read.unwrap().is_none());
+
+        // Removing a missing block still accesses a path.
+        let res_remove = client.remove(&mut path_oram, 5);
+        assert!(res_remove.unwrap().is_none());
+        assert_eq!(client.nb_accesses(), 4);
+    }
+
+    fn _chunked_oram(
+        nb_values: usize,
+        chunks_per_value: usize,
+        block_size: usize,
+    ) -> (ChunkedOram, Oram) {
+        let mut client = ClientOram::new(nb_values * chunks_per_value).unwrap();
+        let path_oram = client.setup_oram(block_size).unwrap();
+
+        (
+            ChunkedOram::new(client, chunks_per_value).unwrap(),
+            path_oram,
+        )
+    }
+
+    #[test]
+    fn chunked_null_chunks_per_value() {
+        assert!(ChunkedOram::new(ClientOram::new(16).unwrap(), 0).is_err());
+    }
+
+    #[test]
+    fn chunked_read_write() {
+        let (mut chunked, mut path_oram) = _chunked_oram(8, 8, 16);
+        assert_eq!(chunked.max_value_size(), 128);
+
+        let mut csprng = CsRng::from_entropy();
+        let mut values = Vec::new();
+        for (value_id, length) in
+            [0, 1, 15, 16, 17, 100, 128].iter().enumerate()
+        {
+            let mut value = vec![0; *length];
+            csprng.fill_bytes(&mut value);
+
+            let res_write =
+                chunked.write(&mut path_oram, value_id as u64, &value);
+            assert!(res_write.is_ok());
+            values.push(value);
+        }
+
+        for (value_id, value) in values.iter().enumerate() {
+            let res_read = chunked.read(&mut path_oram, value_id as u64);
+            assert_eq!(res_read.unwrap().as_ref(), Some(value));
+        }
+
+        let res_read = chunked.read(&mut path_oram, 7);
+        assert!(res_read.unwrap().is_none());
+    }
+
+    #[test]
+    fn chunked_overwrite_shorter() {
+        let (mut chunked, mut path_oram) = _chunked_oram(4, 4, 8);
+
+        chunked.write(&mut path_oram, 2, &[1; 30]).unwrap();
+        chunked.write(&mut path_oram, 2, &[2; 9]).unwrap();
+
+        let res_read = chunked.read(&mut path_oram, 2);
+        assert_eq!(res_read.unwrap(), Some(vec![2; 9]));
+
+        // Chunks of the longer value are gone.
+        assert_eq!(chunked.client().position_map.len(), 2);
+    }
+
+    #[test]
+    fn chunked_remove() {
+        let (mut chunked, mut path_oram) = _chunked_oram(4, 4, 8);
+
+        chunked.write(&mut path_oram, 1, &[1; 20]).unwrap();
+        chunked.write(&mut path_oram, 3, &[]).unwrap();
+
+        let res_remove = chunked.remove(&mut path_oram, 1);
+        assert_eq!(res_remove.unwrap(), Some(vec![1; 20]));
+        assert!(chunked.read(&mut path_oram, 1).unwrap().is_none());
+
+        // Empty values exist until they are removed.
+        assert_eq!(chunked.read(&mut path_oram, 3).unwrap(), Some(Vec::new()));
+        let res_remove = chunked.remove(&mut path_oram, 3);
+        assert_eq!(res_remove.unwrap(), Some(Vec::new()));
+        assert!(chunked.read(&mut path_oram, 3).unwrap().is_none());
+
+        assert!(chunked.client().position_map.is_empty());
+    }
+
+    #[test]
+    fn chunked_fixed_number_of_accesses() {
+        let chunks_per_value = 5;
+        let (mut chunked, mut path_oram) =
+            _chunked_oram(4, chunks_per_value, 8);
+
+        let mut nb_accesses = chunked.client().nb_accesses();
+        for length in [0, 3, 8, 40, 12] {
+            chunked.write(&mut path_oram, 0, &vec![7; length]).unwrap();
+            chunked.read(&mut path_oram, 0).unwrap();
+            chunked.read(&mut path_oram, 1).unwrap();
+
+            let new_nb_accesses = chunked.client().nb_accesses();
+            assert_eq!(
+                new_nb_accesses - nb_accesses,
+                3 * chunks_per_value as u64
+            );
+            nb_accesses = new_nb_accesses;
+        }
+
+        chunked.remove(&mut path_oram, 0).unwrap();
+        assert_eq!(
+            chunked.client().nb_accesses() - nb_accesses,
+            chunks_per_value as u64
+        );
+    }
+
+    #[test]
+    fn chunked_value_too_large() {
+        let (mut chunked, mut path_oram) = _chunked_oram(4, 4, 8);
+
+        let res_write = chunked.write(&mut path_oram, 0, &[0; 33]);
+        assert!(res_write.is_err());
+        assert_eq!(chunked.client().nb_accesses(), 0);
+
+        // Chunk ids of the last values overflow.
+        let res_read = chunked.read(&mut path_oram, u64::MAX);
+        assert!(res_read.is_err());
+    }
+
+    #[test]
+    fn client_update() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        // Updating a missing block creates it.
+        let res_update = client.update(&mut path_oram, 1, |payload| {
+            assert!(payload.is_none());
+            Ok(Some(vec![1]))
+        });
+        assert!(res_update.unwrap().is_none());
+
+        let res_update = client.update(&mut path_oram, 1, |payload| {
+            let mut payload = payload.unwrap().to_vec();
+            payload.push(2);
+            Ok(Some(payload))
+        });
+        assert_eq!(res_update.unwrap(), Some(vec![1]));
+
+        // A failed update leaves the block unchanged but still accesses it.
+        let res_update = client
+            .update(&mut path_oram, 1, |_| Err(std::io::Error::other("abort")));
+        assert!(res_update.is_err());
+
+        let res_update =
+            client.update(&mut path_oram, 1, |_| Ok(Some(vec![0; 17])));
+        assert!(res_update.is_err());
+        assert_eq!(client.nb_accesses(), 4);
+
+        let res_read = client.access(&mut path_oram, AccessType::Read, 1, None);
+        assert_eq!(res_read.unwrap(), Some(vec![1, 2]));
+
+        // Returning `None` removes the block.
+        let res_update = client.update(&mut path_oram, 1, |_| Ok(None));
+        assert_eq!(res_update.unwrap(), Some(vec![1, 2]));
+        assert!(client.position_map.is_empty());
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct _Bytes(Vec<u8>);
+
+    impl Serializable for _Bytes {
+        type Error = CryptoCoreError;
+
+        fn length(&self) -> usize {
+            to_leb128_len(self.0.len()) + self.0.len()
+        }
+
+        fn write(&self, ser: &mut Serializer) -> Result<usize, Self::Error> {
+            ser.write_vec(&self.0)
+        }
+
+        fn read(de: &mut Deserializer) -> Result<Self, Self::Error> {
+            Ok(_Bytes(de.read_vec()?))
+        }
+    }
+
+    fn _oblivious_map(
+        nb_buckets: u64,
+        nb_probes: usize,
+        block_size: usize,
+    ) -> (ObliviousMap<_Bytes, _Bytes>, Oram) {
+        let mut client = ClientOram::new(16.max(nb_buckets as usize)).unwrap();
+        let path_oram = client.setup_oram(block_size).unwrap();
+
+        (
+            ObliviousMap::new(client, nb_buckets, nb_probes).unwrap(),
+            path_oram,
+        )
+    }
+
+    #[test]
+    fn map_null_parameters() {
+        assert!(ObliviousMap::<_Bytes, _Bytes>::new(
+            ClientOram::new(16).unwrap(),
+            0,
+            2
+        )
+        .is_err());
+        assert!(ObliviousMap::<_Bytes, _Bytes>::new(
+            ClientOram::new(16).unwrap(),
+            16,
+            0
+        )
+        .is_err());
+
+        // Candidate buckets are distinct, hence at most one per bucket.
+        let res_new = ObliviousMap::<_Bytes, _Bytes>::new(
+            ClientOram::new(16).unwrap(),
+            1,
+            2,
+        );
+        assert_eq!(res_new.err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn map_insert_get() {
+        let (mut map, mut path_oram) = _oblivious_map(64, 2, 64);
+
+        for i in 0..50u8 {
+            let res_insert = map.insert(
+                &mut path_oram,
+                &_Bytes(vec![i; i as usize % 7 + 1]),
+                &_Bytes(vec![i; 10]),
+            );
+            assert!(res_insert.unwrap().is_none());
+        }
+
+        for i in 0..50u8 {
+            let key = _Bytes(vec![i; i as usize % 7 + 1]);
+            let res_get = map.get(&mut path_oram, &key);
+            assert_eq!(res_get.unwrap(), Some(_Bytes(vec![i; 10])));
+            assert!(map.contains(&mut path_oram, &key).unwrap());
+        }
+
+        let missing = _Bytes(b"missing".to_vec());
+        assert!(map.get(&mut path_oram, &missing).unwrap().is_none());
+        assert!(!map.contains(&mut path_oram, &missing).unwrap());
+    }
+
+    #[test]
+    fn map_overwrite_remove() {
+        let (mut map, mut path_oram) = _oblivious_map(16, 2, 64);
+        let key = _Bytes(b"key".to_vec());
+
+        map.insert(&mut path_oram, &key, &_Bytes(vec![1])).unwrap();
+        let res_insert = map.insert(&mut path_oram, &key, &_Bytes(vec![2]));
+        assert_eq!(res_insert.unwrap(), Some(_Bytes(vec![1])));
+        assert_eq!(
+            map.get(&mut path_oram, &key).unwrap(),
+            Some(_Bytes(vec![2]))
+        );
+
+        let res_remove = map.remove(&mut path_oram, &key);
+        assert_eq!(res_remove.unwrap(), Some(_Bytes(vec![2])));
+        assert!(map.get(&mut path_oram, &key).unwrap().is_none());
+        assert!(map.remove(&mut path_oram, &key).unwrap().is_none());
+
+        // Empty buckets are removed from the ORAM.
+        assert!(map.client().position_map.is_empty());
+    }
+
+    #[test]
+    fn map_collisions() {
+        // A single bucket, every key collides.
+        let (mut map, mut path_oram) = _oblivious_map(1, 1, 32);
+
+        let mut nb_inserted = 0u8;
+        let res_insert = loop {
+            let res_insert = map.insert(
+                &mut path_oram,
+                &_Bytes(vec![nb_inserted]),
+                &_Bytes(vec![nb_inserted; 4]),
+            );
+            if res_insert.is_err() {
+                break res_insert;
+            }
+            nb_inserted += 1;
+        };
+
+        // 1 byte of entry count and 9 bytes per entry.
+        assert_eq!(nb_inserted, 3);
+        assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+
+        // Existing keys can still be updated when the bucket is full.
+        let res_insert =
+            map.insert(&mut path_oram, &_Bytes(vec![1]), &_Bytes(vec![9; 4]));
+        assert_eq!(res_insert.unwrap(), Some(_Bytes(vec![1; 4])));
+
+        for i in 0..nb_inserted {
+            let value = if i == 1 { vec![9; 4] } else { vec![i; 4] };
+            let res_get = map.get(&mut path_oram, &_Bytes(vec![i]));
+            assert_eq!(res_get.unwrap(), Some(_Bytes(value)));
+        }
+        assert!(map
+            .get(&mut path_oram, &_Bytes(vec![nb_inserted]))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn map_distinct_probes() {
+        for nb_probes in 2..=3 {
+            let (mut map, mut path_oram) = _oblivious_map(7, nb_probes, 32);
+
+            // New keys are never mistaken for existing ones, nor lost.
+            let mut nb_inserted = 0u8;
+            let res_insert = loop {
+                let res_insert = map.insert(
+                    &mut path_oram,
+                    &_Bytes(vec![nb_inserted]),
+                    &_Bytes(vec![nb_inserted; 4]),
+                );
+                match res_insert {
+                    Ok(old_value) => assert!(old_value.is_none()),
+                    Err(_) => break res_insert,
+                }
+                nb_inserted += 1;
+            };
+            assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+            assert!(nb_inserted as usize >= 3 * nb_probes);
+
+            for i in 0..nb_inserted {
+                let res_get = map.get(&mut path_oram, &_Bytes(vec![i]));
+                assert_eq!(res_get.unwrap(), Some(_Bytes(vec![i; 4])));
+            }
+        }
+    }
+
+    #[test]
+    fn map_full_update_keeps_entry() {
+        let (mut map, mut path_oram) = _oblivious_map(1, 1, 16);
+
+        // 1 byte of entry count and 7 bytes per entry.
+        for i in 0..2u8 {
+            let res_insert = map.insert(
+                &mut path_oram,
+                &_Bytes(vec![i]),
+                &_Bytes(vec![i; 2]),
+            );
+            assert!(res_insert.unwrap().is_none());
+        }
+
+        // The larger value does not fit, the previous one is kept, without
+        // any additional access.
+        let nb_accesses = map.client().nb_accesses();
+        let res_insert =
+            map.insert(&mut path_oram, &_Bytes(vec![0]), &_Bytes(vec![9; 4]));
+        assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        assert_eq!(map.client().nb_accesses() - nb_accesses, 1);
+        for i in 0..2u8 {
+            let res_get = map.get(&mut path_oram, &_Bytes(vec![i]));
+            assert_eq!(res_get.unwrap(), Some(_Bytes(vec![i; 2])));
+        }
+    }
+
+    #[test]
+    fn map_growing_values() {
+        let nb_probes = 2;
+        let (mut map, mut path_oram) = _oblivious_map(7, nb_probes, 24);
+        let mut model = BTreeMap::new();
+
+        // Values grow until candidate buckets overflow.
+        for length in 1..10 {
+            for i in 0..16u8 {
+                let nb_accesses = map.client().nb_accesses();
+                let value = _Bytes(vec![i; length]);
+                let res_insert =
+                    map.insert(&mut path_oram, &_Bytes(vec![i]), &value);
+                assert_eq!(
+                    map.client().nb_accesses() - nb_accesses,
+                    nb_probes as u64
+                );
+
+                match res_insert {
+                    Ok(old_value) => {
+                        assert_eq!(old_value, model.insert(i, value));
+                    }
+                    Err(e) => assert_eq!(e.kind(), ErrorKind::OutOfMemory),
+                }
+            }
+
+            for i in 0..16u8 {
+                let res_get = map.get(&mut path_oram, &_Bytes(vec![i]));
+                assert_eq!(res_get.unwrap().as_ref(), model.get(&i));
+            }
+        }
+
+        for i in 0..16u8 {
+            let res_remove = map.remove(&mut path_oram, &_Bytes(vec![i]));
+            assert_eq!(res_remove.unwrap(), model.remove(&i));
+            assert!(map
+                .get(&mut path_oram, &_Bytes(vec![i]))
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn map_fixed_number_of_accesses() {
+        let nb_probes = 3;
+        let (mut map, mut path_oram) = _oblivious_map(16, nb_probes, 64);
+        let key = _Bytes(b"key".to_vec());
+        let value = _Bytes(b"value".to_vec());
+
+        let mut nb_accesses = map.client().nb_accesses();
+        for i in 0..6 {
+            match i % 3 {
+                0 => {
+                    map.insert(&mut path_oram, &key, &value).unwrap();
+                }
+                1 => {
+                    map.get(&mut path_oram, &key).unwrap();
+                }
+                _ => {
+                    map.remove(&mut path_oram, &key).unwrap();
+                }
+            }
+
+            let new_nb_accesses = map.client().nb_accesses();
+            assert_eq!(new_nb_accesses - nb_accesses, nb_probes as u64);
+            nb_accesses = new_nb_accesses;
+        }
+    }
+
+    #[test]
+    fn map_entry_too_large() {
+        let (mut map, mut path_oram) = _oblivious_map(16, 2, 16);
+
+        let res_insert =
+            map.insert(&mut path_oram, &_Bytes(vec![1]), &_Bytes(vec![1; 11]));
+        assert!(res_insert.is_err());
+        assert_eq!(map.client().nb_accesses(), 0);
+
+        let res_insert =
+            map.insert(&mut path_oram, &_Bytes(vec![1]), &_Bytes(vec![1; 10]));
+        assert!(res_insert.is_ok());
+    }
+
+    #[test]
+    fn client_read_and_remove_add() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        // Added blocks wait in the stash until they are evicted.
+        let leaf = client.random_leaf();
+        assert!(client.add(1, leaf, vec![1; 4]).is_ok());
+        assert!(client.add(2, leaf, vec![1; 17]).is_err());
+        assert_eq!(client.stash.len(), 1);
+        assert_eq!(client.nb_accesses(), 0);
+
+        for _ in 0..10 {
+            assert!(client.dummy_access(&mut path_oram).is_ok());
+        }
+
+        // Untracked blocks are not in the position map.
+        assert!(client.position_map.is_empty());
+
+        let res_read = client.read_and_remove(&mut path_oram, 1, leaf);
+        assert_eq!(res_read.unwrap(), Some(vec![1; 4]));
+
+        let res_read = client.read_and_remove(&mut path_oram, 1, leaf);
+        assert!(res_read.unwrap().is_none());
+        assert_eq!(client.nb_accesses(), 12);
+    }
+
+    #[test]
+    fn client_add_outsourced_stash() {
+        let nb_items: usize = 15 * BUCKET_SIZE;
+        let block_size: usize = 16;
+
+        let mut client = ClientOram::new(nb_items).unwrap();
+        client.outsource_stash(16);
+        let mut path_oram = client.setup_oram(block_size).unwrap();
+
+        let leaf = client.random_leaf();
+        client.add(1, leaf, vec![1; 4]).unwrap();
+        client.dummy_access(&mut path_oram).unwrap();
+        assert!(client.stash.is_empty());
+
+        let res_read = client.read_and_remove(&mut path_oram, 1, leaf);
+        assert_eq!(res_read.unwrap(), Some(vec![1; 4]));
+    }
+
+    fn _bplus_tree(
+        max_height: usize,
+        block_size: usize,
+    ) -> (ObliviousBPlusTree<_Bytes, _Bytes>, Oram) {
+        let mut client = ClientOram::new(1 << 9).unwrap();
+        let path_oram = client.setup_oram(block_size).unwrap();
+
+        // Keys and values of 8 bytes are serialized in 9 bytes.
+        (
+            ObliviousBPlusTree::new(client, max_height, 9, 9).unwrap(),
+            path_oram,
+        )
+    }
+
+    fn _key(i: u64) -> _Bytes {
+        _Bytes(i.to_be_bytes().to_vec())
+    }
+
+    fn _value(i: u64) -> _Bytes {
+        _Bytes((i * 3).to_le_bytes().to_vec())
+    }
+
+    #[test]
+    fn bplus_tree_block_too_small() {
+        let mut client = ClientOram::new(1 << 9).unwrap();
+        client.setup_oram(100).unwrap();
+
+        assert!(
+            ObliviousBPlusTree::<_Bytes, _Bytes>::new(client, 4, 9, 9).is_err()
+        );
+
+        let mut client = ClientOram::new(1 << 9).unwrap();
+        client.setup_oram(120).unwrap();
+
+        let res_tree =
+            ObliviousBPlusTree::<_Bytes, _Bytes>::new(client, 4, 9, 9);
+        assert_eq!(res_tree.unwrap().max_keys(), 3);
+    }
+
+    #[test]
+    fn bplus_tree_insert_get() {
+        let (mut tree, mut path_oram) = _bplus_tree(12, 120);
+
+        let mut csprng = CsRng::from_entropy();
+        let mut keys: Vec<u64> = (0..200).map(|i| i * 2).collect();
+        keys.shuffle(&mut csprng);
+
+        for &i in &keys {
+            let res_insert = tree.insert(&mut path_oram, _key(i), _value(i));
+            assert!(res_insert.unwrap().is_none());
+        }
+        assert!(tree.height() > 3);
+
+        for i in 0..400 {
+            let res_get = tree.get(&mut path_oram, &_key(i));
+            let expected = if i % 2 == 0 { Some(_value(i)) } else { None };
+            assert_eq!(res_get.unwrap(), expected);
+        }
+
+        // Overwriting returns the previous value.
+        let res_insert = tree.insert(&mut path_oram, _key(10), _value(0));
+        assert_eq!(res_insert.unwrap(), Some(_value(10)));
+        let res_get = tree.get(&mut path_oram, &_key(10));
+        assert_eq!(res_get.unwrap(), Some(_value(0)));
+    }
+
+    #[test]
+    fn bplus_tree_remove() {
+        let (mut tree, mut path_oram) = _bplus_tree(12, 120);
+        let mut model = BTreeMap::new();
+
+        let mut csprng = CsRng::from_entropy();
+        let mut keys: Vec<u64> = (0..150).collect();
+        keys.shuffle(&mut csprng);
+
+        for &i in &keys {
+            tree.insert(&mut path_oram, _key(i), _value(i)).unwrap();
+            model.insert(i, _value(i));
+        }
+
+        keys.shuffle(&mut csprng);
+        for (n, &i) in keys.iter().enumerate() {
+            let res_remove = tree.remove(&mut path_oram, &_key(i));
+            assert_eq!(res_remove.unwrap(), model.remove(&i));
+
+            // Removing twice does nothing.
+            let res_remove = tree.remove(&mut path_oram, &_key(i));
+            assert!(res_remove.unwrap().is_none());
+
+            if n % 25 == 0 {
+                for j in 0..150 {
+                    let res_get = tree.get(&mut path_oram, &_key(j));
+                    assert_eq!(res_get.unwrap().as_ref(), model.get(&j));
+                }
+            }
+        }
+
+        assert_eq!(tree.height(), 0);
+        assert!(tree.get(&mut path_oram, &_key(0)).unwrap().is_none());
+
+        // Every node has been removed from the ORAM.
+        let client = tree.client();
+        assert!(client.position_map.is_empty());
+    }
+
+    #[test]
+    fn bplus_tree_range() {
+        let (mut tree, mut path_oram) = _bplus_tree(12, 120);
+        let mut model = BTreeMap::new();
+
+        let mut csprng = CsRng::from_entropy();
+        let mut keys: Vec<u64> = (0..100).map(|i| i * 3).collect();
+        keys.shuffle(&mut csprng);
+        for &i in &keys {
+            tree.insert(&mut path_oram, _key(i), _value(i)).unwrap();
+            model.insert(_key(i), _value(i));
+        }
+
+        let bounds = [
+            (Bound::Unbounded, Bound::Unbounded),
+            (Bound::Included(_key(30)), Bound::Excluded(_key(90))),
+            (Bound::Excluded(_key(30)), Bound::Included(_key(90))),
+            (Bound::Included(_key(31)), Bound::Unbounded),
+            (Bound::Unbounded, Bound::Included(_key(2))),
+            (Bound::Included(_key(1000)), Bound::Unbounded),
+            (Bound::Included(_key(50)), Bound::Excluded(_key(50))),
+        ];
+
+        for range in bounds {
+            let res_range: Result<Vec<_>, _> =
+                tree.range(&mut path_oram, range.clone()).collect();
+            let expected: Vec<_> = model
+                .range(range)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            assert_eq!(res_range.unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn bplus_tree_fixed_number_of_accesses() {
+        let max_height = 6;
+        let (mut tree, mut path_oram) = _bplus_tree(max_height, 120);
+        let accesses_per_operation = tree.accesses_per_operation() as u64;
+        assert_eq!(accesses_per_operation, 2 * max_height as u64);
+
+        let mut nb_accesses = tree.client().nb_accesses();
+        let mut check_accesses = |tree: &ObliviousBPlusTree<_Bytes, _Bytes>| {
+            let new_nb_accesses = tree.client().nb_accesses();
+            assert_eq!(new_nb_accesses - nb_accesses, accesses_per_operation);
+            nb_accesses = new_nb_accesses;
+        };
+
+        // Operations on an empty tree cost the same.
+        tree.get(&mut path_oram, &_key(1)).unwrap();
+        check_accesses(&tree);
+        tree.remove(&mut path_oram, &_key(1)).unwrap();
+        check_accesses(&tree);
+
+        for i in 0..40 {
+            tree.insert(&mut path_oram, _key(i), _value(i)).unwrap();
+            check_accesses(&tree);
+        }
+        for i in 0..40 {
+            tree.get(&mut path_oram, &_key(i * 7)).unwrap();
+            check_accesses(&tree);
+            tree.remove(&mut path_oram, &_key(i * 3)).unwrap();
+            check_accesses(&tree);
+        }
+    }
+
+    #[test]
+    fn bplus_tree_full() {
+        let (mut tree, mut path_oram) = _bplus_tree(1, 120);
+
+        for i in 0..3 {
+            tree.insert(&mut path_oram, _key(i), _value(i)).unwrap();
+        }
+
+        let res_insert = tree.insert(&mut path_oram, _key(3), _value(3));
+        assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        assert_eq!(tree.client().nb_accesses(), 8);
+
+        // The tree is left unchanged.
+        for i in 0..3 {
+            let res_get = tree.get(&mut path_oram, &_key(i));
+            assert_eq!(res_get.unwrap(), Some(_value(i)));
+        }
+        assert!(tree.get(&mut path_oram, &_key(3)).unwrap().is_none());
+    }
+
+    #[test]
+    fn bplus_tree_full_update() {
+        for max_height in 1..=2 {
+            let (mut tree, mut path_oram) = _bplus_tree(max_height, 120);
+
+            let mut nb_inserted = 0;
+            let res_insert = loop {
+                let res_insert = tree.insert(
+                    &mut path_oram,
+                    _key(nb_inserted),
+                    _value(nb_inserted),
+                );
+                if res_insert.is_err() {
+                    break res_insert;
+                }
+                nb_inserted += 1;
+            };
+            assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+            assert_eq!(tree.height(), max_height);
+
+            // Existing keys can still be updated when the tree is full.
+            for i in 0..nb_inserted {
+                let nb_accesses = tree.client().nb_accesses();
+                let res_insert =
+                    tree.insert(&mut path_oram, _key(i), _value(i + 1));
+                assert_eq!(res_insert.unwrap(), Some(_value(i)));
+                assert_eq!(
+                    tree.client().nb_accesses() - nb_accesses,
+                    tree.accesses_per_operation() as u64
+                );
+            }
+            for i in 0..nb_inserted {
+                let res_get = tree.get(&mut path_oram, &_key(i));
+                assert_eq!(res_get.unwrap(), Some(_value(i + 1)));
+            }
+        }
+    }
+
+    #[test]
+    fn bplus_tree_full_new_keys() {
+        for max_height in 2..=3 {
+            let (mut tree, mut path_oram) = _bplus_tree(max_height, 120);
+            let mut model = BTreeMap::new();
+
+            // Increasing keys leave the leaves split before half full.
+            let mut i = 0;
+            let res_insert = loop {
+                let res_insert =
+                    tree.insert(&mut path_oram, _key(10 * i), _value(i));
+                if res_insert.is_err() {
+                    break res_insert;
+                }
+                model.insert(10 * i, i);
+                i += 1;
+            };
+            assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+            assert_eq!(tree.height(), max_height);
+
+            // New keys are still inserted wherever their leaf has room.
+            let mut nb_new_keys = 0;
+            for j in 0..i {
+                let nb_accesses = tree.client().nb_accesses();
+                let key = 10 * j + 1;
+                match tree.insert(&mut path_oram, _key(key), _value(key)) {
+                    Ok(old_value) => {
+                        assert!(old_value.is_none());
+                        model.insert(key, key);
+                        nb_new_keys += 1;
+                    }
+                    Err(e) => assert_eq!(e.kind(), ErrorKind::OutOfMemory),
+                }
+                assert_eq!(
+                    tree.client().nb_accesses() - nb_accesses,
+                    tree.accesses_per_operation() as u64
+                );
+            }
+            assert!(nb_new_keys > 0);
+            assert_eq!(tree.height(), max_height);
+
+            let res_range: Vec<_> = tree
+                .range(&mut path_oram, ..)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            let expected: Vec<_> = model
+                .iter()
+                .map(|(&key, &value)| (_key(key), _value(value)))
+                .collect();
+            assert_eq!(res_range, expected);
+        }
+    }
+
+    #[test]
+    fn bplus_tree_entry_too_large() {
+        let (mut tree, mut path_oram) = _bplus_tree(4, 120);
+
+        let res_insert =
+            tree.insert(&mut path_oram, _Bytes(vec![0; 9]), _value(0));
+        assert!(res_insert.is_err());
+        assert_eq!(tree.client().nb_accesses(), 0);
+    }
+
+    fn _oblivious_heap(capacity: usize) -> (ObliviousHeap<_Bytes>, Oram) {
+        let mut client = ClientOram::new(16.max(2 * capacity)).unwrap();
+        let path_oram = client.setup_oram(32).unwrap();
+
+        (ObliviousHeap::new(client, capacity).unwrap(), path_oram)
+    }
+
+    #[test]
+    fn heap_invalid_parameters() {
+        let mut client = ClientOram::new(16).unwrap();
+        client.setup_oram(32).unwrap();
+        let res_heap = ObliviousHeap::<_Bytes>::new(client, 0);
+        assert_eq!(res_heap.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        let mut client = ClientOram::new(16).unwrap();
+        client.setup_oram(15).unwrap();
+        let res_heap = ObliviousHeap::<_Bytes>::new(client, 8);
+        assert_eq!(res_heap.err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn heap_insert_extract_min() {
+        let (mut heap, mut path_oram) = _oblivious_heap(50);
+        let mut csprng = CsRng::from_entropy();
+
+        let mut priorities: Vec<u64> =
+            (0..50).map(|_| csprng.next_u64() % 20).collect();
+        for (i, priority) in priorities.iter().enumerate() {
+            heap.insert(&mut path_oram, *priority, &_value(i as u64))
+                .unwrap();
+        }
+        assert_eq!(heap.len(), 50);
+
+        priorities.sort();
+        for priority in priorities {
+            let (min, _) = heap.extract_min(&mut path_oram).unwrap().unwrap();
+            assert_eq!(min, priority);
+        }
+
+        assert!(heap.is_empty());
+        assert!(heap.extract_min(&mut path_oram).unwrap().is_none());
+    }
+
+    #[test]
+    fn heap_equal_priorities_in_insertion_order() {
+        let (mut heap, mut path_oram) = _oblivious_heap(8);
+
+        for i in 0..8 {
+            heap.insert(&mut path_oram, 7, &_value(i)).unwrap();
+        }
+
+        for i in 0..8 {
+            let res_extract = heap.extract_min(&mut path_oram).unwrap();
+            assert_eq!(res_extract, Some((7, _value(i))));
+        }
+    }
+
+    #[test]
+    fn heap_decrease_key() {
+        let (mut heap, mut path_oram) = _oblivious_heap(16);
+
+        let handles: Vec<u64> = (0..16)
+            .map(|i| heap.insert(&mut path_oram, 100 + i, &_value(i)).unwrap())
+            .collect();
+
+        heap.decrease_key(&mut path_oram, handles[12], 3).unwrap();
+        heap.decrease_key(&mut path_oram, handles[5], 1).unwrap();
+        // Decreasing to the same priority is allowed.
+        heap.decrease_key(&mut path_oram, handles[9], 109).unwrap();
+
+        let res_decrease = heap.decrease_key(&mut path_oram, handles[0], 200);
+        assert_eq!(res_decrease.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let res_extract = heap.extract_min(&mut path_oram).unwrap();
+        assert_eq!(res_extract, Some((1, _value(5))));
+        let res_extract = heap.extract_min(&mut path_oram).unwrap();
+        assert_eq!(res_extract, Some((3, _value(12))));
+
+        // Extracted entries cannot be decreased anymore.
+        let res_decrease = heap.decrease_key(&mut path_oram, handles[5], 0);
+        assert_eq!(res_decrease.unwrap_err().kind(), ErrorKind::NotFound);
+        let res_decrease = heap.decrease_key(&mut path_oram, 16, 0);
+        assert_eq!(res_decrease.unwrap_err().kind(), ErrorKind::NotFound);
+
+        for i in (0..16).filter(|i| *i != 5 && *i != 12) {
+            let res_extract = heap.extract_min(&mut path_oram).unwrap();
+            assert_eq!(res_extract, Some((100 + i, _value(i))));
+        }
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn heap_random_operations() {
+        let (mut heap, mut path_oram) = _oblivious_heap(32);
+        let mut csprng = CsRng::from_entropy();
+
+        // Model of the heap, ordered as the heap orders its entries.
+        let mut model = BTreeMap::new();
+        for i in 0..150 {
+            match csprng.next_u64() % 3 {
+                0 | 1 if model.len() < 32 => {
+                    let priority = csprng.next_u64() % 50;
+                    let handle = heap
+                        .insert(&mut path_oram, priority, &_value(i))
+                        .unwrap();
+                    model.insert((priority, handle), _value(i));
+                }
+                0 => {
+                    let ((priority, handle), value) =
+                        model.pop_first().unwrap();
+                    let res_extract = heap.extract_min(&mut path_oram);
+                    assert_eq!(res_extract.unwrap(), Some((priority, value)));
+                    let res_decrease =
+                        heap.decrease_key(&mut path_oram, handle, 0);
+                    assert_eq!(
+                        res_decrease.unwrap_err().kind(),
+                        ErrorKind::NotFound
+                    );
+                }
+                _ => {
+                    let keys: Vec<(u64, u64)> = model.keys().copied().collect();
+                    if let Some(&(priority, handle)) = keys.choose(&mut csprng)
+                    {
+                        let new_priority = priority / 2;
+                        heap.decrease_key(&mut path_oram, handle, new_priority)
+                            .unwrap();
+                        let value = model.remove(&(priority, handle)).unwrap();
+                        model.insert((new_priority, handle), value);
+                    }
+                }
+            }
+            assert_eq!(heap.len(), model.len());
+        }
+
+        while let Some(((priority, _), value)) = model.pop_first() {
+            let res_extract = heap.extract_min(&mut path_oram).unwrap();
+            assert_eq!(res_extract, Some((priority, value)));
+        }
+    }
+
+    #[test]
+    fn heap_fixed_number_of_accesses() {
+        let (mut heap, mut path_oram) = _oblivious_heap(20);
+        let accesses_per_operation = heap.accesses_per_operation() as u64;
+        assert_eq!(accesses_per_operation, 4 * 4 + 5);
+
+        let mut nb_accesses = heap.client().nb_accesses();
+        let mut check_accesses = |heap: &ObliviousHeap<_Bytes>| {
+            let new_nb_accesses = heap.client().nb_accesses();
+            assert_eq!(new_nb_accesses - nb_accesses, accesses_per_operation);
+            nb_accesses = new_nb_accesses;
+        };
+
+        // Operations on an empty heap cost the same.
+        heap.extract_min(&mut path_oram).unwrap();
+        check_accesses(&heap);
+        heap.decrease_key(&mut path_oram, 0, 0).unwrap_err();
+        check_accesses(&heap);
+
+        // Decreasing priorities are sifted up to the root.
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            let handle =
+                heap.insert(&mut path_oram, 100 - i, &_value(i)).unwrap();
+            check_accesses(&heap);
+            handles.push(handle);
+        }
+        for handle in handles.iter().skip(10) {
+            heap.decrease_key(&mut path_oram, *handle, 0).unwrap();
+            check_accesses(&heap);
+        }
+        for _ in 0..20 {
+            heap.extract_min(&mut path_oram).unwrap().unwrap();
+            check_accesses(&heap);
+        }
+    }
+
+    #[test]
+    fn heap_full() {
+        let (mut heap, mut path_oram) = _oblivious_heap(4);
+
+        for i in 0..4 {
+            heap.insert(&mut path_oram, i, &_value(i)).unwrap();
+        }
+        let nb_accesses = heap.client().nb_accesses();
+
+        let res_insert = heap.insert(&mut path_oram, 0, &_value(4));
+        assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        assert_eq!(heap.client().nb_accesses(), nb_accesses);
+
+        heap.extract_min(&mut path_oram).unwrap();
+        heap.insert(&mut path_oram, 0, &_value(4)).unwrap();
+        let res_extract = heap.extract_min(&mut path_oram).unwrap();
+        assert_eq!(res_extract, Some((0, _value(4))));
+    }
+
+    #[test]
+    fn heap_entry_too_large() {
+        let (mut heap, mut path_oram) = _oblivious_heap(4);
+
+        // Serialized in 17 bytes, plus the 16 bytes of priority and handle.
+        let res_insert = heap.insert(&mut path_oram, 0, &_Bytes(vec![0; 16]));
+        assert_eq!(res_insert.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(heap.is_empty());
+        assert_eq!(heap.client().nb_accesses(), 0);
+    }
+
+    fn _stack(capacity: usize) -> (ObliviousStack<_Bytes>, Oram) {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(32).unwrap();
+
+        (ObliviousStack::new(client, capacity), path_oram)
+    }
+
+    fn _queue(capacity: usize) -> (ObliviousQueue<_Bytes>, Oram) {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(32).unwrap();
+
+        (ObliviousQueue::new(client, capacity), path_oram)
+    }
+
+    #[test]
+    fn stack_push_pop() {
+        let (mut stack, mut path_oram) = _stack(64);
+
+        assert!(stack.pop(&mut path_oram).unwrap().is_none());
+        for i in 0..40 {
+            stack.push(&mut path_oram, &_value(i)).unwrap();
+        }
+        assert_eq!(stack.len(), 40);
+        for i in (20..40).rev() {
+            assert_eq!(stack.pop(&mut path_oram).unwrap(), Some(_value(i)));
+        }
+
+        // Interleaved pushes and pops.
+        for i in 40..60 {
+            stack.push(&mut path_oram, &_value(i)).unwrap();
+            if i % 2 == 0 {
+                assert_eq!(stack.pop(&mut path_oram).unwrap(), Some(_value(i)));
+            }
+        }
+        for i in (40..60).rev().filter(|i| i % 2 == 1).chain((0..20).rev()) {
+            assert_eq!(stack.pop(&mut path_oram).unwrap(), Some(_value(i)));
+        }
+
+        assert!(stack.is_empty());
+        assert!(stack.pop(&mut path_oram).unwrap().is_none());
+        // The elements are not in the position map.
+        assert!(stack.client().position_map.is_empty());
+    }
+
+    #[test]
+    fn queue_enqueue_dequeue() {
+        let (mut queue, mut path_oram) = _queue(64);
+
+        assert!(queue.dequeue(&mut path_oram).unwrap().is_none());
+        for i in 0..40 {
+            queue.enqueue(&mut path_oram, &_value(i)).unwrap();
+        }
+        assert_eq!(queue.len(), 40);
+        for i in 0..20 {
+            let res_dequeue = queue.dequeue(&mut path_oram).unwrap();
+            assert_eq!(res_dequeue, Some(_value(i)));
+        }
+
+        for i in 40..60 {
+            queue.enqueue(&mut path_oram, &_value(i)).unwrap();
+            let res_dequeue = queue.dequeue(&mut path_oram).unwrap();
+            assert_eq!(res_dequeue, Some(_value(i - 20)));
+        }
+        for i in 40..60 {
+            let res_dequeue = queue.dequeue(&mut path_oram).unwrap();
+            assert_eq!(res_dequeue, Some(_value(i)));
+        }
+
+        assert!(queue.is_empty());
+        assert!(queue.dequeue(&mut path_oram).unwrap().is_none());
+        assert!(queue.client().position_map.is_empty());
+
+        // The queue keeps working once emptied.
+        queue.enqueue(&mut path_oram, &_value(60)).unwrap();
+        let res_dequeue = queue.dequeue(&mut path_oram).unwrap();
+        assert_eq!(res_dequeue, Some(_value(60)));
+    }
+
+    #[test]
+    fn stack_queue_one_access_per_operation() {
+        let (mut stack, mut path_oram) = _stack(16);
+        for i in 0..16 {
+            stack.push(&mut path_oram, &_value(i)).unwrap();
+            assert_eq!(stack.client().nb_accesses(), i + 1);
+        }
+        for i in 0..17 {
+            stack.pop(&mut path_oram).unwrap();
+            assert_eq!(stack.client().nb_accesses(), 16 + i + 1);
+        }
+
+        let (mut queue, mut path_oram) = _queue(16);
+        for i in 0..16 {
+            queue.enqueue(&mut path_oram, &_value(i)).unwrap();
+            assert_eq!(queue.client().nb_accesses(), i + 1);
+        }
+        for i in 0..17 {
+            queue.dequeue(&mut path_oram).unwrap();
+            assert_eq!(queue.client().nb_accesses(), 16 + i + 1);
+        }
+    }
+
+    #[test]
+    fn stack_queue_full() {
+        let (mut stack, mut path_oram) = _stack(2);
+        stack.push(&mut path_oram, &_value(0)).unwrap();
+        stack.push(&mut path_oram, &_value(1)).unwrap();
+        let res_push = stack.push(&mut path_oram, &_value(2));
+        assert_eq!(res_push.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        assert_eq!(stack.client().nb_accesses(), 2);
+        assert_eq!(stack.pop(&mut path_oram).unwrap(), Some(_value(1)));
+
+        let (mut queue, mut path_oram) = _queue(2);
+        queue.enqueue(&mut path_oram, &_value(0)).unwrap();
+        queue.enqueue(&mut path_oram, &_value(1)).unwrap();
+        let res_enqueue = queue.enqueue(&mut path_oram, &_value(2));
+        assert_eq!(res_enqueue.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        assert_eq!(queue.client().nb_accesses(), 2);
+        assert_eq!(queue.dequeue(&mut path_oram).unwrap(), Some(_value(0)));
+    }
+
+    #[test]
+    fn stack_queue_element_too_large() {
+        // Serialized in 16 bytes, plus the 17 bytes of the pointer.
+        let value = _Bytes(vec![0; 15]);
+
+        let (mut stack, mut path_oram) = _stack(4);
+        let res_push = stack.push(&mut path_oram, &value);
+        assert_eq!(res_push.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let (mut queue, mut path_oram) = _queue(4);
+        let res_enqueue = queue.enqueue(&mut path_oram, &value);
+        assert_eq!(res_enqueue.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn stack_queue_outsourced_stash() {
+        let mut client = ClientOram::new(64).unwrap();
+        client.outsource_stash(64);
+        let mut path_oram = client.setup_oram(32).unwrap();
+        let mut queue = ObliviousQueue::<_Bytes>::new(client, 32);
+
+        for i in 0..32 {
+            queue.enqueue(&mut path_oram, &_value(i)).unwrap();
+        }
+        for i in 0..32 {
+            let res_dequeue = queue.dequeue(&mut path_oram).unwrap();
+            assert_eq!(res_dequeue, Some(_value(i)));
+        }
+    }
+
+    #[test]
+    fn setup_oram_with_blocks() {
+        let nb_items = 64;
+        let block_size = 16;
+        let mut client = ClientOram::new(nb_items).unwrap();
+        let mut csprng = CsRng::from_entropy();
+
+        let mut values = Vec::new();
+        for _ in 0..nb_items {
+            let mut value = vec![0; block_size];
+            csprng.fill_bytes(&mut value);
+            values.push(value);
+        }
+
+        let blocks = values
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, v)| (i as u64, v));
+        let mut path_oram =
+            client.setup_oram_with_blocks(block_size, blocks).unwrap();
+        assert_eq!(client.nb_accesses(), 0);
+        assert_eq!(client.position_map.len(), nb_items);
+
+        // Slots of a tree of height 5 once padded with dummies.
+        let nb_slots =
+            _complete_tree_size(path_oram.tree().root.as_deref()) * BUCKET_SIZE;
+        assert_eq!(nb_slots, 31 * BUCKET_SIZE);
+
+        for (i, value) in values.iter().enumerate() {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, i as u64, None);
+            assert_eq!(res_read.unwrap().as_ref(), Some(value));
+        }
+    }
+
+    #[test]
+    fn setup_oram_with_blocks_overflow_to_stash() {
+        // A tree of 7 buckets only has 28 slots.
+        let mut client = ClientOram::new(16).unwrap();
+        let blocks = (0..40).map(|i: u64| (i, i.to_le_bytes().to_vec()));
+        let mut path_oram = client.setup_oram_with_blocks(8, blocks).unwrap();
+        assert!(client.stash.len() >= 12);
+
+        for i in 0..40u64 {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, i, None);
+            assert_eq!(res_read.unwrap(), Some(i.to_le_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn setup_oram_with_blocks_outsourced_stash() {
+        let mut client = ClientOram::new(16).unwrap();
+        // More than the 28 slots of the tree, never more than the stash.
+        client.outsource_stash(30);
+        let blocks = (0..30).map(|i: u64| (i, i.to_le_bytes().to_vec()));
+        let mut path_oram = client.setup_oram_with_blocks(8, blocks).unwrap();
+
+        // The stash has been uploaded, padded with dummies.
+        assert!(client.stash.is_empty());
+        assert_eq!(path_oram.read_stash().len(), 30);
+
+        for i in 0..30u64 {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, i, None);
+            assert_eq!(res_read.unwrap(), Some(i.to_le_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn setup_oram_with_invalid_blocks() {
+        let mut client = ClientOram::new(16).unwrap();
+        let blocks = vec![(0, vec![0; 8]), (1, vec![1; 8]), (0, vec![2; 8])];
+        let res_setup = client.setup_oram_with_blocks(8, blocks);
+        assert_eq!(res_setup.err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert!(client.position_map.is_empty());
+
+        let blocks = vec![(0, vec![0; 8]), (1, vec![1; 9])];
+        let res_setup = client.setup_oram_with_blocks(8, blocks);
+        assert_eq!(res_setup.err().unwrap().kind(), ErrorKind::InvalidInput);
+        assert!(client.position_map.is_empty());
+    }
+
+    #[test]
+    fn client_invalid_nb_items() {
+        for nb_items in [0, usize::MAX] {
+            let res_client = ClientOram::new(nb_items);
+            assert_eq!(
+                res_client.err().unwrap().kind(),
+                ErrorKind::InvalidInput
+            );
+            let res_client =
+                ClientOram::<Aes256Gcm>::with_key(nb_items, &[3; 32]);
+            assert_eq!(
+                res_client.err().unwrap().kind(),
+                ErrorKind::InvalidInput
+            );
+            let res_client =
+                ClientOram::<Aes256Gcm>::with_seed(nb_items, [3; 32]);
+            assert_eq!(
+                res_client.err().unwrap().kind(),
+                ErrorKind::InvalidInput
+            );
+        }
+    }
+
+    #[test]
+    fn client_grow() {
+        let mut client = ClientOram::new(32).unwrap();
+        let mut path_oram = client.setup_oram(8).unwrap();
+        assert_eq!(path_oram.tree().height(), 4);
+
+        for i in 0..32u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i,
+                    Some(i.to_le_bytes().to_vec()),
+                )
+                .unwrap();
+        }
+
+        client.grow(&mut path_oram).unwrap();
+        assert_eq!(path_oram.tree().height(), 5);
+        assert_eq!(_complete_tree_size(path_oram.tree().root.as_deref()), 31);
+        assert!(client.position_map.values().all(|leaf| *leaf < 16));
+
+        // Former blocks are still found, new ones fill the added capacity.
+        for i in 32..64u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i,
+                    Some(i.to_le_bytes().to_vec()),
+                )
+                .unwrap();
+        }
+        for i in 0..64u64 {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, i, None);
+            assert_eq!(res_read.unwrap(), Some(i.to_le_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn client_shrink() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(8).unwrap();
+
+        for i in 0..24u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i,
+                    Some(i.to_le_bytes().to_vec()),
+                )
+                .unwrap();
+        }
+
+        client.shrink(&mut path_oram).unwrap();
+        assert_eq!(path_oram.tree().height(), 4);
+        assert_eq!(_complete_tree_size(path_oram.tree().root.as_deref()), 15);
+        assert!(client.position_map.values().all(|leaf| *leaf < 8));
+
+        for i in 0..24u64 {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, i, None);
+            assert_eq!(res_read.unwrap(), Some(i.to_le_bytes().to_vec()));
+        }
+
+        // 24 blocks do not fit in a capacity of 16.
+        let res_shrink = client.shrink(&mut path_oram);
+        assert_eq!(res_shrink.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(path_oram.tree().height(), 4);
+    }
+
+    #[test]
+    fn client_resize_pointer_blocks() {
+        let mut client =
+            ClientOram::<Aes256Gcm>::with_key(64, &[3; 32]).unwrap();
+        let mut path_oram = client.setup_oram(8).unwrap();
+        client
+            .access(&mut path_oram, AccessType::Write, 0, Some(vec![1; 8]))
+            .unwrap();
+
+        // The leaf of the block, kept by the caller, could not be remapped.
+        let leaf = client.params().nb_leaves() - 1;
+        client.add(1 << 40, leaf, vec![2; 8]).unwrap();
+        client.dummy_access(&mut path_oram).unwrap();
+
+        let height = path_oram.tree().height();
+        let res_shrink = client.shrink(&mut path_oram);
+        assert_eq!(res_shrink.unwrap_err().kind(), ErrorKind::InvalidInput);
+        let res_grow = client.grow(&mut path_oram);
+        assert_eq!(res_grow.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(path_oram.tree().height(), height);
+
+        // The restored client knows about the block as well.
+        let path = _checkpoint_path("resize_pointer_blocks");
+        client.checkpoint(&path).unwrap();
+        let mut client =
+            ClientOram::<Aes256Gcm>::restore(&path, &[3; 32]).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(client.grow(&mut path_oram).is_err());
+
+        let res_read = client.read_and_remove(&mut path_oram, 1 << 40, leaf);
+        assert_eq!(res_read.unwrap(), Some(vec![2; 8]));
+        client.shrink(&mut path_oram).unwrap();
+        client.grow(&mut path_oram).unwrap();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+    }
+
+    #[test]
+    fn client_shrink_outsourced_stash() {
+        let mut client = ClientOram::new(64).unwrap();
+        client.outsource_stash(4);
+        let mut path_oram = client.setup_oram(8).unwrap();
+        for i in 0..24u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i,
+                    Some(i.to_le_bytes().to_vec()),
+                )
+                .unwrap();
+        }
+
+        // The blocks of the leaves do not fit in the stash.
+        let height = path_oram.tree().height();
+        let res_shrink = client.shrink(&mut path_oram);
+        assert_eq!(res_shrink.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        assert_eq!(path_oram.tree().height(), height);
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+        for i in 0..24u64 {
+            let res_read =
+                client.access(&mut path_oram, AccessType::Read, i, None);
+            assert_eq!(res_read.unwrap(), Some(i.to_le_bytes().to_vec()));
+        }
+    }
+
+    #[test]
+    fn client_shrink_to_root() {
+        let mut client = ClientOram::new(8).unwrap();
+        let mut path_oram = client.setup_oram(8).unwrap();
+
+        client
+            .access(&mut path_oram, AccessType::Write, 0, Some(vec![1; 8]))
+            .unwrap();
+        client.shrink(&mut path_oram).unwrap();
+        assert_eq!(path_oram.tree().height(), 1);
+
+        let res_shrink = client.shrink(&mut path_oram);
+        assert_eq!(res_shrink.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let res_read = client.access(&mut path_oram, AccessType::Read, 0, None);
+        assert_eq!(res_read.unwrap(), Some(vec![1; 8]));
+
+        // Growing back from the root only.
+        client.grow(&mut path_oram).unwrap();
+        let res_read = client.access(&mut path_oram, AccessType::Read, 0, None);
+        assert_eq!(res_read.unwrap(), Some(vec![1; 8]));
+    }
+
+    #[test]
+    fn client_grow_other_oram() {
+        let mut client = ClientOram::new(32).unwrap();
+        client.setup_oram(8).unwrap();
+        let mut other_client = ClientOram::new(64).unwrap();
+        let mut other_oram = other_client.setup_oram(8).unwrap();
+
+        let res_grow = client.grow(&mut other_oram);
+        assert_eq!(res_grow.unwrap_err().kind(), ErrorKind::InvalidInput);
+        let res_shrink = client.shrink(&mut other_oram);
+        assert_eq!(res_shrink.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(other_oram.tree().height(), 5);
+    }
+
+    #[test]
+    fn checkpoint_restore_after_grow() {
+        let path = _checkpoint_path("checkpoint_restore_after_grow");
+        let key = [7; 32];
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(16, &key).unwrap();
+        let mut path_oram = client.setup_oram(8).unwrap();
+        client
+            .access(&mut path_oram, AccessType::Write, 3, Some(vec![3; 8]))
+            .unwrap();
+        client.grow(&mut path_oram).unwrap();
+        client.checkpoint(&path).unwrap();
+
+        let mut client = ClientOram::<Aes256Gcm>::restore(&path, &key).unwrap();
+        client.grow(&mut path_oram).unwrap();
+        let res_read = client.access(&mut path_oram, AccessType::Read, 3, None);
+        assert_eq!(res_read.unwrap(), Some(vec![3; 8]));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn params_derived_quantities() {
+        let params = OramParams::new(26 * BUCKET_SIZE, 16).unwrap();
+        assert_eq!(params.nb_items(), 104);
+        assert_eq!(params.bucket_size(), BUCKET_SIZE);
+        assert_eq!(params.block_size(), 16);
+        assert_eq!(params.height(), 5);
+        assert_eq!(params.nb_leaves(), 16);
+        assert_eq!(params.nb_buckets(), 31);
+        assert_eq!(params.nb_slots(), 31 * BUCKET_SIZE);
+
+        // Sizes below two buckets make a tree of its root only.
+        for nb_items in 1..2 * BUCKET_SIZE {
+            let params = OramParams::new(nb_items, 16).unwrap();
+            assert_eq!(params.height(), 1);
+            assert_eq!(params.nb_leaves(), 1);
+        }
+        let params = OramParams::new(2 * BUCKET_SIZE, 16).unwrap();
+        assert_eq!(params.height(), 2);
+
+        // The tree built by the server matches the parameters.
+        for nb_items in [1, 3, 4, 7, 8, 183, 1000] {
+            let params = OramParams::new(nb_items, 16).unwrap();
+            let path_oram = Oram::with_params(&mut Vec::new(), &params);
+            assert_eq!(path_oram.tree().height(), params.height());
+            assert_eq!(
+                _complete_tree_size(path_oram.tree().root.as_deref()),
+                params.nb_buckets()
+            );
+        }
+    }
+
+    #[test]
+    fn params_stash_bound() {
+        let params = OramParams::new(64, 16).unwrap();
+        assert_eq!(params.failure_probability(), DEFAULT_FAILURE_PROBABILITY);
+        assert_eq!(params.stash_bound(), 2 * 93);
+
+        // 14 * 0.6002^R <= 2^-20 from R = 33, doubled as a margin.
+        let params = OramParams::with_failure_probability(
+            64,
+            16,
+            1.0 / (1 << 20) as f64,
+        )
+        .unwrap();
+        assert_eq!(params.stash_bound(), 2 * 33);
+
+        // A lower failure probability needs a larger stash.
+        let mut stash_bound = 0;
+        for exponent in 1..128 {
+            let params = OramParams::with_failure_probability(
+                64,
+                16,
+                0.5_f64.powi(exponent),
+            )
+            .unwrap();
+            assert!(params.stash_bound() >= stash_bound);
+            stash_bound = params.stash_bound();
+        }
+    }
+
+    #[test]
+    fn params_invalid() {
+        let invalid_params = [
+            OramParams::new(0, 16),
+            OramParams::new(usize::MAX, 16),
+            OramParams::new(16, u32::MAX as usize + 1),
+            OramParams::with_failure_probability(16, 16, 0.0),
+            OramParams::with_failure_probability(16, 16, 1.0),
+            OramParams::with_failure_probability(16, 16, f64::NAN),
+        ];
+        for res_params in invalid_params {
+            assert_eq!(res_params.unwrap_err().kind(), ErrorKind::InvalidInput);
+        }
+
+        let params = OramParams::new(16, 16).unwrap();
+        assert!(params.for_nb_items(0).is_err());
+        assert_eq!(params.for_nb_items(32).unwrap().height(), 4);
+        assert_eq!(params.for_block_size(32).unwrap().block_size(), 32);
+
+        let mut client = ClientOram::new(16).unwrap();
+        let res_setup = client.setup_oram(u32::MAX as usize + 1);
+        assert_eq!(res_setup.err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn client_fewer_items_than_a_bucket() {
+        for nb_items in 1..BUCKET_SIZE {
+            let mut client = ClientOram::new(nb_items).unwrap();
+            let mut path_oram = client.setup_oram(8).unwrap();
+            assert_eq!(path_oram.tree().height(), 1);
+
+            for i in 0..nb_items as u64 {
+                client
+                    .access(
+                        &mut path_oram,
+                        AccessType::Write,
+                        i,
+                        Some(i.to_le_bytes().to_vec()),
+                    )
+                    .unwrap();
+            }
+            for i in 0..nb_items as u64 {
+                let res_read =
+                    client.access(&mut path_oram, AccessType::Read, i, None);
+                assert_eq!(res_read.unwrap(), Some(i.to_le_bytes().to_vec()));
+            }
+
+            // Growing from below a bucket adds a level.
+            client.grow(&mut path_oram).unwrap();
+            assert_eq!(path_oram.tree().height(), 2);
+        }
+    }
+
+    #[test]
+    fn client_with_params() {
+        let params =
+            OramParams::with_failure_probability(64, 16, 1e-9).unwrap();
+        let mut client = ClientOram::<Aes256Gcm>::with_params(params);
+        assert_eq!(client.params(), &params);
+
+        let mut path_oram = client.setup_oram(params.block_size()).unwrap();
+        assert_eq!(client.params(), &params);
+        assert_eq!(path_oram.tree().height(), params.height());
+
+        client
+            .access(&mut path_oram, AccessType::Write, 0, Some(vec![0; 16]))
+            .unwrap();
+        assert!(client.position_map[&0] < params.nb_leaves());
+
+        // Parameters are kept by checkpoints.
+        let path = _checkpoint_path("client_with_params");
+        let key = [0; 32];
+        let mut client = ClientOram::<Aes256Gcm>::with_key(64, &key).unwrap();
+        client.setup_oram(8).unwrap();
+        client.checkpoint(&path).unwrap();
+        let client = ClientOram::<Aes256Gcm>::restore(&path, &key).unwrap();
+        assert_eq!(client.params(), &OramParams::new(64, 8).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    fn _seeded_accesses(client: &mut ClientOram, nb_accesses: u64) -> Oram {
+        let mut path_oram = client.setup_oram(16).unwrap();
+
+        for i in 0..nb_accesses {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i % 20,
+                    Some(vec![i as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        path_oram
+    }
+
+    #[test]
+    fn seeded_clients_are_reproducible() {
+        let mut client = ClientOram::with_seed(64, [7; 32]).unwrap();
+        let mut path_oram = _seeded_accesses(&mut client, 100);
+        let mut same_client = ClientOram::with_seed(64, [7; 32]).unwrap();
+        let mut same_path_oram = _seeded_accesses(&mut same_client, 100);
+
+        assert_eq!(client.position_map, same_client.position_map);
+        assert_eq!(client.stash, same_client.stash);
+        for path in 0..16 {
+            let path_items = path_oram.access(AccessType::Read, path, None);
+            let same_path_items =
+                same_path_oram.access(AccessType::Read, path, None);
+            assert_eq!(path_items.unwrap(), same_path_items.unwrap());
+        }
+
+        let mut other_client = ClientOram::with_seed(64, [8; 32]).unwrap();
+        _seeded_accesses(&mut other_client, 100);
+        assert_ne!(client.position_map, other_client.position_map);
+    }
+
+    #[test]
+    fn client_set_leaf_rng() {
+        let mut client = ClientOram::new(64).unwrap();
+        client.set_leaf_rng(CsRng::from_seed([1; 32]));
+        let mut path_oram = _seeded_accesses(&mut client, 50);
+
+        let mut other_client = ClientOram::new(64).unwrap();
+        other_client.set_leaf_rng(CsRng::from_seed([1; 32]));
+        let mut other_path_oram = _seeded_accesses(&mut other_client, 50);
+
+        // Same leaves, but under different keys and nonces.
+        assert_eq!(client.position_map, other_client.position_map);
+        let path_items = path_oram.access(AccessType::Read, 0, None).unwrap();
+        let other_path_items =
+            other_path_oram.access(AccessType::Read, 0, None).unwrap();
+        assert_ne!(path_items, other_path_items);
+    }
+
+    #[test]
+    fn client_set_nonce_rng() {
+        let key = [3; 32];
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(16, &key).unwrap();
+        client.set_nonce_rng(CsRng::from_seed([2; 32]));
+        let dummies = client.generate_dummy_items(8, 16).unwrap();
+
+        let mut other_client =
+            ClientOram::<Aes256Gcm>::with_key(16, &key).unwrap();
+        other_client.set_nonce_rng(CsRng::from_seed([2; 32]));
+        let other_dummies = other_client.generate_dummy_items(8, 16).unwrap();
+        assert_eq!(dummies, other_dummies);
+
+        // Nonces are not repeated from one item to the next.
+        assert_ne!(dummies[0], dummies[1]);
+    }
+
+    #[test]
+    fn simulation_stash_stats() {
+        let mut rng = CsRng::seed_from_u64(1);
+        let stats = simulate(BUCKET_SIZE, 256, 20000, &mut rng).unwrap();
+
+        assert_eq!(stats.bucket_size, BUCKET_SIZE);
+        assert_eq!(stats.nb_items, 256);
+        assert_eq!(stats.nb_accesses, 20000);
+        assert_eq!(stats.histogram.iter().sum::<u64>(), 20000);
+        assert_eq!(stats.max_stash() + 1, stats.histogram.len());
+        assert!(stats.histogram[stats.max_stash()] > 0);
+        assert!(stats.mean_stash() <= stats.max_stash() as f64);
+
+        // No access overflows the stash bound of the client.
+        let params = OramParams::new(256, 16).unwrap();
+        assert!(stats.max_stash() < params.stash_bound());
+        assert_eq!(stats.overflow_probability(stats.max_stash()), 0.0);
+        assert_eq!(stats.overflow_probability(params.stash_bound()), 0.0);
+
+        for bound in 1..stats.histogram.len() {
+            assert!(
+                stats.overflow_probability(bound)
+                    <= stats.overflow_probability(bound - 1)
+            );
+            assert!(
+                stats.overflow_probability(bound)
+                    <= StashStats::theoretical_bound(bound)
+            );
+        }
+    }
+
+    #[test]
+    fn simulation_bucket_sizes() {
+        let mut rng = CsRng::seed_from_u64(2);
+        let small_buckets = simulate(2, 256, 5000, &mut rng).unwrap();
+        let large_buckets = simulate(6, 256, 5000, &mut rng).unwrap();
+
+        assert_eq!(small_buckets.bucket_size, 2);
+        assert!(small_buckets.mean_stash() > large_buckets.mean_stash());
+
+        // Fewer items than a bucket, the tree being a single bucket.
+        let single_bucket = simulate(6, 4, 100, &mut rng).unwrap();
+        assert_eq!(single_bucket.max_stash(), 0);
+    }
+
+    #[test]
+    fn simulation_is_reproducible() {
+        let stats = simulate(3, 128, 1000, &mut CsRng::seed_from_u64(3));
+        let same_stats = simulate(3, 128, 1000, &mut CsRng::seed_from_u64(3));
+
+        assert_eq!(stats.unwrap(), same_stats.unwrap());
+    }
+
+    #[test]
+    fn simulation_invalid_parameters() {
+        let mut rng = CsRng::seed_from_u64(4);
+
+        let res_simulation = simulate(0, 128, 10, &mut rng);
+        assert_eq!(res_simulation.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let res_simulation = simulate(BUCKET_SIZE, 0, 10, &mut rng);
+        assert_eq!(res_simulation.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let mut client =
+            ClientOram::<PlaintextCipher>::with_seed(16, [4; 32]).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let res_simulation =
+            simulate_client(&mut client, &mut path_oram, 0, 10, &mut rng);
+        assert_eq!(res_simulation.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn simulation_client_without_encryption() {
+        let mut rng = CsRng::seed_from_u64(5);
+        let mut client =
+            ClientOram::<PlaintextCipher>::with_seed(64, [5; 32]).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+
+        let stats =
+            simulate_client(&mut client, &mut path_oram, 64, 2000, &mut rng)
+                .unwrap();
+
+        assert_eq!(stats.bucket_size, BUCKET_SIZE);
+        assert_eq!(stats.nb_items, 64);
+        assert_eq!(stats.nb_accesses, 2000);
+        assert_eq!(stats.histogram.iter().sum::<u64>(), 2000);
+        assert!(stats.max_stash() <= client.params().stash_bound());
+
+        // Items are stored in the clear, with their header.
+        let block_id = 7;
+        let leaf = client.position_map[&block_id];
+        let path_items = path_oram.access(AccessType::Read, leaf, None);
+        let is_block = |item: &DataItem| {
+            BlockHeader::decode(item)
+                .unwrap()
+                .is_some_and(|header| header.id == block_id)
+        };
+        let in_path = path_items.unwrap().iter().flatten().any(is_block);
+        let in_stash = client.stash.iter().any(is_block);
+        assert!(in_path || in_stash);
+    }
+
+    #[test]
+    fn simulation_csv_and_json() {
+        let mut rng = CsRng::seed_from_u64(6);
+        let stats = simulate(BUCKET_SIZE, 64, 500, &mut rng).unwrap();
+
+        let csv = stats.to_csv(true);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), stats.histogram.len() + 1);
+        assert_eq!(
+            lines[0],
+            "bucket_size,nb_items,nb_accesses,stash_size,count,\
+             overflow_probability,theoretical_bound"
+        );
+        assert!(lines[1].starts_with(&format!(
+            "{},64,500,0,{},",
+            BUCKET_SIZE, stats.histogram[0]
+        )));
+        assert!(lines.iter().all(|line| line.split(',').count() == 7));
+        assert_eq!(stats.to_csv(false).lines().count(), stats.histogram.len());
+
+        let json = stats.to_json();
+        assert!(json.starts_with(&format!(
+            "{{\"bucket_size\":{},\"nb_items\":64,\"nb_accesses\":500,\
+             \"max_stash\":{},",
+            BUCKET_SIZE,
+            stats.max_stash()
+        )));
+        assert!(json.ends_with("]}"));
+    }
+
+    #[test]
+    fn trace_path_accesses() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let height = path_oram.tree().height() as usize;
+
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        for i in 0..10u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i,
+                    Some(vec![i as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        // Each access reads then writes back the same whole path.
+        let events = trace.events();
+        assert_eq!(events.len(), 20);
+        for access in events.chunks(2) {
+            assert_eq!(access[0].op, TraceOp::ReadPath);
+            assert_eq!(access[1].op, TraceOp::WritePath);
+            assert_eq!(access[0].path, access[1].path);
+            assert_eq!(access[0].buckets, access[1].buckets);
+            assert_eq!(access[0].nb_items, height * BUCKET_SIZE);
+            assert_eq!(access[1].nb_items, height * BUCKET_SIZE);
+            assert_eq!(access[0].nb_bytes, access[1].nb_bytes);
+
+            // Buckets go from the root down to the leaf of the path.
+            let leaf = access[0].path.unwrap();
+            assert_eq!(access[0].buckets.len(), height);
+            assert_eq!(access[0].buckets[0], 1);
+            assert_eq!(
+                access[0].buckets[height - 1],
+                (1 << (height - 1)) + leaf
+            );
+            for level in 1..height {
+                assert_eq!(
+                    access[0].buckets[level] / 2,
+                    access[0].buckets[level - 1]
+                );
+            }
+        }
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        // Invalid requests are not recorded.
+        assert!(path_oram
+            .access(AccessType::Read, 1 << height, None)
+            .is_err());
+        assert!(path_oram.access(AccessType::Write, 0, None).is_err());
+        assert_eq!(trace.len(), 20);
+
+        assert_eq!(trace.take().len(), 20);
+        assert!(trace.is_empty());
+        assert!(path_oram.take_trace_sink().is_some());
+        client.dummy_access(&mut path_oram).unwrap();
+        assert!(trace.is_empty());
+        assert!(path_oram.take_trace_sink().is_none());
+    }
+
+    #[test]
+    fn trace_stash_and_resizing() {
+        let mut client = ClientOram::new(32).unwrap();
+        client.outsource_stash(20);
+        let mut path_oram = client.setup_oram(16).unwrap();
+
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        client
+            .access(&mut path_oram, AccessType::Write, 0, Some(vec![0; 16]))
+            .unwrap();
+        let ops: Vec<TraceOp> =
+            trace.take().iter().map(|event| event.op).collect();
+        assert_eq!(
+            ops,
+            [
+                TraceOp::ReadPath,
+                TraceOp::ReadStash,
+                TraceOp::WriteStash,
+                TraceOp::WritePath
+            ]
+        );
+
+        let mut client = ClientOram::new(32).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let height = path_oram.tree().height() as usize;
+        path_oram.set_trace_sink(trace.clone());
+
+        client.grow(&mut path_oram).unwrap();
+        client.shrink(&mut path_oram).unwrap();
+        let events = trace.take();
+        let grow = events.iter().find(|e| e.op == TraceOp::Grow).unwrap();
+        assert_eq!(grow.nb_items, (1 << height) * BUCKET_SIZE);
+        assert!(grow.path.is_none() && grow.buckets.is_empty());
+        let shrink = events.iter().find(|e| e.op == TraceOp::Shrink).unwrap();
+        assert_eq!(shrink.nb_items, (1 << height) * BUCKET_SIZE);
+    }
+
+    #[test]
+    fn trace_csv_dump() {
+        let mut client = ClientOram::new(16).unwrap();
+        let mut path_oram = client.setup_oram(8).unwrap();
+
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+        client.dummy_access(&mut path_oram).unwrap();
+
+        let mut dump = Vec::new();
+        trace.dump_csv(&mut dump).unwrap();
+        let dump = String::from_utf8(dump).unwrap();
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], TraceEvent::CSV_HEADER);
+
+        let fields: Vec<&str> = lines[1].split(',').collect();
+        let event = &trace.events()[0];
+        assert_eq!(fields.len(), 6);
+        assert_eq!(fields[1], "read_path");
+        assert_eq!(fields[2], event.path.unwrap().to_string());
+        assert_eq!(fields[3].split(' ').count(), event.buckets.len());
+        assert_eq!(fields[4], event.nb_items.to_string());
+        assert!(lines[2].contains(",write_path,"));
+
+        // Streamed to a writer, the trace is the same.
+        let mut streamed = CsvTrace::new(Vec::new()).unwrap();
+        streamed.record(event.clone());
+        let streamed = String::from_utf8(streamed.finish().unwrap()).unwrap();
+        assert_eq!(streamed, format!("{}\n{}\n", lines[0], lines[1]));
+    }
+
+    fn _verified_oram(outsourced_stash: bool) -> (ClientOram, Oram) {
+        let mut client = ClientOram::new(64).unwrap();
+        if outsourced_stash {
+            client.outsource_stash(64);
+        }
+        let mut path_oram = client.setup_oram(16).unwrap();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+        for i in 0..200u64 {
+            let block_id = i % 40;
+            if i % 7 == 0 {
+                client.remove(&mut path_oram, block_id).unwrap();
+            } else {
+                client
+                    .access(
+                        &mut path_oram,
+                        AccessType::Write,
+                        block_id,
+                        Some(vec![i as u8; 16]),
+                    )
+                    .unwrap();
+            }
+        }
+
+        (client, path_oram)
+    }
+
+    #[test]
+    fn verify_invariants() {
+        for outsourced_stash in [false, true] {
+            let (mut client, mut path_oram) = _verified_oram(outsourced_stash);
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            client.grow(&mut path_oram).unwrap();
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            // Blocks added to the stash are verified before being evicted.
+            let leaf = client.random_leaf();
+            client.add(100, leaf, vec![1; 16]).unwrap();
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+            client.dummy_access(&mut path_oram).unwrap();
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+        }
+
+        // Elements of oblivious data structures are not in the position map.
+        let mut client = ClientOram::new(16).unwrap();
+        let mut path_oram = client.setup_oram(32).unwrap();
+        client.outsource_stash(16);
+        let mut stack = ObliviousStack::<_Bytes>::new(client, 16);
+        for i in 0..10 {
+            stack.push(&mut path_oram, &_Bytes(vec![i; 8])).unwrap();
+        }
+        stack.pop(&mut path_oram).unwrap();
+        assert!(stack.client().verify_invariants(&mut path_oram).is_ok());
+    }
+
+    #[test]
+    fn verify_invariants_wrong_leaf() {
+        let (mut client, mut path_oram) = _verified_oram(false);
+
+        let block_id = *client.position_map.keys().next().unwrap();
+        let nb_leaves = client.params().nb_leaves();
+        client.position_map.insert(block_id, nb_leaves);
+
+        let res_verify = client.verify_invariants(&mut path_oram);
+        assert_eq!(res_verify.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn verify_invariants_misplaced_block() {
+        let (client, mut path_oram) = _verified_oram(false);
+
+        // Buckets of path 0, but the root, are moved to the last path.
+        let height = path_oram.tree().height() as usize;
+        let nb_leaves = client.params().nb_leaves();
+        let path_items = path_oram
+            .access(AccessType::Read, 0, None)
+            .unwrap()
+            .unwrap();
+        let last_items = path_oram
+            .access(AccessType::Read, nb_leaves - 1, None)
+            .unwrap()
+            .unwrap();
+        let mut buckets: Vec<[DataItem; BUCKET_SIZE]> = (0..height)
+            .map(|level| {
+                let items = match level {
+                    0 => &last_items,
+                    _ => &path_items,
+                };
+                let bucket = &items[level * BUCKET_SIZE..][..BUCKET_SIZE];
+                bucket.to_vec().try_into().unwrap()
+            })
+            .collect();
+        path_oram
+            .access(AccessType::Write, nb_leaves - 1, Some(&mut buckets))
+            .unwrap();
+
+        let res_verify = client.verify_invariants(&mut path_oram);
+        assert_eq!(res_verify.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn verify_invariants_duplicate_block() {
+        let (mut client, mut path_oram) = _verified_oram(false);
+
+        // Every block of the tree is copied to the stash.
+        for leaf in 0..client.params().nb_leaves() {
+            let mut path_items = path_oram
+                .access(AccessType::Read, leaf, None)
+                .unwrap()
+                .unwrap();
+            client.stash.append(&mut path_items);
+        }
+
+        let res_verify = client.verify_invariants(&mut path_oram);
+        assert_eq!(res_verify.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn verify_invariants_missing_block() {
+        let (mut client, mut path_oram) = _verified_oram(true);
+        client.position_map.insert(1000, 0);
+
+        let res_verify = client.verify_invariants(&mut path_oram);
+        assert_eq!(res_verify.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn verify_invariants_other_oram() {
+        let (client, _) = _verified_oram(false);
+
+        // The tree is encrypted under another key.
+        let (_, mut other_oram) = _verified_oram(false);
+        let res_verify = client.verify_invariants(&mut other_oram);
+        assert_eq!(res_verify.unwrap_err().kind(), ErrorKind::Interrupted);
+
+        let mut other_client = ClientOram::new(256).unwrap();
+        let mut other_oram = other_client.setup_oram(16).unwrap();
+        let res_verify = client.verify_invariants(&mut other_oram);
+        assert_eq!(res_verify.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn access_batch() {
+        for outsourced_stash in [false, true] {
+            let mut client = ClientOram::new(64).unwrap();
+            if outsourced_stash {
+                client.outsource_stash(64);
+            }
+            let mut path_oram = client.setup_oram(16).unwrap();
+
+            let writes = (0..20u64)
+                .map(|i| (AccessType::Write, i, Some(vec![i as u8; 16])))
+                .collect();
+            let res_batch = client.access_batch(&mut path_oram, writes);
+            assert_eq!(res_batch.unwrap(), vec![None; 20]);
+            assert_eq!(client.nb_accesses(), 20);
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            // Reads, writes and missing blocks, answered in order.
+            let requests = vec![
+                (AccessType::Read, 3, None),
+                (AccessType::Write, 5, Some(vec![50; 8])),
+                (AccessType::Read, 100, None),
+                (AccessType::Write, 101, Some(vec![101; 16])),
+            ];
+            let res_batch = client.access_batch(&mut path_oram, requests);
+            assert_eq!(
+                res_batch.unwrap(),
+                vec![Some(vec![3; 16]), Some(vec![5; 16]), None, None]
+            );
+            assert!(!client.position_map.contains_key(&100));
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            // Single accesses see the blocks written by batches.
+            for (block_id, expected) in
+                [(5, vec![50; 8]), (101, vec![101; 16]), (19, vec![19; 16])]
+            {
+                let res_read = client.access(
+                    &mut path_oram,
+                    AccessType::Read,
+                    block_id,
+                    None,
+                );
+                assert_eq!(res_read.unwrap(), Some(expected));
+            }
+
+            assert!(client
+                .access_batch(&mut path_oram, Vec::new())
+                .unwrap()
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn access_batch_shared_buckets() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let height = path_oram.tree().height() as usize;
+        for i in 0..8u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    i,
+                    Some(vec![i as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        let requests = (0..8u64).map(|i| (AccessType::Read, i, None)).collect();
+        client.access_batch(&mut path_oram, requests).unwrap();
+
+        // A single round trip, each bucket of the union of paths once.
+        let events = trace.take();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].op, TraceOp::ReadBuckets);
+        assert_eq!(events[1].op, TraceOp::WriteBuckets);
+
+        let buckets = &events[0].buckets;
+        assert_eq!(buckets[0], 1);
+        assert!(buckets.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(buckets.len() < 8 * height);
+        assert_eq!(events[0].nb_items, buckets.len() * BUCKET_SIZE);
+
+        let mut written = events[1].buckets.clone();
+        written.sort();
+        assert_eq!(&written, buckets);
+
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+    }
+
+    #[test]
+    fn access_batch_invalid_requests() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+
+        let requests = vec![
+            (AccessType::Write, 1, Some(vec![1; 16])),
+            (AccessType::Read, 1, None),
+        ];
+        let res_batch = client.access_batch(&mut path_oram, requests);
+        assert_eq!(res_batch.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let requests =
+            vec![(AccessType::Read, 1, None), (AccessType::Write, 2, None)];
+        let res_batch = client.access_batch(&mut path_oram, requests);
+        assert_eq!(res_batch.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        let requests = vec![(AccessType::Write, 2, Some(vec![2; 17]))];
+        let res_batch = client.access_batch(&mut path_oram, requests);
+        assert_eq!(res_batch.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        // The ORAM is left untouched.
+        assert!(trace.is_empty());
+        assert_eq!(client.nb_accesses(), 0);
+        assert!(client.position_map.is_empty());
+    }
+
+    #[test]
+    fn oram_read_paths_write_buckets() {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let height = path_oram.tree().height() as usize;
+        let nb_leaves = client.params().nb_leaves();
+
+        let res_read = path_oram.read_paths(&[0, nb_leaves]);
+        assert_eq!(res_read.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        // Paths 0 and 1 only differ by their leaf bucket.
+        let buckets = path_oram.read_paths(&[0, 1, 1]).unwrap();
+        assert_eq!(buckets.len(), height + 1);
+        let path_items = path_oram.access(AccessType::Read, 1, None).unwrap();
+        let leaf_bucket = &buckets.last().unwrap().1;
+        assert_eq!(buckets.last().unwrap().0, nb_leaves + 1);
+        assert_eq!(
+            &path_items.unwrap()[(height - 1) * BUCKET_SIZE..],
+            leaf_bucket.as_slice()
+        );
+
+        let res_write =
+            path_oram.write_buckets(vec![(2 * nb_leaves, leaf_bucket.clone())]);
+        assert_eq!(res_write.unwrap_err().kind(), ErrorKind::InvalidInput);
+        let res_write = path_oram.write_buckets(vec![(0, leaf_bucket.clone())]);
+        assert_eq!(res_write.unwrap_err().kind(), ErrorKind::InvalidInput);
+
+        // The root is overwritten by the leaf bucket of path 1.
+        path_oram
+            .write_buckets(vec![(1, leaf_bucket.clone())])
+            .unwrap();
+        let root = &path_oram.read_paths(&[0]).unwrap()[0];
+        assert_eq!(root, &(1, leaf_bucket.clone()));
+    }
+
+    /// Client with blocks 0 to 39 written, caching the top `nb_levels` levels.
+    fn _cached_oram(
+        nb_levels: u16,
+        outsourced_stash: bool,
+    ) -> (ClientOram, Oram) {
+        let mut client = ClientOram::new(64).unwrap();
+        if outsourced_stash {
+            client.outsource_stash(64);
+        }
+        let mut path_oram = client.setup_oram(16).unwrap();
+
+        for block_id in 0..40u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![block_id as u8; 16]),
+                )
+                .unwrap();
+        }
+
+        client.cache_tree_top(&mut path_oram, nb_levels).unwrap();
+        assert_eq!(client.tree_top_levels(), nb_levels);
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+        (client, path_oram)
+    }
+
+    fn _assert_blocks(client: &mut ClientOram, path_oram: &mut Oram) {
+        for block_id in 0..40u64 {
+            let value = client
+                .access(path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8; 16]));
+        }
+    }
+
+    #[test]
+    fn tree_top_cache() {
+        for outsourced_stash in [false, true] {
+            let (mut client, mut path_oram) = _cached_oram(2, outsourced_stash);
+            let height = path_oram.tree().height() as usize;
+
+            // The cached buckets are replaced by dummies on the server.
+            let mut items: Vec<DataItem> = path_oram
+                .read_buckets(&[1, 2, 3])
+                .unwrap()
+                .into_iter()
+                .flatten()
+                .collect();
+            client.decrypt_items(&mut items).unwrap();
+            for item in items {
+                assert_eq!(Block::decode(&item).unwrap(), None);
+            }
+
+            let trace = MemoryTrace::new();
+            path_oram.set_trace_sink(trace.clone());
+            _assert_blocks(&mut client, &mut path_oram);
+            client.dummy_access(&mut path_oram).unwrap();
+
+            // Only the buckets below the cached levels are transferred.
+            for event in trace.take() {
+                if event.op == TraceOp::ReadStash
+                    || event.op == TraceOp::WriteStash
+                {
+                    continue;
+                }
+
+                assert!(
+                    event.op == TraceOp::ReadBuckets
+                        || event.op == TraceOp::WriteBuckets
+                );
+                assert_eq!(event.buckets.len(), height - 2);
+                assert!(event.buckets.iter().all(|bucket| *bucket >= 4));
+                assert_eq!(event.nb_items, (height - 2) * BUCKET_SIZE);
+            }
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            // Batches evict to the cached buckets as well.
+            let requests = (0..8u64)
+                .map(|i| (AccessType::Write, i, Some(vec![i as u8; 16])))
+                .collect();
+            client.access_batch(&mut path_oram, requests).unwrap();
+            assert!(trace
+                .take()
+                .iter()
+                .flat_map(|event| &event.buckets)
+                .all(|bucket| *bucket >= 4));
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            // The cache follows the tree when it grows.
+            client.grow(&mut path_oram).unwrap();
+            trace.take();
+            _assert_blocks(&mut client, &mut path_oram);
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            // Changing the number of cached levels flushes the cache first.
+            client.cache_tree_top(&mut path_oram, 3).unwrap();
+            assert_eq!(client.tree_top_levels(), 3);
+            _assert_blocks(&mut client, &mut path_oram);
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+            client.cache_tree_top(&mut path_oram, 0).unwrap();
+            assert_eq!(client.tree_top_levels(), 0);
+            assert!(client.verify_invariants(&mut path_oram).is_ok());
+            trace.take();
+            _assert_blocks(&mut client, &mut path_oram);
+            assert!(trace
+                .take()
+                .iter()
+                .all(|event| event.op != TraceOp::ReadBuckets));
+        }
+    }
+
+    #[test]
+    fn tree_top_cache_invalid_levels() {
+        let (mut client, mut path_oram) = _cached_oram(0, false);
+        let height = path_oram.tree().height();
+
+        let res_cache = client.cache_tree_top(&mut path_oram, height);
+        assert_eq!(res_cache.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(client.tree_top_levels(), 0);
+
+        // The cached levels must remain above the leaves.
+        client.cache_tree_top(&mut path_oram, height - 1).unwrap();
+        let res_shrink = client.shrink(&mut path_oram);
+        assert_eq!(res_shrink.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(path_oram.tree().height(), height);
+
+        client.cache_tree_top(&mut path_oram, height - 2).unwrap();
+        for block_id in 32..40u64 {
+            client.remove(&mut path_oram, block_id).unwrap();
+        }
+        client.shrink(&mut path_oram).unwrap();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+
+        let mut other_client = ClientOram::new(64).unwrap();
+        let mut other_oram = other_client.setup_oram(16).unwrap();
+        other_oram.shrink().unwrap();
+        let res_cache = other_client.cache_tree_top(&mut other_oram, 1);
+        assert_eq!(res_cache.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn tree_top_cache_checkpoint() {
+        let mut csprng = CsRng::from_entropy();
+        let mut key = vec![0; 32];
+        csprng.fill_bytes(&mut key);
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(64, &key).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        for block_id in 0..40u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![block_id as u8; 16]),
+                )
+                .unwrap();
+        }
+        client.cache_tree_top(&mut path_oram, 2).unwrap();
+
+        let checkpoint_path = _checkpoint_path("tree_top");
+        client.checkpoint(&checkpoint_path).unwrap();
+        drop(client);
+
+        let res_restore =
+            ClientOram::<Aes256Gcm>::restore(&checkpoint_path, &key);
+        fs::remove_file(&checkpoint_path).unwrap();
+        let mut client = res_restore.unwrap();
+
+        // Cached blocks were saved with the stash.
+        assert_eq!(client.tree_top_levels(), 0);
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        _assert_blocks(&mut client, &mut path_oram);
+    }
+
+    #[test]
+    fn general_behavior() {
+        /*
+         * Example of use for 183 items stored and a ciphertext size of 16 bytes.
+         * This means that there will be ceil(183/4) = 46 nodes to hold those
+         * items which completes to 63 nodes for the tree. There will then be 32
+         * leaves.
+         */
+        let nb_items: usize = 183;
+        let ct_size: usize = 16;
+
+        /*
+         * Client.
+         */
+        let mut client = ClientOram::new(nb_items).unwrap();
+
+        let res_dummies = client.generate_dummy_items(nb_items, ct_size);
+        assert!(res_dummies.is_ok());
+        let mut dummies = res_dummies.unwrap();
+
+        /*
+         * Server.
+         */
+        let res_oram = Oram::new(&mut dummies, nb_items);
+        assert!(res_oram.is_ok());
+        let mut path_oram = res_oram.unwrap();
+
+        // Let's read path number 22.
+        let path = 22;
+
+        let res_access = path_oram.access(AccessType::Read, path, Option::None);
+        assert!(res_access.is_ok());
+        let opt_access = res_access.unwrap();
+        assert!(opt_access.is_some());
+
+        // This is the data we read from the ORAM, only dummies at this point.
+        let mut read_data = opt_access.unwrap();
+
+        /*
+         * We received server response. Moving client side...
+         */
+
+        // Decrypt them nonetheless.
+        let decrypt_res = client.decrypt_items(&mut read_data);
+        assert!(decrypt_res.is_ok());
+
+        // Decrypt client stash.
+        let stsh_dec_res = client.decrypt_stash();
+        assert!(stsh_dec_res.is_ok());
+
+        // Now read_data contains plaintext values, decrypted dummies here.
+        assert!(!read_data[9].data().is_empty());
+        assert!(Block::decode(&read_data[9]).unwrap().is_none());
+
+        // Let's add some real data to our position map now.
+        let mut csprng = CsRng::from_entropy();
+        let mut new_values = Vec::with_capacity(
+            path_oram.tree().height() as usize * BUCKET_SIZE + 2,
+        );
+
+        let nb_new_values =
+            path_oram.tree().height() as u64 * BUCKET_SIZE as u64 + 2;
+        for block_id in 0..nb_new_values {
+            let mut rand_value = vec![0; ct_size];
+            csprng.fill_bytes(&mut rand_value);
+
+            client.position_map.insert(block_id, 0);
+
+            let res_chg = client.change_element_position(block_id);
+            assert!(res_chg.is_ok());
+
+            new_values.push(
+                Block::new(block_id, 0, rand_value).encode(ct_size).unwrap(),
+            );
+        }
+        // Push a witness value for later.
+        let witness: Vec<u8> = [
+            10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 10,
+        ]
+        .to_vec();
+        let witness_id = nb_new_values;
+        client.position_map.insert(witness_id, path);
+
+        new_values.push(
+            Block::new(witness_id, path, witness.clone())
+                .encode(ct_size)
+                .unwrap(),
+        );
+
+        /* We ordered elements and put the ones that could not be written in the
+         * stash. Since we want to write 26 elements and a path can only contain
+         * tree.height * BUCKET_SIZE = 6 * 4 (here) = 24 elements, the stash has
+         * to be not empty. However, its size can vary since we assigned paths
+         * at random.
+         */
+        let mut ordered_elements = client.order_elements_for_writing(
+            &mut [new_values.as_slice(), read_data.as_slice()].concat(),
+            path,
+            path_oram.tree().height() as usize,
+        );
+
+        assert!(!client.stash.is_empty());
+
+        assert_eq!(ordered_elements.len(), path_oram.tree().height() as usize);
+        assert_eq!(ordered_elements[0].len(), BUCKET_SIZE);
+
+        // Encrypt read items to write them back to the ORAM.
+        let enc_res = client.encrypt_items(&mut ordered_elements);
+        assert!(enc_res.is_ok());
+
+        // Encrypt back the stash.
+        let stsh_enc_res = client.encrypt_stash();
+        assert!(stsh_enc_res.is_ok());
+
+        /*
+         * Sending data to the server for writing...
+         */
+
+        // Write ordered elements to the same path.
+        let res_write = path_oram.access(
+            AccessType::Write,
+            path,
+            Some(&mut ordered_elements),
+        );
+
+        assert!(res_write.is_ok());
+        let opt_write = res_write.unwrap();
+        assert!(opt_write.is_none());
+
+        /*
+         * Let's read the same path again to check if values read are the same.
+         */
+        let res_read = path_oram.access(AccessType::Read, path, Option::None);
+        assert!(res_read.is_ok());
+        let opt_read = res_read.unwrap();
+        assert!(opt_read.is_some());
+
+        let mut read_values = opt_read.unwrap();
+
+        let dec_res = client.decrypt_items(&mut read_values);
+        assert!(dec_res.is_ok());
+
+        // Check that the path we read from contains the witness we inserted.
+        assert!(read_values.iter().any(|data_item| {
+            Block::decode(data_item).unwrap()
+                == Some(Block::new(witness_id, path, witness.clone()))
+        }));
     }
 }
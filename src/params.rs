@@ -0,0 +1,183 @@
+use crate::oram::BUCKET_SIZE;
+use std::io::{Error, ErrorKind};
+
+/// Default probability that the stash exceeds `OramParams::stash_bound`,
+/// 2^-64.
+pub const DEFAULT_FAILURE_PROBABILITY: f64 = 1.0 / (1u128 << 64) as f64;
+
+/// Factor applied to the stash bound, which is not proven for buckets of
+/// `BUCKET_SIZE` items.
+const STASH_BOUND_MARGIN: usize = 2;
+
+/// Parameters of a Path ORAM and the quantities derived from them, shared by
+/// the client and the server tree.
+///
+/// The tree has one leaf per `BUCKET_SIZE` items, rounded down to a power of
+/// two and to at least one leaf, so that any positive number of items is
+/// supported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OramParams {
+    nb_items: usize,
+    block_size: usize,
+    failure_probability: f64,
+}
+
+impl OramParams {
+    /// Parameters for `nb_items` blocks of at most `block_size` bytes of
+    /// payload, with a stash bound exceeded with probability
+    /// `DEFAULT_FAILURE_PROBABILITY`.
+    pub fn new(
+        nb_items: usize,
+        block_size: usize,
+    ) -> Result<OramParams, Error> {
+        OramParams::with_failure_probability(
+            nb_items,
+            block_size,
+            DEFAULT_FAILURE_PROBABILITY,
+        )
+    }
+
+    /// Parameters whose stash bound is exceeded with probability
+    /// `failure_probability`.
+    pub fn with_failure_probability(
+        nb_items: usize,
+        block_size: usize,
+        failure_probability: f64,
+    ) -> Result<OramParams, Error> {
+        if nb_items == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Number of items shall not be null".to_string(),
+            ));
+        }
+
+        // The number of slots of the tree is below twice the number of items.
+        if nb_items > usize::MAX / 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Number of items too large. Got {}, expected at most {}",
+                    nb_items,
+                    usize::MAX / 2
+                ),
+            ));
+        }
+
+        // Payload lengths are encoded on 4 bytes in block headers.
+        if block_size > u32::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Block size too large. Got {} bytes, expected at most {}",
+                    block_size,
+                    u32::MAX
+                ),
+            ));
+        }
+
+        if !(failure_probability > 0.0 && failure_probability < 1.0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid failure probability. Got {}, expected in range \
+                     ]0, 1[",
+                    failure_probability
+                ),
+            ));
+        }
+
+        Ok(OramParams {
+            nb_items,
+            block_size,
+            failure_probability,
+        })
+    }
+
+    /// Parameters of a client built from a bare number of items, the block
+    /// size being given when the ORAM is set up.
+    pub(crate) fn for_client(nb_items: usize) -> Result<OramParams, Error> {
+        OramParams::new(nb_items, 0)
+    }
+
+    /// Same parameters for `nb_items` blocks.
+    pub fn for_nb_items(&self, nb_items: usize) -> Result<OramParams, Error> {
+        OramParams::with_failure_probability(
+            nb_items,
+            self.block_size,
+            self.failure_probability,
+        )
+    }
+
+    /// Same parameters for blocks of `block_size` bytes.
+    pub fn for_block_size(
+        &self,
+        block_size: usize,
+    ) -> Result<OramParams, Error> {
+        OramParams::with_failure_probability(
+            self.nb_items,
+            block_size,
+            self.failure_probability,
+        )
+    }
+
+    pub fn nb_items(&self) -> usize {
+        self.nb_items
+    }
+
+    pub fn bucket_size(&self) -> usize {
+        BUCKET_SIZE
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn failure_probability(&self) -> f64 {
+        self.failure_probability
+    }
+
+    /// Number of levels of the tree, the root being a level.
+    pub fn height(&self) -> u16 {
+        tree_height(self.nb_items, BUCKET_SIZE)
+    }
+
+    /// Number of leaves, hence of paths, of the tree.
+    pub fn nb_leaves(&self) -> usize {
+        1 << (self.height() - 1)
+    }
+
+    /// Number of nodes of the complete tree.
+    pub fn nb_buckets(&self) -> usize {
+        2 * self.nb_leaves() - 1
+    }
+
+    /// Number of item slots of the tree, real blocks and dummies.
+    pub fn nb_slots(&self) -> usize {
+        self.nb_buckets() * BUCKET_SIZE
+    }
+
+    /// Number of blocks the stash is expected to exceed with probability at
+    /// most `failure_probability`, after any sequence of accesses.
+    ///
+    /// The bound is proven for buckets of at least 5 items only: for buckets
+    /// of `BUCKET_SIZE` items, it is an empirical heuristic, doubled as a
+    /// safety margin.
+    pub fn stash_bound(&self) -> usize {
+        /*
+         * The stash exceeds R blocks with probability at most 14 * 0.6002^R
+         * for buckets of 5 items or more, as shown in
+         * `https://eprint.iacr.org/2013/280`. Simulations show buckets of 4
+         * items to behave alike, which the margin leaves room for.
+         */
+        let bound = (self.failure_probability / 14.0).ln() / 0.6002_f64.ln();
+
+        STASH_BOUND_MARGIN * bound.ceil().max(0.0) as usize
+    }
+}
+
+/// Number of levels of a tree holding `nb_items` items in buckets of
+/// `bucket_size` items, with one leaf per `bucket_size` items rounded down to a
+/// power of two and to at least one leaf.
+pub(crate) fn tree_height(nb_items: usize, bucket_size: usize) -> u16 {
+    (nb_items / bucket_size).max(1).ilog2() as u16 + 1
+}
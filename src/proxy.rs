@@ -0,0 +1,326 @@
+use crate::concurrent::{ConcurrentOram, Request};
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    io::{BufRead, BufReader, Error, ErrorKind, Write},
+    net::Shutdown,
+    os::unix::{
+        fs::PermissionsExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+/*
+ * Applications send one request per line and get one response per line, in
+ * the same order. Payloads are hex encoded, the empty payload being `-`.
+ *
+ *   GET <block id>             ->  VALUE <payload> | NONE
+ *   PUT <block id> <payload>   ->  VALUE <previous payload> | NONE
+ *   DEL <block id>             ->  VALUE <previous payload> | NONE
+ *
+ * Invalid requests and failed accesses get `ERR <message>` instead, the
+ * connection staying open.
+ */
+
+/// Key-value front-end of an ORAM for trusted local applications, in the
+/// spirit of TaoStore. The proxy owns the client, its key and its state, and
+/// serves the applications connecting to a Unix socket, each connection
+/// being handled by its own thread. Requests of all the connections are
+/// served by a `ConcurrentOram`, which hides from the server when and how
+/// much the applications access it.
+pub struct Proxy {
+    socket_path: PathBuf,
+    stopped: Arc<AtomicBool>,
+    /// Streams of the open connections, to close them when stopping.
+    connections: Arc<Mutex<HashMap<u64, UnixStream>>>,
+    acceptor: JoinHandle<Vec<JoinHandle<()>>>,
+}
+
+impl Proxy {
+    /// Listens on a new Unix socket at `socket_path` and serves the
+    /// requests of the applications connecting to it with `front_end`. Only
+    /// the user running the proxy may connect, the socket being made
+    /// readable and writable by its owner only.
+    ///
+    /// Fails with `ErrorKind::AddrInUse` if `socket_path` already exists.
+    pub fn start(
+        socket_path: impl AsRef<Path>,
+        front_end: ConcurrentOram,
+    ) -> Result<Proxy, Error> {
+        let socket_path = socket_path.as_ref().to_path_buf();
+        let listener = UnixListener::bind(&socket_path)?;
+
+        /*
+         * The socket is created with the umask of the process, which usually
+         * lets other users connect. A connection made before the permissions
+         * are restricted would still be served: sockets in a directory only
+         * the user can access are not exposed even then.
+         */
+        let res_permissions = fs::set_permissions(
+            &socket_path,
+            fs::Permissions::from_mode(0o600),
+        );
+        if let Err(e) = res_permissions {
+            let _ = fs::remove_file(&socket_path);
+            return Err(e);
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let connections = Arc::new(Mutex::new(HashMap::new()));
+        let acceptor = {
+            let stopped = Arc::clone(&stopped);
+            let connections = Arc::clone(&connections);
+            thread::spawn(move || {
+                accept(listener, front_end, &stopped, &connections)
+            })
+        };
+
+        Ok(Proxy {
+            socket_path,
+            stopped,
+            connections,
+            acceptor,
+        })
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    /// Stops accepting connections, closes the open ones once their current
+    /// request is served and removes the socket. The front-end is then
+    /// dropped, so that its worker stops once no other handle is left.
+    pub fn stop(self) -> Result<(), Error> {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wakes the acceptor up, which sees the proxy is stopped.
+        drop(UnixStream::connect(&self.socket_path));
+
+        for stream in self.connections.lock().unwrap().values() {
+            // The application may have closed it already.
+            let _ = stream.shutdown(Shutdown::Read);
+        }
+
+        let handlers = self.acceptor.join().map_err(|_| panicked())?;
+        for handler in handlers {
+            handler.join().map_err(|_| panicked())?;
+        }
+
+        fs::remove_file(&self.socket_path)
+    }
+}
+
+/// Accepts connections until the proxy is stopped. Returns the threads
+/// handling the connections still open.
+fn accept(
+    listener: UnixListener,
+    front_end: ConcurrentOram,
+    stopped: &AtomicBool,
+    connections: &Arc<Mutex<HashMap<u64, UnixStream>>>,
+) -> Vec<JoinHandle<()>> {
+    let mut handlers: Vec<JoinHandle<()>> = Vec::new();
+    for (id, stream) in (0..).zip(listener.incoming()) {
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+
+        // Failed connections only concern the application which tried.
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Ok(clone) = stream.try_clone() else {
+            continue;
+        };
+
+        {
+            let mut connections = connections.lock().unwrap();
+            // The proxy may have been stopped while accepting the connection.
+            if stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            connections.insert(id, clone);
+        }
+
+        handlers.retain(|handler| !handler.is_finished());
+        let front_end = front_end.clone();
+        let connections = Arc::clone(connections);
+        handlers.push(thread::spawn(move || {
+            serve(stream, &front_end);
+            connections.lock().unwrap().remove(&id);
+        }));
+    }
+
+    handlers
+}
+
+/// Serves the requests of a connection until it is closed.
+fn serve(stream: UnixStream, front_end: &ConcurrentOram) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+
+        let response = match parse_request(&line) {
+            Ok(request) => match front_end.submit(request) {
+                Ok(pending) => format_response(pending.wait()),
+                Err(e) => format_response(Err(e)),
+            },
+            Err(e) => format_response(Err(e)),
+        };
+
+        if writeln!(writer, "{}", response).is_err() {
+            return;
+        }
+    }
+}
+
+fn parse_request(line: &str) -> Result<Request, Error> {
+    let invalid_request = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Invalid request. Got {:?}, expected GET <id>, PUT <id> \
+                 <hex payload> or DEL <id>",
+                line
+            ),
+        )
+    };
+
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let block_id = || -> Result<u64, Error> {
+        words[1].parse().map_err(|_| invalid_request())
+    };
+
+    match words.as_slice() {
+        ["GET", _] => Ok(Request::Read(block_id()?)),
+        ["PUT", _, payload] => Ok(Request::Write(
+            block_id()?,
+            decode_hex(payload).ok_or_else(invalid_request)?,
+        )),
+        ["DEL", _] => Ok(Request::Remove(block_id()?)),
+        _ => Err(invalid_request()),
+    }
+}
+
+fn format_response(result: Result<Option<Vec<u8>>, Error>) -> String {
+    match result {
+        Ok(Some(payload)) => format!("VALUE {}", encode_hex(&payload)),
+        Ok(None) => "NONE".to_string(),
+        // Messages are kept on a single line.
+        Err(e) => format!("ERR {}", e.to_string().replace('\n', " ")),
+    }
+}
+
+/// Client of a `Proxy`, for trusted applications written in Rust.
+pub struct ProxyClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl ProxyClient {
+    pub fn connect(
+        socket_path: impl AsRef<Path>,
+    ) -> Result<ProxyClient, Error> {
+        let writer = UnixStream::connect(socket_path)?;
+        let reader = BufReader::new(writer.try_clone()?);
+
+        Ok(ProxyClient { reader, writer })
+    }
+
+    pub fn get(&mut self, block_id: u64) -> Result<Option<Vec<u8>>, Error> {
+        self.send(&format!("GET {}", block_id))
+    }
+
+    /// Replaces the payload of the block `block_id` and returns the previous
+    /// one, if any.
+    pub fn put(
+        &mut self,
+        block_id: u64,
+        payload: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.send(&format!("PUT {} {}", block_id, encode_hex(payload)))
+    }
+
+    pub fn remove(&mut self, block_id: u64) -> Result<Option<Vec<u8>>, Error> {
+        self.send(&format!("DEL {}", block_id))
+    }
+
+    fn send(&mut self, request: &str) -> Result<Option<Vec<u8>>, Error> {
+        writeln!(self.writer, "{}", request)?;
+
+        let mut response = String::new();
+        if self.reader.read_line(&mut response)? == 0 {
+            return Err(Error::new(
+                ErrorKind::ConnectionAborted,
+                "The proxy closed the connection".to_string(),
+            ));
+        }
+
+        // Only the line ending is stripped, payloads being significant.
+        let response = response
+            .strip_suffix('\n')
+            .map(|response| response.strip_suffix('\r').unwrap_or(response))
+            .unwrap_or(&response);
+        if response == "NONE" {
+            Ok(None)
+        } else if let Some(payload) = response.strip_prefix("VALUE ") {
+            decode_hex(payload).map(Some).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid payload from the proxy {:?}", payload),
+                )
+            })
+        } else if let Some(message) = response.strip_prefix("ERR ") {
+            Err(Error::other(message.to_string()))
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid response from the proxy {:?}", response),
+            ))
+        }
+    }
+}
+
+/// Hex encoding of a payload, `-` if it is empty so that it still makes a
+/// word of the line.
+fn encode_hex(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "-".to_string();
+    }
+
+    let mut hex = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        // Writing to a `String` cannot fail.
+        let _ = write!(hex, "{:02x}", byte);
+    }
+
+    hex
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex == "-" {
+        return Some(Vec::new());
+    }
+    if hex.is_empty() || !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn panicked() -> Error {
+    Error::other("A thread of the proxy panicked".to_string())
+}
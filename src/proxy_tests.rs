@@ -0,0 +1,174 @@
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, ErrorKind, Write},
+        os::unix::{fs::PermissionsExt, net::UnixStream},
+        path::PathBuf,
+        thread,
+        time::Duration,
+    };
+
+    use cosmian_crypto_core::{
+        reexport::rand_core::{RngCore, SeedableRng},
+        Aes256Gcm, CsRng,
+    };
+
+    use crate::{
+        client::ClientOram,
+        concurrent::{ConcurrentOram, WorkerHandle},
+        oram::AccessType,
+        proxy::{Proxy, ProxyClient},
+    };
+
+    fn _socket_path(name: &str) -> PathBuf {
+        let mut csprng = CsRng::from_entropy();
+        std::env::temp_dir().join(format!(
+            "path_oram_{}_{}.sock",
+            name,
+            csprng.next_u64()
+        ))
+    }
+
+    fn _front_end() -> (ConcurrentOram, WorkerHandle<Aes256Gcm>) {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        ConcurrentOram::spawn(client, path_oram, 4, Duration::from_millis(1))
+            .unwrap()
+    }
+
+    fn _start_proxy(name: &str) -> (Proxy, WorkerHandle<Aes256Gcm>) {
+        let (front_end, worker) = _front_end();
+
+        (Proxy::start(_socket_path(name), front_end).unwrap(), worker)
+    }
+
+    #[test]
+    fn proxy_key_value() {
+        let (proxy, worker) = _start_proxy("key_value");
+
+        let threads: Vec<_> = (0..4u64)
+            .map(|i| {
+                let socket_path = proxy.socket_path().to_path_buf();
+                thread::spawn(move || {
+                    let mut app = ProxyClient::connect(socket_path).unwrap();
+                    for block_id in 10 * i..10 * i + 10 {
+                        let payload = vec![block_id as u8; 16];
+                        assert_eq!(app.put(block_id, &payload).unwrap(), None);
+                    }
+                    for block_id in 10 * i..10 * i + 10 {
+                        assert_eq!(
+                            app.get(block_id).unwrap(),
+                            Some(vec![block_id as u8; 16])
+                        );
+                    }
+                    assert_eq!(
+                        app.remove(10 * i).unwrap(),
+                        Some(vec![10 * i as u8; 16])
+                    );
+                    assert_eq!(app.get(10 * i).unwrap(), None);
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // Blocks written through a connection are seen by the others.
+        let mut app = ProxyClient::connect(proxy.socket_path()).unwrap();
+        assert_eq!(app.get(13).unwrap(), Some(vec![13; 16]));
+
+        // Empty payloads round-trip.
+        assert_eq!(app.put(2, &[]).unwrap(), Some(vec![2; 16]));
+        assert_eq!(app.get(2).unwrap(), Some(Vec::new()));
+        assert_eq!(app.put(2, &[2; 16]).unwrap(), Some(Vec::new()));
+
+        let res_put = app.put(1, &[1; 17]);
+        assert_eq!(res_put.unwrap_err().kind(), ErrorKind::Other);
+        assert_eq!(app.get(1).unwrap(), Some(vec![1; 16]));
+
+        proxy.stop().unwrap();
+        let (mut client, mut path_oram, res_run) = worker.join().unwrap();
+        assert!(res_run.is_ok());
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        assert_eq!(client.position_map.len(), 36);
+        let value = client
+            .access(&mut path_oram, AccessType::Read, 25, None)
+            .unwrap();
+        assert_eq!(value, Some(vec![25; 16]));
+    }
+
+    #[test]
+    fn proxy_protocol() {
+        let (proxy, worker) = _start_proxy("protocol");
+
+        let stream = UnixStream::connect(proxy.socket_path()).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut send = |request: &str| {
+            writeln!(writer, "{}", request).unwrap();
+            let mut response = String::new();
+            reader.read_line(&mut response).unwrap();
+            response.trim_end().to_string()
+        };
+
+        assert_eq!(send("GET 7"), "NONE");
+        assert_eq!(send("PUT 7 00ff10"), "NONE");
+        assert_eq!(send("GET 7"), "VALUE 00ff10");
+        assert_eq!(send("PUT 7 abcd"), "VALUE 00ff10");
+        assert_eq!(send("DEL 7"), "VALUE abcd");
+        assert_eq!(send("GET 7"), "NONE");
+
+        // Empty payloads are written `-`.
+        assert_eq!(send("PUT 7 -"), "NONE");
+        assert_eq!(send("GET 7"), "VALUE -");
+        assert_eq!(send("DEL 7"), "VALUE -");
+
+        // Invalid requests are answered without closing the connection.
+        for request in [
+            "",
+            "GET",
+            "GET x",
+            "PUT 7",
+            "PUT 7 abc",
+            "PUT 7 zz",
+            "PUT 7 --",
+            "LIST",
+        ] {
+            assert!(send(request).starts_with("ERR Invalid request"));
+        }
+        assert!(send(&format!("PUT 7 {}", "00".repeat(17))).starts_with("ERR"));
+        assert_eq!(send("GET 7"), "NONE");
+
+        proxy.stop().unwrap();
+        assert!(worker.join().unwrap().2.is_ok());
+    }
+
+    #[test]
+    fn proxy_stop() {
+        let (proxy, worker) = _start_proxy("stop");
+        let socket_path = proxy.socket_path().to_path_buf();
+
+        // Other users cannot connect.
+        let mode = fs::metadata(&socket_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let (front_end, other_worker) = _front_end();
+        let res_start = Proxy::start(&socket_path, front_end);
+        assert_eq!(res_start.err().unwrap().kind(), ErrorKind::AddrInUse);
+        assert!(other_worker.join().unwrap().2.is_ok());
+
+        // Idle connections do not prevent the proxy from stopping.
+        let mut app = ProxyClient::connect(&socket_path).unwrap();
+        app.put(3, &[3; 16]).unwrap();
+        proxy.stop().unwrap();
+        assert!(!socket_path.exists());
+
+        assert!(app.get(3).is_err());
+        assert!(ProxyClient::connect(&socket_path).is_err());
+
+        let (client, _, res_run) = worker.join().unwrap();
+        assert!(res_run.is_ok());
+        assert_eq!(client.position_map.len(), 1);
+    }
+}
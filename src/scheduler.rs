@@ -0,0 +1,231 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::{AccessType, Oram},
+};
+use cosmian_crypto_core::Aes256Gcm;
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Logical request to a block, as issued by a caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Read(u64),
+    /// Replaces the payload of the block, creating it if needed.
+    Write(u64, Vec<u8>),
+    Remove(u64),
+}
+
+impl Request {
+    pub fn block_id(&self) -> u64 {
+        match self {
+            Request::Read(block_id)
+            | Request::Write(block_id, _)
+            | Request::Remove(block_id) => *block_id,
+        }
+    }
+}
+
+/// Identifier of a request submitted to a `FixedRateScheduler`, to retrieve
+/// its result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ticket(u64);
+
+/// Client-side layer issuing ORAM accesses at a constant rate, so that
+/// neither the timing nor the number of accesses leak when the application
+/// is active. Rounds of exactly `nb_accesses` accesses take place every
+/// `period`: requests are buffered until a round serves them, and dummy
+/// accesses fill the rounds when too few requests are buffered. Buffered
+/// requests to the same block are served together by a single access, in
+/// their order of submission.
+///
+/// The scheduler performs no access on its own: the caller runs the rounds,
+/// either waiting for each of them with `run_round`, or from its own event
+/// loop with `run_due_rounds`. Rounds run late are caught up, so that the
+/// number of accesses only depends on the time elapsed.
+pub struct FixedRateScheduler<C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+    oram: Oram,
+    /// Requests not served yet, in their order of submission.
+    pending: Vec<(Ticket, Request)>,
+    /// Results of the requests served which have not been taken yet.
+    results: HashMap<Ticket, Result<Option<Vec<u8>>, Error>>,
+    nb_accesses: usize,
+    period: Duration,
+    next_round: Instant,
+    next_ticket: u64,
+}
+
+impl<C: OramCipher> FixedRateScheduler<C> {
+    /// Schedules the accesses of `client` to `oram`, which it must have set
+    /// up. The first round takes place one `period` from now.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `nb_accesses` or `period` is
+    /// zero.
+    pub fn new(
+        client: ClientOram<C>,
+        oram: Oram,
+        nb_accesses: usize,
+        period: Duration,
+    ) -> Result<FixedRateScheduler<C>, Error> {
+        if nb_accesses == 0 || period.is_zero() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid schedule. Got {} accesses every {:?}, expected \
+                     a positive number of accesses and period",
+                    nb_accesses, period
+                ),
+            ));
+        }
+
+        Ok(FixedRateScheduler {
+            client,
+            oram,
+            pending: Vec::new(),
+            results: HashMap::new(),
+            nb_accesses,
+            period,
+            next_round: Instant::now() + period,
+            next_ticket: 0,
+        })
+    }
+
+    /// Buffers `request` until a round serves it. Writes of payloads too
+    /// large for a block fail right away, without taking an access.
+    pub fn submit(&mut self, request: Request) -> Ticket {
+        let ticket = Ticket(self.next_ticket);
+        self.next_ticket += 1;
+
+        if let Request::Write(block_id, data) = &request {
+            if let Err(e) = self.client.new_payload(
+                AccessType::Write,
+                *block_id,
+                Some(data.clone()),
+            ) {
+                self.results.insert(ticket, Err(e));
+                return ticket;
+            }
+        }
+
+        self.pending.push((ticket, request));
+
+        ticket
+    }
+
+    /// Returns the result of the request `ticket` once served, as
+    /// `ClientOram::access` does, and forgets it.
+    pub fn take_result(
+        &mut self,
+        ticket: Ticket,
+    ) -> Option<Result<Option<Vec<u8>>, Error>> {
+        self.results.remove(&ticket)
+    }
+
+    /// Time at which the next round is due.
+    pub fn next_round(&self) -> Instant {
+        self.next_round
+    }
+
+    /// Number of requests waiting for a round.
+    pub fn nb_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Waits for the next round and runs it.
+    pub fn run_round(&mut self) -> Result<(), Error> {
+        thread::sleep(
+            self.next_round.saturating_duration_since(Instant::now()),
+        );
+
+        self.next_round += self.period;
+        for _ in 0..self.nb_accesses {
+            self.serve_next()?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs the rounds which are due, without waiting. Returns their number.
+    pub fn run_due_rounds(&mut self) -> Result<usize, Error> {
+        let mut nb_rounds = 0;
+        while self.next_round <= Instant::now() {
+            self.run_round()?;
+            nb_rounds += 1;
+        }
+
+        Ok(nb_rounds)
+    }
+
+    /// Serves all the requests to the block of the oldest pending request
+    /// within a single access, or performs a dummy access if none is pending.
+    ///
+    /// Fails if the access fails, the tree being then possibly out of sync
+    /// with the client. The requests served get the error as result.
+    fn serve_next(&mut self) -> Result<(), Error> {
+        let block_id = match self.pending.first() {
+            Some((_, request)) => request.block_id(),
+            None => return self.client.dummy_access(&mut self.oram),
+        };
+
+        let (requests, pending): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending)
+                .into_iter()
+                .partition(|(_, request)| request.block_id() == block_id);
+        self.pending = pending;
+
+        // Results of the requests, applied one after the other.
+        let mut results = Vec::with_capacity(requests.len());
+        let res_update =
+            self.client.update(&mut self.oram, block_id, |payload| {
+                let mut payload = payload.map(<[u8]>::to_vec);
+                for (_, request) in &requests {
+                    results.push(payload.clone());
+                    match request {
+                        Request::Read(_) => {}
+                        Request::Write(_, data) => payload = Some(data.clone()),
+                        Request::Remove(_) => payload = None,
+                    }
+                }
+
+                Ok(payload)
+            });
+
+        match res_update {
+            Ok(_) => {
+                for ((ticket, _), result) in requests.into_iter().zip(results) {
+                    self.results.insert(ticket, Ok(result));
+                }
+
+                Ok(())
+            }
+            Err(e) => {
+                for (ticket, _) in requests {
+                    self.results.insert(
+                        ticket,
+                        Err(Error::new(e.kind(), e.to_string())),
+                    );
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    /// Client and server, to save them between two rounds.
+    pub(crate) fn parts_mut(&mut self) -> (&mut ClientOram<C>, &mut Oram) {
+        (&mut self.client, &mut self.oram)
+    }
+
+    pub fn into_parts(self) -> (ClientOram<C>, Oram) {
+        (self.client, self.oram)
+    }
+}
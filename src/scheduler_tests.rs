@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::ErrorKind,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{
+        client::ClientOram,
+        oram::AccessType,
+        scheduler::{FixedRateScheduler, Request},
+        trace::{MemoryTrace, TraceOp},
+    };
+
+    fn _scheduler(
+        nb_accesses: usize,
+        period: Duration,
+    ) -> (FixedRateScheduler, MemoryTrace) {
+        let mut client = ClientOram::new(64).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+        let scheduler =
+            FixedRateScheduler::new(client, path_oram, nb_accesses, period)
+                .unwrap();
+
+        (scheduler, trace)
+    }
+
+    #[test]
+    fn scheduler_dummy_accesses() {
+        let (mut scheduler, trace) = _scheduler(2, Duration::from_millis(1));
+        for _ in 0..5 {
+            scheduler.run_round().unwrap();
+        }
+
+        // Idle rounds still perform all their accesses.
+        assert_eq!(scheduler.client().nb_accesses(), 10);
+        assert_eq!(trace.len(), 2 * 10);
+        assert!(scheduler.client().position_map.is_empty());
+    }
+
+    #[test]
+    fn scheduler_buffered_requests() {
+        let (mut scheduler, trace) = _scheduler(1, Duration::from_millis(1));
+        let tickets: Vec<_> = (0..3u64)
+            .map(|i| scheduler.submit(Request::Write(i, vec![i as u8; 16])))
+            .collect();
+        assert!(trace.is_empty());
+        assert_eq!(scheduler.nb_pending(), 3);
+
+        // Each round serves a single block.
+        scheduler.run_round().unwrap();
+        assert_eq!(scheduler.take_result(tickets[0]).unwrap().unwrap(), None);
+        assert!(scheduler.take_result(tickets[1]).is_none());
+        assert_eq!(scheduler.nb_pending(), 2);
+
+        scheduler.run_round().unwrap();
+        scheduler.run_round().unwrap();
+        assert_eq!(scheduler.nb_pending(), 0);
+        for &ticket in &tickets[1..] {
+            assert_eq!(scheduler.take_result(ticket).unwrap().unwrap(), None);
+        }
+        assert_eq!(trace.len(), 2 * 3);
+
+        // Results are only returned once.
+        assert!(scheduler.take_result(tickets[0]).is_none());
+
+        let (mut client, mut path_oram) = scheduler.into_parts();
+        assert!(client.verify_invariants(&mut path_oram).is_ok());
+        for block_id in 0..3u64 {
+            let value = client
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8; 16]));
+        }
+    }
+
+    #[test]
+    fn scheduler_deduplication() {
+        let (mut scheduler, trace) = _scheduler(1, Duration::from_millis(1));
+        let requests = vec![
+            Request::Write(5, vec![1; 16]),
+            Request::Read(7),
+            Request::Read(5),
+            Request::Remove(5),
+            Request::Write(5, vec![2; 16]),
+        ];
+        let tickets: Vec<_> = requests
+            .into_iter()
+            .map(|request| scheduler.submit(request))
+            .collect();
+
+        // The requests to block 5 are served by the first access.
+        scheduler.run_round().unwrap();
+        let ops: Vec<_> = trace.events().iter().map(|event| event.op).collect();
+        assert_eq!(ops, vec![TraceOp::ReadPath, TraceOp::WritePath]);
+        assert_eq!(scheduler.nb_pending(), 1);
+
+        let mut result = |i: usize| scheduler.take_result(tickets[i]);
+        assert_eq!(result(0).unwrap().unwrap(), None);
+        assert!(result(1).is_none());
+        assert_eq!(result(2).unwrap().unwrap(), Some(vec![1; 16]));
+        assert_eq!(result(3).unwrap().unwrap(), Some(vec![1; 16]));
+        assert_eq!(result(4).unwrap().unwrap(), None);
+
+        scheduler.run_round().unwrap();
+        assert_eq!(scheduler.take_result(tickets[1]).unwrap().unwrap(), None);
+
+        let ticket = scheduler.submit(Request::Read(5));
+        scheduler.run_round().unwrap();
+        let value = scheduler.take_result(ticket).unwrap().unwrap();
+        assert_eq!(value, Some(vec![2; 16]));
+    }
+
+    #[test]
+    fn scheduler_fixed_rate() {
+        let period = Duration::from_millis(5);
+        let start = Instant::now();
+        let (mut scheduler, trace) = _scheduler(3, period);
+
+        // No round is due before the first period.
+        assert_eq!(scheduler.run_due_rounds().unwrap(), 0);
+        assert!(trace.is_empty());
+
+        for round in 1..=4u32 {
+            scheduler.submit(Request::Read(round as u64));
+            scheduler.run_round().unwrap();
+            assert!(start.elapsed() >= period * round);
+        }
+        assert_eq!(scheduler.client().nb_accesses(), 4 * 3);
+
+        // Rounds run late are caught up.
+        thread::sleep(period * 3);
+        let nb_rounds = scheduler.run_due_rounds().unwrap();
+        assert!(nb_rounds >= 2);
+        assert!(scheduler.next_round() > Instant::now());
+        assert_eq!(
+            scheduler.client().nb_accesses(),
+            (4 + nb_rounds as u64) * 3
+        );
+    }
+
+    #[test]
+    fn scheduler_invalid_requests() {
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let res_scheduler = FixedRateScheduler::new(
+            client,
+            path_oram,
+            0,
+            Duration::from_millis(1),
+        );
+        assert_eq!(
+            res_scheduler.err().unwrap().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        let mut client = ClientOram::new(64).unwrap();
+        let path_oram = client.setup_oram(16).unwrap();
+        let res_scheduler =
+            FixedRateScheduler::new(client, path_oram, 1, Duration::ZERO);
+        assert_eq!(
+            res_scheduler.err().unwrap().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        // Oversized writes fail without taking an access.
+        let (mut scheduler, trace) = _scheduler(1, Duration::from_millis(1));
+        let ticket = scheduler.submit(Request::Write(1, vec![1; 17]));
+        let res_write = scheduler.take_result(ticket).unwrap();
+        assert_eq!(res_write.unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(scheduler.nb_pending(), 0);
+        assert!(trace.is_empty());
+    }
+}
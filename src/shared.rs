@@ -0,0 +1,338 @@
+use crate::{
+    btree::DataItem,
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::{AccessType, Oram},
+    params::OramParams,
+};
+use cosmian_crypto_core::{Aes256Gcm, CryptoCoreError};
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+};
+
+/// Length of the fixed fields of the shared state.
+const HEADER_LENGTH: usize = 6 * 8;
+
+/// Length of an entry of the position map, block id then leaf.
+const ENTRY_LENGTH: usize = 2 * 8;
+
+/// Client state shared on the server by the clients of a tree, besides the
+/// outsourced stash.
+#[derive(Debug, Clone, PartialEq)]
+struct SharedState {
+    nb_items: usize,
+    block_size: usize,
+    failure_probability: f64,
+    stash_capacity: usize,
+    nb_accesses: u64,
+    position_map: HashMap<u64, usize>,
+}
+
+impl SharedState {
+    /// Encodes the state on `HEADER_LENGTH + nb_items * ENTRY_LENGTH` bytes
+    /// whatever the number of blocks, unused entries being zeroed.
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        if self.position_map.len() > self.nb_items {
+            return Err(Error::new(
+                ErrorKind::OutOfMemory,
+                format!(
+                    "Position map overflow. Got {} blocks, capacity is {}",
+                    self.position_map.len(),
+                    self.nb_items
+                ),
+            ));
+        }
+
+        let mut bytes =
+            Vec::with_capacity(HEADER_LENGTH + self.nb_items * ENTRY_LENGTH);
+        for field in [
+            self.nb_items as u64,
+            self.block_size as u64,
+            self.failure_probability.to_bits(),
+            self.stash_capacity as u64,
+            self.nb_accesses,
+            self.position_map.len() as u64,
+        ] {
+            bytes.extend(field.to_le_bytes());
+        }
+
+        for (&block_id, &leaf) in &self.position_map {
+            bytes.extend(block_id.to_le_bytes());
+            bytes.extend((leaf as u64).to_le_bytes());
+        }
+        bytes.resize(HEADER_LENGTH + self.nb_items * ENTRY_LENGTH, 0);
+
+        Ok(bytes)
+    }
+
+    fn decode(bytes: &[u8]) -> Result<SharedState, Error> {
+        let invalid_length = || {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("Invalid shared state of {} bytes", bytes.len()),
+            )
+        };
+
+        if bytes.len() < HEADER_LENGTH {
+            return Err(invalid_length());
+        }
+        let fields: Vec<u64> = bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        let nb_items = fields[0] as usize;
+        let nb_blocks = fields[5] as usize;
+        if nb_blocks > nb_items
+            || bytes.len() != HEADER_LENGTH + nb_items * ENTRY_LENGTH
+        {
+            return Err(invalid_length());
+        }
+
+        let position_map = fields[6..6 + 2 * nb_blocks]
+            .chunks_exact(2)
+            .map(|entry| (entry[0], entry[1] as usize))
+            .collect();
+
+        Ok(SharedState {
+            nb_items,
+            block_size: fields[1] as usize,
+            failure_probability: f64::from_bits(fields[2]),
+            stash_capacity: fields[3] as usize,
+            nb_accesses: fields[4],
+            position_map,
+        })
+    }
+}
+
+/// Client of a tree shared by several clients holding the same key, such as
+/// the processes of a team. The stash is outsourced and the position map is
+/// kept on the server too, encrypted and padded to its capacity, so that
+/// clients only keep their key between accesses.
+///
+/// Each access fetches the whole shared state, accesses the tree as
+/// `ClientOram::access` does, then uploads the new state. The server sees the
+/// same fixed-size transfers whatever the client and the blocks accessed.
+/// Accesses are serialisable as long as each of them holds the server
+/// exclusively, which `&mut Oram` ensures within a process, for instance
+/// behind a `Mutex`. An access failing midway leaves the tree out of sync
+/// with the shared state.
+///
+/// The state being transferred on every access, this suits trees of up to a
+/// few hundred thousand blocks.
+pub struct SharedClientOram<C: OramCipher = Aes256Gcm> {
+    client: ClientOram<C>,
+}
+
+impl<C: OramCipher> SharedClientOram<C> {
+    /// Shares `oram` with other clients, uploading the state of `client`,
+    /// which must have set it up. The key of `client` is needed to `join`.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if the stash of `client` is not
+    /// outsourced, or if it caches the tree top.
+    pub fn share(
+        client: ClientOram<C>,
+        oram: &mut Oram,
+    ) -> Result<SharedClientOram<C>, Error> {
+        if client.outsourced_stash().is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid client. Got a local stash, expected an outsourced one"
+                    .to_string(),
+            ));
+        }
+        if client.tree_top_levels() > 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid client. Got {} cached levels, expected none",
+                    client.tree_top_levels()
+                ),
+            ));
+        }
+
+        let mut shared_client = SharedClientOram { client };
+        shared_client.push_state(oram)?;
+
+        Ok(shared_client)
+    }
+
+    /// Joins the clients of `oram`, which must have been shared with `key`.
+    ///
+    /// Fails with `ErrorKind::NotFound` if the tree is not shared, and with
+    /// `ErrorKind::InvalidData` if its state cannot be authenticated.
+    pub fn join(
+        oram: &mut Oram,
+        key: &[u8],
+    ) -> Result<SharedClientOram<C>, Error> {
+        let state = oram.read_client_state().ok_or_else(not_shared)?;
+
+        let cipher = C::from_key(key)
+            .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?;
+        let state = decode_state(cipher.decrypt(state.data()))?;
+
+        let params = OramParams::with_failure_probability(
+            state.nb_items,
+            state.block_size,
+            state.failure_probability,
+        )
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        let mut client = ClientOram::with_params_and_key(params, key)?;
+        client.outsource_stash(state.stash_capacity);
+        client.set_nb_accesses(state.nb_accesses);
+        client.position_map = state.position_map;
+
+        Ok(SharedClientOram { client })
+    }
+
+    /// Path ORAM access to the block `block_id`, as `ClientOram::access`.
+    ///
+    /// Writing a new block fails with `ErrorKind::OutOfMemory` before any
+    /// access if the position map is full.
+    pub fn access(
+        &mut self,
+        oram: &mut Oram,
+        op: AccessType,
+        block_id: u64,
+        data: Option<Vec<u8>>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.pull_state(oram)?;
+        if let AccessType::Write = op {
+            self.check_capacity(block_id)?;
+        }
+
+        let old_payload = self.client.access(oram, op, block_id, data)?;
+        self.push_state(oram)?;
+
+        Ok(old_payload)
+    }
+
+    /// Replaces the payload of the block `block_id` by the result of `f`
+    /// within a single access, as `ClientOram::update`. Read-modify-write
+    /// sequences of concurrent clients are thus serialised.
+    ///
+    /// Fails with `ErrorKind::OutOfMemory` before any access if the block
+    /// does not exist and the position map is full.
+    pub fn update<F>(
+        &mut self,
+        oram: &mut Oram,
+        block_id: u64,
+        f: F,
+    ) -> Result<Option<Vec<u8>>, Error>
+    where
+        F: FnOnce(Option<&[u8]>) -> Result<Option<Vec<u8>>, Error>,
+    {
+        self.pull_state(oram)?;
+        self.check_capacity(block_id)?;
+
+        let nb_accesses = self.client.nb_accesses();
+        let res_update = self.client.update(oram, block_id, f);
+        // The path is written back even if `f` fails.
+        if self.client.nb_accesses() > nb_accesses {
+            self.push_state(oram)?;
+        }
+
+        res_update
+    }
+
+    /// Removes the block `block_id`, as `ClientOram::remove`.
+    pub fn remove(
+        &mut self,
+        oram: &mut Oram,
+        block_id: u64,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.pull_state(oram)?;
+        let old_payload = self.client.remove(oram, block_id)?;
+        self.push_state(oram)?;
+
+        Ok(old_payload)
+    }
+
+    /// Client as of the last access. Its position map may be outdated by the
+    /// accesses of other clients since.
+    pub fn client(&self) -> &ClientOram<C> {
+        &self.client
+    }
+
+    /// Fetches the state left by the last client to access the tree.
+    fn pull_state(&mut self, oram: &mut Oram) -> Result<(), Error> {
+        let state = oram.read_client_state().ok_or_else(not_shared)?;
+        let state = decode_state(self.client.decrypt_bytes(state.data()))?;
+
+        let params = self.client.params();
+        if state.nb_items != params.nb_items()
+            || state.block_size != params.block_size()
+            || Some(state.stash_capacity) != self.client.outsourced_stash()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Invalid shared state. Got another shape of ORAM, expected \
+                 the one joined"
+                    .to_string(),
+            ));
+        }
+
+        self.client.set_nb_accesses(state.nb_accesses);
+        self.client.position_map = state.position_map;
+
+        Ok(())
+    }
+
+    fn push_state(&mut self, oram: &mut Oram) -> Result<(), Error> {
+        let params = self.client.params();
+        let state = SharedState {
+            nb_items: params.nb_items(),
+            block_size: params.block_size(),
+            failure_probability: params.failure_probability(),
+            stash_capacity: self.client.outsourced_stash().unwrap_or_default(),
+            nb_accesses: self.client.nb_accesses(),
+            position_map: self.client.position_map.clone(),
+        };
+
+        let encrypted_state = self.client.encrypt_bytes(&state.encode()?)?;
+        oram.write_client_state(DataItem::new(encrypted_state));
+
+        Ok(())
+    }
+
+    fn check_capacity(&self, block_id: u64) -> Result<(), Error> {
+        let position_map = &self.client.position_map;
+        if !position_map.contains_key(&block_id)
+            && position_map.len() >= self.client.params().nb_items()
+        {
+            return Err(Error::new(
+                ErrorKind::OutOfMemory,
+                format!(
+                    "Position map overflow. Got {} blocks, capacity is {}",
+                    position_map.len() + 1,
+                    self.client.params().nb_items()
+                ),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes the state decrypted by `res_decrypt`.
+fn decode_state(
+    res_decrypt: Result<Vec<u8>, CryptoCoreError>,
+) -> Result<SharedState, Error> {
+    let bytes = res_decrypt.map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Shared state authentication failed: {}", e),
+        )
+    })?;
+
+    SharedState::decode(&bytes)
+}
+
+fn not_shared() -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        "No shared client state on the server".to_string(),
+    )
+}
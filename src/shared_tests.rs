@@ -0,0 +1,265 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::ErrorKind,
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    use cosmian_crypto_core::{
+        reexport::rand_core::{RngCore, SeedableRng},
+        Aes256Gcm, CsRng,
+    };
+
+    use crate::{
+        client::ClientOram,
+        oram::{AccessType, Oram},
+        shared::SharedClientOram,
+        trace::{MemoryTrace, TraceOp},
+    };
+
+    fn _key() -> Vec<u8> {
+        let mut key = vec![0; 32];
+        CsRng::from_entropy().fill_bytes(&mut key);
+        key
+    }
+
+    /// Tree of 64 blocks of 16 bytes shared under `key` by a first client.
+    fn _shared_oram(key: &[u8]) -> (SharedClientOram, Oram) {
+        let mut client = ClientOram::with_key(64, key).unwrap();
+        client.outsource_stash(64);
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let shared_client =
+            SharedClientOram::share(client, &mut path_oram).unwrap();
+
+        (shared_client, path_oram)
+    }
+
+    #[test]
+    fn shared_access() {
+        let key = _key();
+        let (mut first, mut path_oram) = _shared_oram(&key);
+        let mut second: SharedClientOram =
+            SharedClientOram::join(&mut path_oram, &key).unwrap();
+        let mut third: SharedClientOram =
+            SharedClientOram::join(&mut path_oram, &key).unwrap();
+
+        // Each client sees the blocks written by the others.
+        for block_id in 0..30u64 {
+            let clients = [&mut first, &mut second, &mut third];
+            let value = clients[block_id as usize % 3]
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![block_id as u8; 16]),
+                )
+                .unwrap();
+            assert_eq!(value, None);
+        }
+        for block_id in 0..30u64 {
+            let clients = [&mut first, &mut second, &mut third];
+            let value = clients[(block_id as usize + 1) % 3]
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            assert_eq!(value, Some(vec![block_id as u8; 16]));
+        }
+
+        let removed = second.remove(&mut path_oram, 4).unwrap();
+        assert_eq!(removed, Some(vec![4; 16]));
+        let value = third
+            .access(&mut path_oram, AccessType::Read, 4, None)
+            .unwrap();
+        assert_eq!(value, None);
+
+        // Counters are shared as well.
+        assert_eq!(third.client().nb_accesses(), 62);
+
+        // A client joining later gets the latest state.
+        let fourth: SharedClientOram =
+            SharedClientOram::join(&mut path_oram, &key).unwrap();
+        assert_eq!(fourth.client().position_map.len(), 29);
+        assert!(fourth.client().verify_invariants(&mut path_oram).is_ok());
+    }
+
+    #[test]
+    fn shared_serialisable_updates() {
+        let key = _key();
+        let (_, path_oram) = _shared_oram(&key);
+        let path_oram = Arc::new(Mutex::new(path_oram));
+
+        let threads: Vec<_> = (0..4u64)
+            .map(|i| {
+                let path_oram = Arc::clone(&path_oram);
+                let key = key.clone();
+                thread::spawn(move || {
+                    let mut client: SharedClientOram = SharedClientOram::join(
+                        &mut path_oram.lock().unwrap(),
+                        &key,
+                    )
+                    .unwrap();
+
+                    for j in 0..25u64 {
+                        // Counter shared by all the clients.
+                        client
+                            .update(&mut path_oram.lock().unwrap(), 0, |c| {
+                                let count = c.map_or(0, |c| c[0]);
+                                Ok(Some(vec![count + 1; 16]))
+                            })
+                            .unwrap();
+
+                        let block_id = 1 + 10 * i + j % 10;
+                        client
+                            .access(
+                                &mut path_oram.lock().unwrap(),
+                                AccessType::Write,
+                                block_id,
+                                Some(vec![j as u8; 16]),
+                            )
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // No increment nor write was lost.
+        let mut path_oram = path_oram.lock().unwrap();
+        let mut client: SharedClientOram =
+            SharedClientOram::join(&mut path_oram, &key).unwrap();
+        let counter = client
+            .access(&mut path_oram, AccessType::Read, 0, None)
+            .unwrap();
+        assert_eq!(counter, Some(vec![100; 16]));
+        for block_id in 1..41u64 {
+            let value = client
+                .access(&mut path_oram, AccessType::Read, block_id, None)
+                .unwrap();
+            let k = (block_id - 1) % 10;
+            let last_j = if k < 5 { 20 + k } else { 10 + k };
+            assert_eq!(value, Some(vec![last_j as u8; 16]));
+        }
+        assert!(client.client().verify_invariants(&mut path_oram).is_ok());
+    }
+
+    #[test]
+    fn shared_state_transfers() {
+        let key = _key();
+        let (mut first, mut path_oram) = _shared_oram(&key);
+        let mut second: SharedClientOram =
+            SharedClientOram::join(&mut path_oram, &key).unwrap();
+
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+        for block_id in 0..10u64 {
+            let client = if block_id % 2 == 0 {
+                &mut first
+            } else {
+                &mut second
+            };
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![1; 16]),
+                )
+                .unwrap();
+        }
+        first
+            .access(&mut path_oram, AccessType::Read, 1000, None)
+            .unwrap();
+
+        // Every access transfers the whole state, of the same size.
+        let events = trace.events();
+        assert_eq!(events.len(), 11 * 6);
+        for access in events.chunks(6) {
+            let ops: Vec<_> = access.iter().map(|event| event.op).collect();
+            assert_eq!(
+                ops,
+                vec![
+                    TraceOp::ReadState,
+                    TraceOp::ReadPath,
+                    TraceOp::ReadStash,
+                    TraceOp::WriteStash,
+                    TraceOp::WritePath,
+                    TraceOp::WriteState,
+                ]
+            );
+            for event in [&access[0], &access[5]] {
+                assert_eq!(event.nb_items, 1);
+                assert_eq!(event.nb_bytes, events[0].nb_bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn shared_invalid_clients() {
+        let key = _key();
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(64, &key).unwrap();
+        let mut path_oram = client.setup_oram(16).unwrap();
+        let res_share = SharedClientOram::share(client, &mut path_oram);
+        assert_eq!(res_share.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        let res_join =
+            SharedClientOram::<Aes256Gcm>::join(&mut path_oram, &key);
+        assert_eq!(res_join.err().unwrap().kind(), ErrorKind::NotFound);
+
+        let mut client = ClientOram::<Aes256Gcm>::with_key(64, &key).unwrap();
+        client.outsource_stash(64);
+        let mut path_oram = client.setup_oram(16).unwrap();
+        client.cache_tree_top(&mut path_oram, 2).unwrap();
+        let res_share = SharedClientOram::share(client, &mut path_oram);
+        assert_eq!(res_share.err().unwrap().kind(), ErrorKind::InvalidInput);
+
+        // Only clients holding the key can join.
+        let (_, mut path_oram) = _shared_oram(&key);
+        let res_join =
+            SharedClientOram::<Aes256Gcm>::join(&mut path_oram, &_key());
+        assert_eq!(res_join.err().unwrap().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn shared_position_map_overflow() {
+        let key = _key();
+        let (mut client, mut path_oram) = _shared_oram(&key);
+        for block_id in 0..64u64 {
+            client
+                .access(
+                    &mut path_oram,
+                    AccessType::Write,
+                    block_id,
+                    Some(vec![1; 16]),
+                )
+                .unwrap();
+        }
+
+        // New blocks are refused before any access, existing ones are not.
+        let trace = MemoryTrace::new();
+        path_oram.set_trace_sink(trace.clone());
+        let res_write = client.access(
+            &mut path_oram,
+            AccessType::Write,
+            64,
+            Some(vec![1; 16]),
+        );
+        assert_eq!(res_write.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        let res_update =
+            client.update(&mut path_oram, 65, |_| Ok(Some(vec![1; 16])));
+        assert_eq!(res_update.unwrap_err().kind(), ErrorKind::OutOfMemory);
+        let ops: Vec<_> = trace.events().iter().map(|event| event.op).collect();
+        assert_eq!(ops, vec![TraceOp::ReadState; 2]);
+
+        let value = client
+            .access(&mut path_oram, AccessType::Write, 3, Some(vec![2; 16]))
+            .unwrap();
+        assert_eq!(value, Some(vec![1; 16]));
+        let value = client
+            .access(&mut path_oram, AccessType::Read, 64, None)
+            .unwrap();
+        assert_eq!(value, None);
+    }
+}
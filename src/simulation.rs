@@ -0,0 +1,250 @@
+use crate::{
+    cipher::OramCipher,
+    client::ClientOram,
+    oram::{AccessType, Oram},
+    params::tree_height,
+};
+use rand::{Rng, RngCore};
+use std::{
+    fmt::Write,
+    io::{Error, ErrorKind},
+};
+
+/// Distribution of the stash size measured after each access of a simulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StashStats {
+    pub bucket_size: usize,
+    pub nb_items: usize,
+    pub nb_accesses: u64,
+    /// Number of accesses after which the stash held `i` blocks, at index `i`.
+    pub histogram: Vec<u64>,
+}
+
+impl StashStats {
+    fn new(bucket_size: usize, nb_items: usize) -> StashStats {
+        StashStats {
+            bucket_size,
+            nb_items,
+            nb_accesses: 0,
+            histogram: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, stash_size: usize) {
+        if self.histogram.len() <= stash_size {
+            self.histogram.resize(stash_size + 1, 0);
+        }
+        self.histogram[stash_size] += 1;
+        self.nb_accesses += 1;
+    }
+
+    /// Largest stash observed.
+    pub fn max_stash(&self) -> usize {
+        self.histogram.len().saturating_sub(1)
+    }
+
+    pub fn mean_stash(&self) -> f64 {
+        if self.nb_accesses == 0 {
+            return 0.0;
+        }
+
+        let total: u64 = self
+            .histogram
+            .iter()
+            .enumerate()
+            .map(|(stash_size, count)| stash_size as u64 * count)
+            .sum();
+
+        total as f64 / self.nb_accesses as f64
+    }
+
+    /// Fraction of the accesses after which the stash held more than `bound`
+    /// blocks.
+    pub fn overflow_probability(&self, bound: usize) -> f64 {
+        if self.nb_accesses == 0 {
+            return 0.0;
+        }
+
+        let nb_overflows: u64 = self.histogram.iter().skip(bound + 1).sum();
+
+        nb_overflows as f64 / self.nb_accesses as f64
+    }
+
+    /// Bound `14 * 0.6002^R` on the probability that the stash holds more
+    /// than `R` blocks, proven in `https://eprint.iacr.org/2013/280` for
+    /// buckets of 5 items at least.
+    pub fn theoretical_bound(bound: usize) -> f64 {
+        (14.0 * 0.6002_f64.powi(bound as i32)).min(1.0)
+    }
+
+    /// One line per stash size up to the largest observed, with the number of
+    /// accesses ending with that size, the empirical probability to exceed
+    /// it and the theoretical bound on that probability. Starts with a header
+    /// unless `header` is false, so that simulations can be concatenated.
+    pub fn to_csv(&self, header: bool) -> String {
+        let mut csv = String::new();
+        if header {
+            csv.push_str(
+                "bucket_size,nb_items,nb_accesses,stash_size,count,\
+                 overflow_probability,theoretical_bound\n",
+            );
+        }
+
+        for (stash_size, count) in self.histogram.iter().enumerate() {
+            // Writing to a `String` cannot fail.
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{:e},{:e}",
+                self.bucket_size,
+                self.nb_items,
+                self.nb_accesses,
+                stash_size,
+                count,
+                self.overflow_probability(stash_size),
+                StashStats::theoretical_bound(stash_size),
+            );
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let join = |values: Vec<String>| values.join(",");
+
+        format!(
+            "{{\"bucket_size\":{},\"nb_items\":{},\"nb_accesses\":{},\
+             \"max_stash\":{},\"mean_stash\":{:e},\"histogram\":[{}],\
+             \"overflow_probability\":[{}],\"theoretical_bound\":[{}]}}",
+            self.bucket_size,
+            self.nb_items,
+            self.nb_accesses,
+            self.max_stash(),
+            self.mean_stash(),
+            join(self.histogram.iter().map(u64::to_string).collect()),
+            join(
+                (0..self.histogram.len())
+                    .map(|i| format!("{:e}", self.overflow_probability(i)))
+                    .collect()
+            ),
+            join(
+                (0..self.histogram.len())
+                    .map(|i| format!("{:e}", StashStats::theoretical_bound(i)))
+                    .collect()
+            ),
+        )
+    }
+}
+
+/// Simulates `nb_accesses` Path ORAM accesses to uniformly random blocks
+/// among `nb_items`, with buckets of `bucket_size` blocks, and measures the
+/// stash size after each of them.
+///
+/// Only the leaves of the blocks are simulated, without any payload,
+/// encryption or server, which allows millions of accesses for any bucket
+/// size. The tree is shaped as the one of a `ClientOram`, with one leaf per
+/// bucket of items rounded down to a power of two. Every block is first
+/// accessed once, without being measured, to reach a steady state.
+pub fn simulate<R: RngCore>(
+    bucket_size: usize,
+    nb_items: usize,
+    nb_accesses: u64,
+    rng: &mut R,
+) -> Result<StashStats, Error> {
+    if bucket_size == 0 || nb_items == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Bucket size and number of items shall not be null".to_string(),
+        ));
+    }
+
+    /*
+     * Buckets are indexed as a binary heap: the root is 1 and the children
+     * of bucket i are 2i and 2i + 1. The leaf bucket of path p is then
+     * nb_leaves + p.
+     */
+    let height = tree_height(nb_items, bucket_size);
+    let nb_leaves = 1 << (height - 1);
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); 2 * nb_leaves];
+    let mut position_map: Vec<usize> =
+        (0..nb_items).map(|_| rng.gen_range(0..nb_leaves)).collect();
+    let mut stash: Vec<usize> = (0..nb_items).collect();
+
+    let mut stats = StashStats::new(bucket_size, nb_items);
+    for access in 0..nb_items as u64 + nb_accesses {
+        let block = match access < nb_items as u64 {
+            true => access as usize,
+            false => rng.gen_range(0..nb_items),
+        };
+
+        let leaf = position_map[block];
+        let mut bucket = nb_leaves + leaf;
+        while bucket > 0 {
+            stash.append(&mut buckets[bucket]);
+            bucket /= 2;
+        }
+
+        position_map[block] = rng.gen_range(0..nb_leaves);
+
+        // Greedy eviction from the leaf up to the root.
+        for level in 0..height {
+            let bucket = (nb_leaves + leaf) >> level;
+            let mut i = 0;
+            while i < stash.len() && buckets[bucket].len() < bucket_size {
+                if (nb_leaves + position_map[stash[i]]) >> level == bucket {
+                    buckets[bucket].push(stash.swap_remove(i));
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        if access >= nb_items as u64 {
+            stats.record(stash.len());
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Performs `nb_accesses` reads of uniformly random blocks among the
+/// `nb_blocks` first ones through `client` and measures the stash size after
+/// each of them. Blocks are first written once, without being measured.
+///
+/// Unlike `simulate`, this runs the actual client and server, with buckets of
+/// `BUCKET_SIZE` items. Using the `PlaintextCipher` skips encryption to speed
+/// the simulation up. The stash must be kept by the client.
+pub fn simulate_client<C: OramCipher, R: RngCore>(
+    client: &mut ClientOram<C>,
+    oram: &mut Oram,
+    nb_blocks: u64,
+    nb_accesses: u64,
+    rng: &mut R,
+) -> Result<StashStats, Error> {
+    if nb_blocks == 0 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Number of blocks shall not be null".to_string(),
+        ));
+    }
+
+    let params = *client.params();
+    let block_size = params.block_size();
+    for block_id in 0..nb_blocks {
+        client.access(
+            oram,
+            AccessType::Write,
+            block_id,
+            Some(vec![0; block_size]),
+        )?;
+    }
+
+    let mut stats = StashStats::new(params.bucket_size(), params.nb_items());
+    for _ in 0..nb_accesses {
+        let block_id = rng.gen_range(0..nb_blocks);
+        client.access(oram, AccessType::Read, block_id, None)?;
+
+        stats.record(client.stash.len());
+    }
+
+    Ok(stats)
+}
@@ -0,0 +1,261 @@
+use crate::btree::DataItem;
+use std::{
+    fmt,
+    io::{Error, Write},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Kind of request served by the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceOp {
+    ReadPath,
+    WritePath,
+    /// Buckets of several paths read at once.
+    ReadBuckets,
+    WriteBuckets,
+    ReadStash,
+    WriteStash,
+    /// State shared by the clients of the tree.
+    ReadState,
+    WriteState,
+    Grow,
+    Shrink,
+}
+
+impl fmt::Display for TraceOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TraceOp::ReadPath => "read_path",
+            TraceOp::WritePath => "write_path",
+            TraceOp::ReadBuckets => "read_buckets",
+            TraceOp::WriteBuckets => "write_buckets",
+            TraceOp::ReadStash => "read_stash",
+            TraceOp::WriteStash => "write_stash",
+            TraceOp::ReadState => "read_state",
+            TraceOp::WriteState => "write_state",
+            TraceOp::Grow => "grow",
+            TraceOp::Shrink => "shrink",
+        };
+
+        f.write_str(name)
+    }
+}
+
+/// Request served by the server, as observed by the server itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub op: TraceOp,
+    /// Path accessed, for single path reads and writes.
+    pub path: Option<usize>,
+    /// Buckets accessed in increasing order, hence from the root down to the
+    /// leaf for a path, indexed as a binary heap: the root is 1 and the
+    /// children of bucket `i` are `2i` and `2i + 1`.
+    pub buckets: Vec<usize>,
+    /// Number of items sent or received.
+    pub nb_items: usize,
+    /// Total size of those items.
+    pub nb_bytes: usize,
+    /// Time elapsed between the start of the recording and the request.
+    pub timestamp: Duration,
+}
+
+impl TraceEvent {
+    /// Header of the CSV lines produced by `to_csv`.
+    pub const CSV_HEADER: &'static str =
+        "timestamp_ns,op,path,buckets,nb_items,nb_bytes";
+
+    /// One CSV line, without line break. Buckets are separated by spaces.
+    pub fn to_csv(&self) -> String {
+        let buckets: Vec<String> =
+            self.buckets.iter().map(usize::to_string).collect();
+
+        format!(
+            "{},{},{},{},{},{}",
+            self.timestamp.as_nanos(),
+            self.op,
+            self.path.map(|path| path.to_string()).unwrap_or_default(),
+            buckets.join(" "),
+            self.nb_items,
+            self.nb_bytes,
+        )
+    }
+}
+
+/// Destination of the events recorded by the server.
+pub trait TraceSink: Send {
+    fn record(&mut self, event: TraceEvent);
+}
+
+/// Sink keeping the events in memory. Clones share the same events, so that
+/// a clone kept aside can dump the trace of the sink given to the server.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTrace {
+    events: Arc<Mutex<Vec<TraceEvent>>>,
+}
+
+impl MemoryTrace {
+    pub fn new() -> MemoryTrace {
+        MemoryTrace::default()
+    }
+
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.lock().clone()
+    }
+
+    /// Returns the events recorded so far and forgets them.
+    pub fn take(&self) -> Vec<TraceEvent> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Writes the events as CSV, header included, to `writer`.
+    pub fn dump_csv<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        writeln!(writer, "{}", TraceEvent::CSV_HEADER)?;
+        for event in self.lock().iter() {
+            writeln!(writer, "{}", event.to_csv())?;
+        }
+
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<TraceEvent>> {
+        // A panic while recording cannot leave the events inconsistent.
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl TraceSink for MemoryTrace {
+    fn record(&mut self, event: TraceEvent) {
+        self.lock().push(event);
+    }
+}
+
+/// Sink streaming the events as CSV lines to a writer, such as a file.
+///
+/// Write errors cannot be reported to the server, which keeps serving
+/// requests. The first one is kept and returned by `finish`, later events
+/// being dropped.
+pub struct CsvTrace<W: Write + Send> {
+    writer: W,
+    error: Option<Error>,
+}
+
+impl<W: Write + Send> CsvTrace<W> {
+    /// Writes the CSV header to `writer`.
+    pub fn new(mut writer: W) -> Result<CsvTrace<W>, Error> {
+        writeln!(writer, "{}", TraceEvent::CSV_HEADER)?;
+
+        Ok(CsvTrace {
+            writer,
+            error: None,
+        })
+    }
+
+    /// Flushes the writer and returns it, or the first write error.
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write + Send> TraceSink for CsvTrace<W> {
+    fn record(&mut self, event: TraceEvent) {
+        if self.error.is_none() {
+            if let Err(error) = writeln!(self.writer, "{}", event.to_csv()) {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+/// Sink of a server along with the start of the recording.
+pub(crate) struct Tracer {
+    sink: Box<dyn TraceSink>,
+    start: Instant,
+}
+
+impl Tracer {
+    pub(crate) fn new(sink: Box<dyn TraceSink>) -> Tracer {
+        Tracer {
+            sink,
+            start: Instant::now(),
+        }
+    }
+
+    pub(crate) fn into_sink(self) -> Box<dyn TraceSink> {
+        self.sink
+    }
+
+    /// Records a request of kind `op` on `path`, if any, of a tree of
+    /// `height` levels, which transferred `items`.
+    pub(crate) fn record<'a, I>(
+        &mut self,
+        op: TraceOp,
+        path: Option<(usize, u16)>,
+        items: I,
+    ) where
+        I: IntoIterator<Item = &'a DataItem>,
+    {
+        let buckets = match path {
+            Some((path, height)) => (0..height)
+                .map(|level| (1 << level) | (path >> (height - 1 - level)))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        self.record_event(op, path.map(|(path, _)| path), buckets, items);
+    }
+
+    /// Records a request of kind `op` on `buckets`, which transferred
+    /// `items`.
+    pub(crate) fn record_buckets<'a, I>(
+        &mut self,
+        op: TraceOp,
+        buckets: Vec<usize>,
+        items: I,
+    ) where
+        I: IntoIterator<Item = &'a DataItem>,
+    {
+        self.record_event(op, None, buckets, items);
+    }
+
+    fn record_event<'a, I>(
+        &mut self,
+        op: TraceOp,
+        path: Option<usize>,
+        buckets: Vec<usize>,
+        items: I,
+    ) where
+        I: IntoIterator<Item = &'a DataItem>,
+    {
+        let timestamp = self.start.elapsed();
+
+        let mut nb_items = 0;
+        let mut nb_bytes = 0;
+        for item in items {
+            nb_items += 1;
+            nb_bytes += item.data().len();
+        }
+
+        self.sink.record(TraceEvent {
+            op,
+            path,
+            buckets,
+            nb_items,
+            nb_bytes,
+            timestamp,
+        });
+    }
+}